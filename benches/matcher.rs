@@ -0,0 +1,92 @@
+//! Measures the matcher and the filter pipeline on synthetic datasets.
+//! Run with `cargo bench`, or `cargo bench -- matcher/sh01comp` for some of them.
+
+use std::hint::black_box;
+
+use cgs_go::{
+    backend::{Item, ItemKind, Status},
+    matcher::FuzzyMatcher,
+    query::Query,
+    sort::SortMode,
+};
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+
+const SIZES: [usize; 3] = [1_000, 100_000, 1_000_000];
+/// The big datasets take a while per run, the default of 100 samples is too many
+const SAMPLES: usize = 10;
+const QUERIES: [&str; 3] = ["s", "sh01comp", "seq_042/sh0100"];
+
+/// Names shaped like a studio hierarchy, e.g. `seq_042/sh0100_comp_v003`
+fn dataset(size: usize) -> Vec<Item> {
+    const ARTISTS: [&str; 3] = ["anna", "bob", "carla"];
+    (0..size)
+        .map(|i| {
+            let name = format!(
+                "seq_{:03}/sh{:04}_comp_v{:03}",
+                i / 1000,
+                i % 1000 * 10,
+                i % 17
+            );
+            Item::new(name, ItemKind::Shot)
+                .with_status(Status::ALL[i % Status::ALL.len()])
+                .with_metadata("artist", ARTISTS[i % ARTISTS.len()])
+        })
+        .collect()
+}
+
+/// Just the fuzzy scoring and ranking
+fn bench_matcher(c: &mut Criterion) {
+    let mut group = c.benchmark_group("matcher");
+    group.sample_size(SAMPLES);
+    for size in SIZES {
+        let items = dataset(size);
+        group.throughput(Throughput::Elements(size as u64));
+        let mut matcher = FuzzyMatcher::default();
+        for query in QUERIES {
+            group.bench_with_input(BenchmarkId::new(query, size), &items, |b, items| {
+                b.iter(|| {
+                    black_box(matcher.match_names(query, items.iter().map(|i| i.name.as_str())))
+                })
+            });
+        }
+        group.bench_with_input(BenchmarkId::new("top 100", size), &items, |b, items| {
+            b.iter(|| {
+                let names = items.iter().map(|i| i.name.as_str());
+                black_box(matcher.match_top("sh01comp", names, Some(100)))
+            })
+        });
+    }
+    group.finish();
+}
+
+/// What happens on each keystroke: parse, narrow down by facets, match and sort
+fn bench_pipeline(c: &mut Criterion) {
+    let mut group = c.benchmark_group("pipeline");
+    group.sample_size(SAMPLES);
+    for size in SIZES {
+        let items = dataset(size);
+        let mut matcher = FuzzyMatcher::default();
+        for input in ["sh01comp", "status:approved artist:anna sh01comp"] {
+            group.bench_with_input(BenchmarkId::new(input, size), &items, |b, items| {
+                b.iter(|| {
+                    let query = Query::parse(input);
+                    let candidates: Vec<usize> = (0..items.len())
+                        .filter(|i| query.matches_facets(&items[*i]))
+                        .collect();
+                    let names = candidates.iter().map(|i| items[*i].name.as_str());
+                    let mut matches: Vec<usize> = matcher
+                        .match_names(&query.text, names)
+                        .into_iter()
+                        .map(|(index, _score)| candidates[index])
+                        .collect();
+                    SortMode::Alphabetical.sort(&mut matches, items);
+                    black_box(matches)
+                })
+            });
+        }
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_matcher, bench_pipeline);
+criterion_main!(benches);
@@ -0,0 +1,173 @@
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+/// Everything the user can ask the app to do, whatever key or terminal it came from.
+/// Key presses are turned into actions by the [`keymap`](crate::keymap).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Action {
+    Quit,
+    /// Go up a level, or cancel what's in progress
+    Back,
+    /// Descend into the highlighted item, or select it if it's a leaf
+    Accept,
+    MoveUp,
+    MoveDown,
+    /// Scroll the highlighted name horizontally, or collapse and expand its
+    /// group when results are grouped
+    ScrollLeft,
+    ScrollRight,
+    ScrollPreviewUp,
+    ScrollPreviewDown,
+    /// Switch to the next or previous choice, e.g. the buttons of a dialog
+    FocusNext,
+    FocusPrevious,
+    /// Type a character in the query
+    Insert(char),
+    /// Delete the last character of the query
+    DeleteChar,
+    ClearQuery,
+    Undo,
+    Redo,
+    CycleStatusFilter,
+    CycleSort,
+    TogglePin,
+    /// Mark or unmark the highlighted item, in multi-select mode
+    ToggleMark,
+    /// Mark every item matching the query
+    MarkAll,
+    /// Unmark everything, whatever level it was marked at
+    UnmarkAll,
+    /// Mark the items matching the query that aren't, and unmark the others
+    InvertMarks,
+    /// Show or hide the list of everything marked, to unmark some
+    ToggleMarksPanel,
+    ToggleArchived,
+    ToggleMatchMode,
+    GoToTop,
+    /// Show hints on the visible results, to highlight one by typing its hint
+    Jump,
+    /// Show or hide the preview pane
+    TogglePreview,
+    /// Open where the highlighted item lives in `$EDITOR`, or the file manager
+    OpenExternally,
+    /// Show what can be done to the highlighted item
+    OpenItemMenu,
+    /// Show everything known about the highlighted item
+    ShowDetails,
+    /// Write the results matching the query to a CSV file
+    ExportCsv,
+    /// Stop capturing the mouse until the next key press, so text can be
+    /// selected and copied the way the terminal does it
+    SelectText,
+    /// Start recording the inputs that follow, or stop and keep them as the macro
+    RecordMacro,
+    /// Handle the inputs of the last macro recorded again
+    ReplayMacro,
+    /// Fetch the items of the current level again
+    Reload,
+    /// Apply the config file again, e.g. after editing the theme
+    ReloadConfig,
+    OpenPalette,
+    /// List every selection accepted, to accept one of them again
+    ShowHistory,
+    /// Show or hide frame timings and counts
+    ToggleDebug,
+    NextTab,
+    PreviousTab,
+    /// Switch to the tab at this index
+    SelectTab(usize),
+}
+
+/// An input the app reacts to, independent of the terminal library
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum InputEvent {
+    Action(Action),
+    /// Text pasted in one go, see `App::handle_paste`
+    Paste(String),
+}
+
+impl Action {
+    /// The actions that can be bound to keys by name, e.g. in the config file.
+    /// `Insert` isn't one, and `SelectTab` is named `select-tab-1`, `select-tab-2`... instead.
+    pub const NAMED: [(&'static str, Action); 42] = [
+        ("quit", Action::Quit),
+        ("back", Action::Back),
+        ("accept", Action::Accept),
+        ("move-up", Action::MoveUp),
+        ("move-down", Action::MoveDown),
+        ("scroll-left", Action::ScrollLeft),
+        ("scroll-right", Action::ScrollRight),
+        ("scroll-preview-up", Action::ScrollPreviewUp),
+        ("scroll-preview-down", Action::ScrollPreviewDown),
+        ("focus-next", Action::FocusNext),
+        ("focus-previous", Action::FocusPrevious),
+        ("delete-char", Action::DeleteChar),
+        ("clear-query", Action::ClearQuery),
+        ("undo", Action::Undo),
+        ("redo", Action::Redo),
+        ("cycle-status-filter", Action::CycleStatusFilter),
+        ("cycle-sort", Action::CycleSort),
+        ("toggle-pin", Action::TogglePin),
+        ("toggle-mark", Action::ToggleMark),
+        ("mark-all", Action::MarkAll),
+        ("unmark-all", Action::UnmarkAll),
+        ("invert-marks", Action::InvertMarks),
+        ("toggle-marks-panel", Action::ToggleMarksPanel),
+        ("toggle-archived", Action::ToggleArchived),
+        ("toggle-match-mode", Action::ToggleMatchMode),
+        ("go-to-top", Action::GoToTop),
+        ("jump", Action::Jump),
+        ("toggle-preview", Action::TogglePreview),
+        ("open", Action::OpenExternally),
+        ("open-item-menu", Action::OpenItemMenu),
+        ("show-details", Action::ShowDetails),
+        ("export-csv", Action::ExportCsv),
+        ("select-text", Action::SelectText),
+        ("record-macro", Action::RecordMacro),
+        ("replay-macro", Action::ReplayMacro),
+        ("reload", Action::Reload),
+        ("reload-config", Action::ReloadConfig),
+        ("open-palette", Action::OpenPalette),
+        ("show-history", Action::ShowHistory),
+        ("toggle-debug", Action::ToggleDebug),
+        ("next-tab", Action::NextTab),
+        ("previous-tab", Action::PreviousTab),
+    ];
+}
+
+impl FromStr for Action {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        if let Some((_, action)) = Action::NAMED.iter().find(|(known, _)| *known == name) {
+            return Ok(*action);
+        }
+        if let Some(number) = name.strip_prefix("select-tab-") {
+            if let Ok(number @ 1..) = number.parse::<usize>() {
+                return Ok(Action::SelectTab(number - 1));
+            }
+        }
+
+        let known: Vec<&str> = Action::NAMED.iter().map(|(name, _)| *name).collect();
+        Err(format!(
+            "unknown action {name:?}, expected one of {}, select-tab-N",
+            known.join(", ")
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_action_from_name() {
+        assert_eq!("reload".parse(), Ok(Action::Reload));
+        assert_eq!("select-tab-3".parse(), Ok(Action::SelectTab(2)));
+        assert!("select-tab-0".parse::<Action>().is_err());
+        let error = "explode".parse::<Action>().unwrap_err();
+        assert!(error.starts_with("unknown action \"explode\", expected one of quit, back"));
+    }
+}
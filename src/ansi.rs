@@ -0,0 +1,180 @@
+use std::ops::Range;
+
+use ratatui::{
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+};
+
+/// Text with its ANSI SGR escape codes taken out and turned into styles
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AnsiText {
+    pub text: String,
+    /// Byte ranges of `text` and the style they were drawn with
+    pub styles: Vec<(Range<usize>, Style)>,
+}
+
+impl AnsiText {
+    /// A line drawing the text with its styles
+    pub fn to_line(&self) -> Line<'_> {
+        let mut spans = Vec::new();
+        let mut position = 0;
+        for (range, style) in &self.styles {
+            if range.start > position {
+                spans.push(Span::raw(&self.text[position..range.start]));
+            }
+            spans.push(Span::styled(&self.text[range.clone()], *style));
+            position = range.end;
+        }
+        if position < self.text.len() {
+            spans.push(Span::raw(&self.text[position..]));
+        }
+
+        Line::from(spans)
+    }
+}
+
+/// Parses `input`, e.g. the colored output of `rg` or `ls --color`.
+/// Escape sequences other than SGR (cursor movement, titles...) are dropped.
+pub fn parse(input: &str) -> AnsiText {
+    let mut parsed = AnsiText::default();
+    let mut style = Style::new();
+    let mut chars = input.char_indices().peekable();
+
+    while let Some((_, c)) = chars.next() {
+        if c != '\x1b' {
+            let start = parsed.text.len();
+            parsed.text.push(c);
+            push_style(&mut parsed.styles, start..parsed.text.len(), style);
+            continue;
+        }
+
+        match chars.next() {
+            // CSI: parameters, then a final byte in @..~
+            Some((_, '[')) => {
+                let mut params = String::new();
+                for (_, c) in chars.by_ref() {
+                    if ('@'..='~').contains(&c) {
+                        if c == 'm' {
+                            style = apply_sgr(style, &params);
+                        }
+                        break;
+                    }
+                    params.push(c);
+                }
+            }
+            // OSC: runs until BEL or ST (ESC \)
+            Some((_, ']')) => {
+                while let Some((_, c)) = chars.next() {
+                    if c == '\x07' {
+                        break;
+                    }
+                    if c == '\x1b' {
+                        chars.next_if(|(_, c)| *c == '\\');
+                        break;
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    parsed
+}
+
+/// Extends the last styled range when the style didn't change
+fn push_style(styles: &mut Vec<(Range<usize>, Style)>, range: Range<usize>, style: Style) {
+    if style == Style::new() {
+        return;
+    }
+    match styles.last_mut() {
+        Some((last, last_style)) if last.end == range.start && *last_style == style => {
+            last.end = range.end;
+        }
+        _ => styles.push((range, style)),
+    }
+}
+
+fn apply_sgr(mut style: Style, params: &str) -> Style {
+    let mut codes = params
+        .split(';')
+        .map(|code| code.parse::<u8>().unwrap_or(0));
+
+    while let Some(code) = codes.next() {
+        style = match code {
+            0 => Style::new(),
+            1 => style.add_modifier(Modifier::BOLD),
+            2 => style.add_modifier(Modifier::DIM),
+            3 => style.add_modifier(Modifier::ITALIC),
+            4 => style.add_modifier(Modifier::UNDERLINED),
+            5 => style.add_modifier(Modifier::SLOW_BLINK),
+            7 => style.add_modifier(Modifier::REVERSED),
+            9 => style.add_modifier(Modifier::CROSSED_OUT),
+            22 => style.remove_modifier(Modifier::BOLD | Modifier::DIM),
+            23 => style.remove_modifier(Modifier::ITALIC),
+            24 => style.remove_modifier(Modifier::UNDERLINED),
+            25 => style.remove_modifier(Modifier::SLOW_BLINK),
+            27 => style.remove_modifier(Modifier::REVERSED),
+            29 => style.remove_modifier(Modifier::CROSSED_OUT),
+            30..=37 => style.fg(Color::Indexed(code - 30)),
+            38 => match extended_color(&mut codes) {
+                Some(color) => style.fg(color),
+                None => style,
+            },
+            39 => style.fg(Color::Reset),
+            40..=47 => style.bg(Color::Indexed(code - 40)),
+            48 => match extended_color(&mut codes) {
+                Some(color) => style.bg(color),
+                None => style,
+            },
+            49 => style.bg(Color::Reset),
+            90..=97 => style.fg(Color::Indexed(code - 90 + 8)),
+            100..=107 => style.bg(Color::Indexed(code - 100 + 8)),
+            _ => style,
+        };
+    }
+
+    style
+}
+
+/// Reads the rest of a `38;5;n` or `38;2;r;g;b` sequence
+fn extended_color(codes: &mut impl Iterator<Item = u8>) -> Option<Color> {
+    match codes.next()? {
+        5 => Some(Color::Indexed(codes.next()?)),
+        2 => Some(Color::Rgb(codes.next()?, codes.next()?, codes.next()?)),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_parse_sgr() {
+        let parsed = parse("\x1b[1;31msrc\x1b[0m/\x1b[38;5;208mmain.rs\x1b[m:12");
+        assert_eq!(parsed.text, "src/main.rs:12");
+        assert_eq!(
+            parsed.styles,
+            vec![
+                (
+                    0..3,
+                    Style::new()
+                        .fg(Color::Indexed(1))
+                        .add_modifier(Modifier::BOLD)
+                ),
+                (4..11, Style::new().fg(Color::Indexed(208))),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_strip_other_sequences() {
+        let parsed = parse("\x1b]0;title\x07a\x1b[2Kb\x1b[38;2;1;2;3mc");
+        assert_eq!(parsed.text, "abc");
+        assert_eq!(
+            parsed.styles,
+            vec![(2..3, Style::new().fg(Color::Rgb(1, 2, 3)))]
+        );
+    }
+}
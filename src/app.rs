@@ -1,153 +1,5198 @@
-use color_eyre::eyre::WrapErr;
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    path::{Path, PathBuf},
+    process,
+    time::{Duration, Instant, SystemTime},
+};
+
+use color_eyre::eyre::{eyre, WrapErr};
+use serde::Serialize;
 
-use crate::tui;
+use crate::{
+    action::{Action, InputEvent},
+    backend::{validate_name, Backend, Item, ItemKind, Scan, Status},
+    config::ConfigSource,
+    debug::{DebugOverlay, DebugStats},
+    details::{self, DetailsPopup},
+    dialog::ConfirmDialog,
+    export,
+    fields::FieldSelector,
+    graphics::GraphicsProtocol,
+    history::{self, BrowserOutcome, History, HistoryBrowser},
+    item_menu::{ItemMenu, MenuEntry, MenuOutcome},
+    jump::{GoTo, Jump, JumpOutcome},
+    keymap::Keymap,
+    levels::LevelState,
+    macros::MacroRecorder,
+    marks::{Marks, MarksPanel, PanelOutcome},
+    matcher::{self, Bonuses, FuzzyMatcher, MatchMode},
+    opener,
+    palette::{CommandPalette, PaletteOutcome},
+    pins::Pins,
+    preview::{self, Previewer},
+    query::Query,
+    rate_limit::TokenBucket,
+    repeat::Acceleration,
+    retry::Backoff,
+    sort::{SortMode, StartAt},
+    tabs::{Tab, TabState},
+    text,
+    theme::Theme,
+    transcript::{Entry, Recorder, Replay, ReplayStep, Snapshot},
+    tui,
+    undo::UndoStack,
+    watch::{DirWatcher, FileWatcher},
+};
 use ratatui::{
+    backend::{Backend as TerminalBackend, TestBackend},
     buffer::Buffer,
-    layout::Rect,
+    layout::{Constraint, Layout, Rect},
     prelude::{Alignment, Stylize},
-    symbols::border,
-    terminal::Frame,
-    text::{Line, Text},
+    style::Style,
+    symbols::{self, border},
+    terminal::{Frame, Terminal},
+    text::{Line, Span},
     widgets::{
         block::{Position, Title},
-        Block, Borders, Paragraph, Widget,
+        Block, Borders, Clear, Gauge, List, ListItem, ListState, Paragraph, StatefulWidget, Tabs,
+        Widget,
     },
 };
 
-use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind};
+use crossterm::event::{self, KeyCode, KeyEvent};
+
+/// Knobs that change how the app behaves, usually coming from the command line
+#[derive(Debug, Clone)]
+pub struct Options {
+    /// Whether nerd-font icons can be drawn, or only ASCII
+    pub unicode: bool,
+    /// Appended to names too long to fit in their row
+    pub ellipsis: String,
+    /// Drawn in the gutter of the highlighted row
+    pub pointer: String,
+    /// Drawn in the gutter of marked rows
+    pub marker: String,
+    /// Whether accented characters match their unaccented version
+    pub normalize: bool,
+    /// Whether names one typo away from the query match too, after the others
+    pub typos: bool,
+    /// How much matching at the start of words adds to scores
+    pub bonuses: Bonuses,
+    /// Restricts matching to some fields of each item, like `fzf --nth`
+    pub fields: Option<FieldSelector>,
+    /// Whether the query starts out as a fuzzy query or a regex
+    pub match_mode: MatchMode,
+    /// How results are ordered when the app starts
+    pub sort: SortMode,
+    /// Where the highlight goes when the results are ranked again
+    pub start_at: StartAt,
+    /// Whether archived items are listed when the app starts
+    pub show_archived: bool,
+    /// Where pinned items are persisted. When `None`, pins are not saved.
+    pub pins_file: Option<PathBuf>,
+    /// Where accepted selections are persisted. When `None`, they are not saved.
+    pub history_file: Option<PathBuf>,
+    /// Where the results are exported to as CSV, see `--export-csv`
+    pub export_file: PathBuf,
+    /// The hierarchy path to start from, e.g. when resuming the last session
+    pub start_path: Vec<String>,
+    /// What the query starts out as
+    pub query: String,
+    /// Command whose output is shown next to the results, like `fzf --preview`
+    pub preview: Option<String>,
+    /// Whether to show the thumbnail of the highlighted item, in the preview pane
+    pub thumbnails: bool,
+    /// Whether to show where the highlighted item links to, in the preview pane
+    pub link_targets: bool,
+    /// Whether to show how long matching and fetching took in the status bar
+    pub show_timings: bool,
+    /// How the terminal can draw thumbnails
+    pub graphics: GraphicsProtocol,
+    /// Whether the results are split into tabs by entity type
+    pub tabs: bool,
+    /// Whether results of different kinds are shown in groups, under a header
+    pub group: bool,
+    /// How many results stay visible above and below the highlighted one
+    pub scroll_off: usize,
+    /// How moving up and down speeds up while the key is held. Without it,
+    /// e.g. when scripted, every move goes one row.
+    pub acceleration: Option<Acceleration>,
+    /// Only keep this many of the best fuzzy matches
+    pub max_results: Option<usize>,
+    /// Whether to refresh the results when entries are added to or removed
+    /// from the directory shown, for backends living on disk
+    pub watch: bool,
+    /// How many pages can be fetched per second while scrolling, when limited
+    pub rate_limit: Option<u32>,
+    pub theme: Theme,
+    /// Key bindings on top of the default ones
+    pub keymap: Keymap,
+    /// Where the config was loaded from, to apply it again when it changes
+    pub config: Option<ConfigSource>,
+    /// Let several leaf items be marked and accepted together
+    pub multi: bool,
+    /// Keep going after accepting leaf items, see `--loop`
+    pub keep_open: bool,
+    /// Where every input is written, see `--record`
+    pub record: Option<PathBuf>,
+    /// Inputs to play back instead of waiting for them, see `--replay`
+    pub replay: Option<PathBuf>,
+    /// How many times faster than recorded the inputs are played back
+    pub replay_speed: f64,
+    /// Run for every item accepted while keeping open, see `--exec`
+    pub exec: Option<String>,
+    /// How the terminal was set up, to hand it over to an editor and take it back
+    pub tui: tui::Options,
+    /// Commands offered in the item menu, as `(label, command)`
+    pub item_commands: Vec<(String, String)>,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            unicode: true,
+            ellipsis: "…".to_string(),
+            pointer: ">".to_string(),
+            marker: "▌".to_string(),
+            normalize: false,
+            typos: false,
+            bonuses: Bonuses::default(),
+            fields: None,
+            match_mode: MatchMode::default(),
+            sort: SortMode::default(),
+            start_at: StartAt::default(),
+            show_archived: false,
+            pins_file: None,
+            history_file: None,
+            export_file: PathBuf::from("results.csv"),
+            start_path: Vec::new(),
+            query: String::new(),
+            preview: None,
+            thumbnails: false,
+            link_targets: false,
+            show_timings: false,
+            graphics: GraphicsProtocol::None,
+            tabs: false,
+            group: false,
+            scroll_off: 0,
+            acceleration: None,
+            max_results: None,
+            watch: false,
+            rate_limit: None,
+            theme: Theme::default(),
+            keymap: Keymap::default(),
+            config: None,
+            multi: false,
+            keep_open: false,
+            record: None,
+            replay: None,
+            replay_speed: 1.0,
+            exec: None,
+            tui: tui::Options::default(),
+            item_commands: Vec::new(),
+        }
+    }
+}
+
+/// An input fed to [`App::run_scripted`], as it would come from the terminal
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AppEvent {
+    Key(KeyEvent),
+    Paste(String),
+    /// The terminal was resized to this many columns and rows
+    Resize(u16, u16),
+}
+
+impl AppEvent {
+    /// One key press per character of `text`, as if it was typed
+    pub fn typed(text: &str) -> Vec<AppEvent> {
+        text.chars().map(|c| KeyCode::Char(c).into()).collect()
+    }
+}
+
+impl From<KeyCode> for AppEvent {
+    fn from(code: KeyCode) -> Self {
+        AppEvent::Key(code.into())
+    }
+}
+
+impl From<KeyEvent> for AppEvent {
+    fn from(key_event: KeyEvent) -> Self {
+        AppEvent::Key(key_event)
+    }
+}
+
+/// An accepted leaf item, by its path in the hierarchy
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct Selection {
+    pub path: Vec<String>,
+    /// How well it matched the query, when it was picked from the results
+    pub score: Option<u32>,
+    /// What the backend knows about it, when it was picked from the results
+    pub metadata: BTreeMap<String, String>,
+}
+
+impl Selection {
+    /// The project/sequence the item was selected in
+    pub fn context(&self) -> &[String] {
+        &self.path[..self.path.len().saturating_sub(1)]
+    }
+}
+
+/// Written like `project/sequence/shot`
+impl std::fmt::Display for Selection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.path.join("/"))
+    }
+}
+
+/// How the app ended
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Outcome {
+    /// The selected leaf item, or every marked one in multi-select mode
+    Accepted(Vec<Selection>),
+    /// The user quit or backed out of the top level without picking anything
+    Aborted,
+    /// Nothing matched, so there was nothing to pick, see `--exit-0`
+    NoMatch,
+}
+
+impl Outcome {
+    /// The process exit code for this outcome, the same as fzf's
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            Outcome::Accepted(_) => 0,
+            Outcome::NoMatch => 1,
+            Outcome::Aborted => 130,
+        }
+    }
+}
+
+/// How a scripted session ended, see [`App::run_scripted`]
+#[derive(Debug, Clone)]
+pub struct ScriptedRun {
+    /// How the app ended, or `None` if it was still running when the events ran out
+    pub outcome: Option<Outcome>,
+    /// What the screen looked like after the last event
+    pub screen: Buffer,
+}
+
+/// The terminal size scripted sessions start with
+const SCRIPTED_SIZE: (u16, u16) = (80, 24);
+
+/// A failed listing of the current level, to be tried again
+#[derive(Debug, Clone)]
+struct Retry {
+    /// How many attempts failed so far
+    attempt: u32,
+    /// When to try again
+    at: Instant,
+}
+
+/// Everything a result row depends on, besides the item itself and the marks
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct RowKey {
+    index: usize,
+    item: usize,
+    highlighted: bool,
+    /// How far the name is scrolled, only ever set for the highlighted row
+    scroll: usize,
+    name_width: usize,
+}
 
+/// The result rows drawn in the last frame, for the next one to reuse
+/// those that didn't change. Only the visible ones are kept.
 #[derive(Debug, Default)]
+struct RowCache {
+    rows: HashMap<RowKey, Line<'static>>,
+    /// The [`Marks::revision`] the rows were drawn with
+    marks_revision: u64,
+}
+
+/// A run of results of the same kind, shown under a header row
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Group {
+    kind: ItemKind,
+    /// The index of its first result in the matches
+    start: usize,
+    /// How many of its results are in the matches
+    len: usize,
+    /// How many more are left out while it's collapsed
+    hidden: usize,
+    /// Where its header is in the list, counting the headers above it
+    row: usize,
+}
+
+/// What is drawn on a row of the results list
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ListRow {
+    /// The header of the group at this index
+    Header(usize),
+    /// The result at this index in the matches
+    Match(usize),
+    /// One of the rows shown after the results
+    Extra(usize),
+}
+
+/// Something to do with the terminal itself, between two frames
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum TerminalRequest {
+    /// Suspend the app while this is opened in an editor
+    Open(PathBuf),
+    CopyToClipboard(String),
+    /// Start or stop receiving mouse events
+    MouseCapture(bool),
+    /// Draw everything again, whatever was drawn before
+    Redraw,
+}
+
+/// Side-effectful actions that need the user's confirmation first
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum PendingAction {
+    /// Create an entity with the given name at the current level
+    Create(String),
+    /// Write the results over the export file, which exists already
+    Export,
+}
+
 pub struct App {
-    counter: u8,
+    backend: Box<dyn Backend>,
+    options: Options,
+    theme: Theme,
+    matcher: FuzzyMatcher,
+    pins: Pins,
+    history: History,
+    /// The paths accepted the most lately, offered while the query is empty at the top level
+    recent: Vec<Vec<String>>,
+    /// Which of the recent paths is highlighted, instead of a result
+    recent_selected: Option<usize>,
+    /// What's marked in multi-select mode
+    marks: Marks,
+
+    /// The tab shown, when tabs are enabled
+    tab: Tab,
+    /// What each tab was showing when it was left, indexed like [`Tab::ALL`]
+    tab_states: Vec<TabState>,
+    /// Names of the items we descended into, from the top level down
+    path: Vec<String>,
+    /// What the levels we descended from were showing, one per entry of `path`.
+    /// Unknown for the levels above the one the app started in.
+    levels: Vec<Option<LevelState>>,
+    /// The items fetched so far at the current level
+    items: Vec<Item>,
+    /// Whether the backend may have more items than the ones fetched so far
+    has_more_items: bool,
+    /// The items of the current level being listed in the background, when it's that big
+    scan: Option<Scan>,
+    /// Limits how often pages are fetched, see [`Options::rate_limit`]
+    rate_limit: Option<TokenBucket>,
+    /// Set when fetching another page had to wait for the rate limit
+    load_more_pending: bool,
+    /// Set when listing the current level, or a later page of it, failed for a
+    /// transient reason
+    retry: Option<Retry>,
+    backoff: Backoff,
+    /// The level to put back once its items are in, see [`App::restore_level`]
+    pending_level: Option<LevelState>,
+    /// Indices into `items` of the entries matching the query, best first
+    matches: Vec<usize>,
+    query: String,
+    /// Previous versions of the query, for Ctrl+Z / Ctrl+Shift+Z
+    query_undo: UndoStack,
+    match_mode: MatchMode,
+    /// Why the query couldn't be used, e.g. an invalid regex
+    query_error: Option<String>,
+    /// When set, only items with this status are shown
+    status_filter: Option<Status>,
+    show_archived: bool,
+    sort_mode: SortMode,
+    list_state: ListState,
+    /// How many columns the highlighted name is scrolled horizontally
+    name_scroll: usize,
+    /// How many columns names had room for in the last drawn frame
+    name_width: usize,
+    /// A short note for the user shown in the status bar, until the next key press
+    message: Option<String>,
+    /// When set, a modal dialog is shown and captures every key press
+    confirm: Option<ConfirmDialog<PendingAction>>,
+    /// When set, the command palette is shown and captures every action
+    palette: Option<CommandPalette>,
+    /// When set, the selections accepted so far are listed and capture every action
+    history_browser: Option<HistoryBrowser>,
+    /// What to do with the terminal once the current event is handled
+    terminal_request: Option<TerminalRequest>,
+    /// Whether the mouse is left to the terminal, to select text, until a key is pressed
+    selecting_text: bool,
+    /// Whether the input being handled is played back from a macro or a transcript,
+    /// rather than pressed. Played back moves are never sped up like held keys.
+    replaying: bool,
+    /// Whether the terminal has focus. Watching and retrying pause without it.
+    focused: bool,
+    macros: MacroRecorder,
+    /// Where every input goes, when recording the session
+    recorder: Option<Recorder>,
+    /// The inputs still to play back, when replaying a session
+    replay: Option<Replay>,
+    /// When set, the item menu is shown and captures every action
+    item_menu: Option<ItemMenu>,
+    /// When set, the details of an item are shown and capture every action
+    details: Option<DetailsPopup>,
+    /// When set, the marked items are listed on the side and capture every action
+    marks_panel: Option<MarksPanel>,
+    /// When set, hints are shown on the visible results and capture every action
+    jump: Option<Jump>,
+    /// When set, a result number is typed in the search bar instead of the query
+    go_to: Option<GoTo>,
+    /// How many rows of results fit on screen in the last frame
+    list_height: usize,
+    /// Runs the preview command, when there is one
+    previewer: Option<Previewer>,
+    /// Whether the user hid the preview pane
+    preview_hidden: bool,
+    /// Full path of the item whose preview is shown
+    preview_path: Option<String>,
+    /// How many lines the preview is scrolled down
+    preview_scroll: usize,
+    /// The thumbnail to draw on top of the last frame, and where
+    thumbnail: Option<(PathBuf, Rect)>,
+    /// The thumbnail currently drawn on the terminal, to only redraw it when it changes
+    drawn_thumbnail: Option<(PathBuf, Rect)>,
+
+    /// Notices changes to the directory shown, when watching
+    watcher: Option<DirWatcher>,
+    /// Notices changes to the config file, to apply them right away
+    config_watcher: Option<FileWatcher>,
+
+    /// Timings for the debug overlay, collected whether it's shown or not
+    debug_stats: DebugStats,
+    /// How many items the backend had read so far, while it's still reading them
+    streaming: Option<usize>,
+    /// Whether something changed since the last frame was drawn
+    dirty: bool,
+    row_cache: RowCache,
+    /// The score of each match, by its index in the items
+    scores: HashMap<usize, u32>,
+    /// The groups the matches are split into, empty unless they span several kinds
+    groups: Vec<Group>,
+    /// The kinds whose group only shows its best result, at the current level
+    collapsed: HashSet<ItemKind>,
+    /// The matches collapsed groups leave out of the list, still counted,
+    /// marked and exported with the others
+    hidden_matches: Vec<usize>,
+    show_debug: bool,
+
+    /// What the user picked, once they did
+    accepted: Vec<Selection>,
     should_exit: bool,
 }
 
 impl App {
-    pub fn run(&mut self, terminal: &mut tui::Tui) -> color_eyre::Result<u8> {
+    pub fn new(backend: Box<dyn Backend>, options: Options) -> color_eyre::Result<Self> {
+        let mut app = Self {
+            backend,
+            pins: Pins::load(options.pins_file.clone())?,
+            history: History::load(options.history_file.clone())?,
+            recent: Vec::new(),
+            recent_selected: None,
+            // Loaded before the recorder empties its file, in case it's the same one
+            replay: options
+                .replay
+                .as_deref()
+                .map(|file| Replay::load(file, options.replay_speed))
+                .transpose()?,
+            recorder: options
+                .record
+                .as_deref()
+                .map(Recorder::create)
+                .transpose()?,
+            marks: Marks::default(),
+            sort_mode: options.sort,
+            show_archived: options.show_archived,
+            tab: Tab::default(),
+            tab_states: Tab::ALL.iter().map(|_| TabState::default()).collect(),
+            path: options.start_path.clone(),
+            levels: vec![None; options.start_path.len()],
+            theme: options.theme.clone(),
+            matcher: FuzzyMatcher::new(options.normalize)
+                .with_typos(options.typos)
+                .with_bonuses(options.bonuses),
+            items: Vec::new(),
+            has_more_items: false,
+            scan: None,
+            retry: None,
+            backoff: Backoff::default(),
+            rate_limit: options
+                .rate_limit
+                .map(|rate| TokenBucket::new(rate, Instant::now())),
+            load_more_pending: false,
+            pending_level: None,
+            matches: Vec::new(),
+            query: String::new(),
+            query_undo: UndoStack::default(),
+            match_mode: options.match_mode,
+            query_error: None,
+            status_filter: None,
+            list_state: ListState::default(),
+            name_scroll: 0,
+            name_width: usize::MAX,
+            message: None,
+            confirm: None,
+            palette: None,
+            history_browser: None,
+            marks_panel: None,
+            jump: None,
+            go_to: None,
+            list_height: 0,
+            terminal_request: None,
+            selecting_text: false,
+            replaying: false,
+            focused: true,
+            macros: MacroRecorder::default(),
+            item_menu: None,
+            details: None,
+            previewer: options.preview.as_deref().map(Previewer::new),
+            preview_hidden: false,
+            preview_path: None,
+            preview_scroll: 0,
+            thumbnail: None,
+            drawn_thumbnail: None,
+            watcher: None,
+            config_watcher: None,
+            debug_stats: DebugStats::default(),
+            streaming: None,
+            dirty: true,
+            row_cache: RowCache::default(),
+            scores: HashMap::new(),
+            groups: Vec::new(),
+            collapsed: HashSet::new(),
+            hidden_matches: Vec::new(),
+            show_debug: false,
+            accepted: Vec::new(),
+            should_exit: false,
+            options,
+        };
+        if app.options.watch {
+            app.watcher = Some(DirWatcher::new()?);
+        }
+        // Without a config directory, there's no config file to come either
+        let config_file = app.options.config.as_ref().and_then(|c| c.file.as_deref());
+        if let Some(file) = config_file.filter(|file| file.parent().is_some_and(Path::is_dir)) {
+            match FileWatcher::new(file) {
+                Ok(watcher) => app.config_watcher = Some(watcher),
+                Err(e) => tracing::info!("Not watching the config file: {e:#}"),
+            }
+        }
+
+        // The starting context may have been renamed or removed since
+        if let Err(e) = app.load_items() {
+            if app.path.is_empty() {
+                return Err(e);
+            }
+            tracing::warn!("Could not start from {:?}: {e:#}", app.path);
+            app.path.clear();
+            app.levels.clear();
+            app.load_items()?;
+        }
+        if !app.options.query.is_empty() {
+            app.query = app.options.query.clone();
+            app.update_matches();
+        }
+        // So that Enter right away goes back to the last shot
+        app.recent = app.history.frecent(RECENT_ROWS, history::now());
+        if app.shows_recent() {
+            app.recent_selected = Some(0);
+        }
+
+        Ok(app)
+    }
+
+    /// Runs the app until the user either quits or selects a leaf item
+    pub fn run<B: TerminalBackend>(
+        &mut self,
+        terminal: &mut Terminal<B>,
+    ) -> color_eyre::Result<Outcome> {
         while !self.should_exit {
-            // Draw all the widgets
-            terminal.draw(|frame| self.render_frame(frame))?;
+            // Changes on disk and retries wait until the terminal has focus again
+            if self.focused {
+                self.refresh_if_changed()?;
+                self.retry_if_due()?;
+            }
+            self.poll_scan()?;
+            self.poll_stream()?;
+            self.load_more_if_allowed()?;
+            match self.terminal_request.take() {
+                Some(TerminalRequest::Open(location)) => {
+                    self.open_externally(terminal, &location)?;
+                    self.dirty = true;
+                }
+                Some(TerminalRequest::CopyToClipboard(text)) => {
+                    tui::copy_to_clipboard(&mut std::io::stdout(), &text)?
+                }
+                Some(TerminalRequest::MouseCapture(enabled)) => {
+                    tui::set_mouse_capture(self.options.tui, enabled)?
+                }
+                Some(TerminalRequest::Redraw) => terminal.clear()?,
+                None => {}
+            }
+            self.update_preview();
+
+            // Draw all the widgets, unless nothing changed since the last frame
+            if self.dirty {
+                let draw_start = Instant::now();
+                terminal.draw(|frame| self.render_frame(frame))?;
+                self.draw_thumbnail()?;
+                self.debug_stats.frame_drawn(draw_start);
+                self.dirty = false;
+            }
 
             // Handle events
             self.handle_events().wrap_err("handle_events failed")?;
         }
 
-        Ok(self.counter)
+        Ok(self.outcome())
+    }
+
+    fn outcome(&mut self) -> Outcome {
+        if self.accepted.is_empty() {
+            Outcome::Aborted
+        } else {
+            Outcome::Accepted(std::mem::take(&mut self.accepted))
+        }
+    }
+
+    /// How many items match at the current level, fetching every page first.
+    /// Used to decide whether to show the app at all, like `fzf --exit-0`.
+    pub fn count_all_matches(&mut self) -> color_eyre::Result<usize> {
+        if let Some(scan) = self.scan.take() {
+            self.finish_scan(scan.wait())?;
+        }
+        while self.streaming.is_some() {
+            std::thread::sleep(PREVIEW_REFRESH_INTERVAL);
+            self.poll_stream()?;
+        }
+        if self.has_more_items {
+            while self.has_more_items {
+                self.fetch_page()?;
+            }
+            self.update_matches();
+        }
+        Ok(self.matches.len())
+    }
+
+    /// Accepts the only match for as long as there is exactly one, like `fzf --select-1`,
+    /// descending through levels. Returns the selection when it ends on a leaf.
+    pub fn select_only_match(&mut self) -> color_eyre::Result<Option<Selection>> {
+        while self.count_all_matches()? == 1 {
+            self.accept()?;
+            if let Some(selection) = self.accepted.pop() {
+                return Ok(Some(selection));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Where the selected item lives on disk, if anywhere
+    pub fn location(&self, selection: &Selection) -> Option<PathBuf> {
+        self.backend.location(&selection.path)
+    }
+
+    /// Feeds `events` to the app as if they came from a terminal, drawing into a
+    /// [`TestBackend`] in between, until they run out or the app exits.
+    /// Events after the app exited are ignored.
+    pub fn run_scripted(&mut self, events: Vec<AppEvent>) -> color_eyre::Result<ScriptedRun> {
+        let (width, height) = SCRIPTED_SIZE;
+        let mut terminal = Terminal::new(TestBackend::new(width, height))?;
+
+        for event in events {
+            if self.should_exit {
+                break;
+            }
+
+            self.poll_scan()?;
+            self.update_preview();
+            terminal.draw(|frame| self.render_frame(frame))?;
+            match event {
+                AppEvent::Key(key_event) => self.handle_key_event(key_event)?,
+                AppEvent::Paste(text) => self.handle_paste(&text),
+                AppEvent::Resize(width, height) => terminal.backend_mut().resize(width, height),
+            }
+        }
+
+        self.poll_scan()?;
+        self.update_preview();
+        terminal.draw(|frame| self.render_frame(frame))?;
+
+        Ok(ScriptedRun {
+            outcome: self.should_exit.then(|| self.outcome()),
+            screen: terminal.backend().buffer().clone(),
+        })
     }
 
-    fn render_frame(&self, frame: &mut Frame) {
-        frame.render_widget(self, frame.size());
+    fn render_frame(&mut self, frame: &mut Frame) {
+        let area = frame.size();
+        frame.render_widget(&mut *self, area);
+
+        // Put the terminal cursor right after the query, inside the search box
+        let modal = self.confirm.is_some()
+            || self.palette.is_some()
+            || self.history_browser.is_some()
+            || self.marks_panel.is_some()
+            || self.item_menu.is_some()
+            || self.details.is_some();
+        if !modal && !is_too_small(area) {
+            let [_, _, search_area, _, _] = layout(area, self.options.tabs);
+            let prompt = match &self.go_to {
+                Some(go_to) => go_to.digits().len() + 1,
+                None => text::width(&self.query),
+            };
+            let prompt_width = 3 + prompt as u16;
+            if search_area.height > 2 && prompt_width < search_area.width.saturating_sub(1) {
+                frame.set_cursor(search_area.x + prompt_width, search_area.y + 1);
+            }
+        }
+    }
+
+    /// Images can't go through the ratatui buffer, so they're drawn on top of it
+    fn draw_thumbnail(&mut self) -> color_eyre::Result<()> {
+        if self.thumbnail == self.drawn_thumbnail {
+            return Ok(());
+        }
+
+        let graphics = self.options.graphics;
+        let mut stdout = std::io::stdout();
+        graphics.clear(&mut stdout)?;
+        if let Some((path, area)) = &self.thumbnail {
+            if let Err(e) = graphics.draw(&mut stdout, path, *area) {
+                tracing::warn!("Could not draw the thumbnail {path:?}: {e}");
+            }
+        }
+        self.drawn_thumbnail = self.thumbnail.clone();
+
+        Ok(())
     }
 
     fn handle_events(&mut self) -> color_eyre::Result<()> {
-        match event::read()? {
-            // It's important to check that the event is a key press event as
-            // crossterm also emits key release and repeat events on Windows.
-            Event::Key(key_event) if key_event.kind == KeyEventKind::Press => self
-                .handle_key_event(key_event)
-                .wrap_err_with(|| format!("handling key event failed:\n{key_event:#?}"))?,
-            _ => {}
+        // Key presses still go through while replaying, e.g. to quit
+        if let Some(replay) = &mut self.replay {
+            match replay.next(Instant::now()) {
+                ReplayStep::Input(entry) => return self.replay_input(entry),
+                ReplayStep::Wait(wait) => {
+                    if !event::poll(wait.min(WATCH_INTERVAL))? {
+                        return Ok(());
+                    }
+                }
+                ReplayStep::Done => {
+                    self.replay = None;
+                    self.message = Some("replay finished".to_string());
+                    self.dirty = true;
+                    return Ok(());
+                }
+            }
+        }
+        // Sleep until something happens in the terminal while it's in the background,
+        // replays aside
+        if !self.focused {
+            return self.handle_event(event::read()?);
+        }
+        // Don't block on input while a preview or items are streaming in, or
+        // items are being scanned, to redraw them
+        let busy = self.previewer.as_ref().is_some_and(Previewer::is_busy)
+            || self.scan.is_some()
+            || self.streaming.is_some();
+        if busy && !event::poll(PREVIEW_REFRESH_INTERVAL)? {
+            // The scan progress moved on
+            self.dirty |= self.scan.is_some();
+            return Ok(());
+        }
+        // Nor while watching, to pick up changes without waiting for a key press,
+        // or while a page waits for the rate limit, to fetch it once it's allowed
+        let watching =
+            self.watcher.is_some() || self.config_watcher.is_some() || self.retry.is_some();
+        let timeout = match (&mut self.rate_limit, self.load_more_pending) {
+            (Some(rate_limit), true) => Some(rate_limit.wait(Instant::now()).min(WATCH_INTERVAL)),
+            (None, true) => Some(Duration::ZERO),
+            (_, false) => watching.then_some(WATCH_INTERVAL),
         };
+        if let Some(timeout) = timeout {
+            if !event::poll(timeout)? {
+                // The retry countdown moved on
+                self.dirty |= self.retry.is_some();
+                return Ok(());
+            }
+        }
+
+        self.debug_stats.event_received();
+        self.handle_event(event::read()?)?;
+        // Handle bursts of events (e.g. key repeat) before drawing once
+        for _ in 1..MAX_EVENTS_PER_FRAME {
+            if self.should_exit || !event::poll(Duration::ZERO)? {
+                break;
+            }
+            self.handle_event(event::read()?)?;
+        }
 
         Ok(())
     }
 
-    fn handle_key_event(&mut self, key_event: KeyEvent) -> color_eyre::Result<()> {
-        match key_event.code {
-            KeyCode::Char('q') => self.exit(),
-            KeyCode::Left => self.decrement_counter()?,
-            KeyCode::Right => self.increment_counter()?,
+    fn handle_event(&mut self, event: event::Event) -> color_eyre::Result<()> {
+        let _span = tracing::debug_span!("handle_event", ?event).entered();
+        // Even events that aren't bound to anything may be a resize
+        self.dirty = true;
+        match event {
+            event::Event::FocusLost => self.focused = false,
+            event::Event::FocusGained => {
+                self.focused = true;
+                self.terminal_request = Some(TerminalRequest::Redraw);
+            }
             _ => {}
         }
+        if matches!(event, event::Event::Key(key) if key.kind == event::KeyEventKind::Press)
+            && self.stop_selecting_text()
+        {
+            return Ok(());
+        }
+        if let Some(input) = self.options.keymap.translate(event) {
+            self.handle_input(input.clone())
+                .wrap_err_with(|| format!("handling {input:?} failed"))?;
+            self.record(&input)?;
+        }
+        Ok(())
+    }
+
+    /// Handles an input played back from a transcript, telling when the app
+    /// doesn't end up the way it did when it was recorded
+    fn replay_input(&mut self, entry: Entry) -> color_eyre::Result<()> {
+        self.dirty = true;
+        self.handle_replayed_input(entry.input.clone())
+            .wrap_err_with(|| format!("replaying {:?} failed", entry.input))?;
+        self.record(&entry.input)?;
+
+        let state = self.snapshot();
+        if state != entry.state {
+            tracing::warn!(
+                "Replay differs after {:?} at {}ms: recorded {:?}, got {state:?}",
+                entry.input,
+                entry.at_ms,
+                entry.state
+            );
+            self.message = Some(format!(
+                "replay differs from the recording at {}ms",
+                entry.at_ms
+            ));
+        }
+        Ok(())
+    }
+
+    fn record(&mut self, input: &InputEvent) -> color_eyre::Result<()> {
+        let state = self.snapshot();
+        match &mut self.recorder {
+            Some(recorder) => recorder.record(input, state),
+            None => Ok(()),
+        }
+    }
+
+    /// What a transcript remembers of the app after each input
+    fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            path: self.path.clone(),
+            query: self.query.clone(),
+            matches: self.matches.len(),
+            highlighted: self.highlighted_item().map(|item| item.name.clone()),
+            message: self.message.clone(),
+        }
+    }
+
+    /// Handles a key press the way the keymap says
+    fn handle_key_event(&mut self, key_event: KeyEvent) -> color_eyre::Result<()> {
+        if self.stop_selecting_text() {
+            return Ok(());
+        }
+        self.message = None;
+
+        match self.options.keymap.action_for(key_event) {
+            Some(action) => self.handle_input(InputEvent::Action(action)),
+            None => Ok(()),
+        }
+    }
+
+    fn handle_input(&mut self, input: InputEvent) -> color_eyre::Result<()> {
+        self.macros.record(&input);
+        match input {
+            // Whatever is open, so they can be recorded in the middle of anything
+            InputEvent::Action(Action::RecordMacro) => self.toggle_macro_recording(),
+            InputEvent::Action(Action::ReplayMacro) => self.replay_macro()?,
+            InputEvent::Action(action) => {
+                self.message = None;
+                self.handle_action(action)?;
+            }
+            InputEvent::Paste(text) => self.handle_paste(&text),
+        }
 
         Ok(())
     }
 
+    fn handle_action(&mut self, action: Action) -> color_eyre::Result<()> {
+        if let Some(palette) = &mut self.palette {
+            match palette.handle_action(action, &mut self.matcher) {
+                PaletteOutcome::Pending => {}
+                PaletteOutcome::Cancelled => self.palette = None,
+                PaletteOutcome::Run(action) => {
+                    self.palette = None;
+                    self.handle_action(action)?;
+                }
+            }
+            return Ok(());
+        }
+
+        if let Some(browser) = &mut self.history_browser {
+            match browser.handle_action(action, &mut self.matcher) {
+                BrowserOutcome::Pending => {}
+                BrowserOutcome::Cancelled => self.history_browser = None,
+                BrowserOutcome::Accept(path) => {
+                    self.history_browser = None;
+                    self.accept_from_history(path);
+                }
+            }
+            return Ok(());
+        }
+
+        if let Some(details) = &mut self.details {
+            if details.handle_action(action) {
+                self.details = None;
+            }
+            return Ok(());
+        }
+
+        if let Some(menu) = &mut self.item_menu {
+            match menu.handle_action(action) {
+                MenuOutcome::Pending => {}
+                MenuOutcome::Cancelled => self.item_menu = None,
+                MenuOutcome::Run(entry) => {
+                    self.item_menu = None;
+                    self.run_menu_entry(entry)?;
+                }
+            }
+            return Ok(());
+        }
+
+        if let Some(panel) = &mut self.marks_panel {
+            match panel.handle_action(action, &mut self.marks) {
+                PanelOutcome::Pending => {}
+                PanelOutcome::Closed => self.marks_panel = None,
+                PanelOutcome::Accept => {
+                    self.marks_panel = None;
+                    self.accept_marks();
+                }
+            }
+            return Ok(());
+        }
+
+        if let Some(jump) = &mut self.jump {
+            match jump.handle_action(action) {
+                JumpOutcome::Pending => {}
+                JumpOutcome::Cancelled => self.jump = None,
+                JumpOutcome::Jump(index) => {
+                    self.jump = None;
+                    self.recent_selected = None;
+                    self.list_state.select(Some(index));
+                    self.name_scroll = 0;
+                }
+            }
+            return Ok(());
+        }
+
+        if let Some(go_to) = &mut self.go_to {
+            match go_to.handle_action(action) {
+                JumpOutcome::Pending => {}
+                JumpOutcome::Cancelled => {
+                    self.go_to = None;
+                    // Quitting doesn't take closing the prompt first
+                    if action == Action::Quit {
+                        return self.handle_action(action);
+                    }
+                }
+                JumpOutcome::Jump(index) => {
+                    self.go_to = None;
+                    self.recent_selected = None;
+                    if let Some(last) = self.matches.len().checked_sub(1) {
+                        self.list_state.select(Some(index.min(last)));
+                        self.name_scroll = 0;
+                        self.load_more_if_needed()?;
+                    }
+                }
+            }
+            return Ok(());
+        }
+
+        if let Some(dialog) = &mut self.confirm {
+            match dialog.handle_action(action) {
+                Some(true) => {
+                    if let Some(dialog) = self.confirm.take() {
+                        self.run_pending_action(dialog.into_action())?;
+                    }
+                }
+                Some(false) => self.confirm = None,
+                None => {}
+            }
+            return Ok(());
+        }
+
+        match action {
+            Action::Quit => self.exit(),
+            Action::Back => self.go_up()?,
+            Action::Accept => match self.highlighted_recent() {
+                Some(index) => self.go_to_recent(index)?,
+                None => self.accept()?,
+            },
+            Action::MoveUp => {
+                let rows = self.accelerated(-1);
+                if !self.move_through_recent(rows) {
+                    self.move_highlight(rows);
+                }
+            }
+            Action::MoveDown => {
+                let rows = self.accelerated(1);
+                if !self.move_through_recent(rows) {
+                    self.move_highlight(rows);
+                    self.load_more_if_needed()?;
+                }
+            }
+            Action::ScrollLeft => {
+                if !self.collapse_group() {
+                    self.scroll_name(-NAME_SCROLL_STEP);
+                }
+            }
+            Action::ScrollRight => {
+                if !self.expand_group() {
+                    self.scroll_name(NAME_SCROLL_STEP);
+                }
+            }
+            Action::ScrollPreviewUp => self.scroll_preview(-1),
+            Action::ScrollPreviewDown => self.scroll_preview(1),
+            // Tab and Shift+Tab mark items as they go, like in fzf
+            Action::FocusNext if self.options.multi => {
+                self.toggle_mark();
+                self.move_highlight(1);
+                self.load_more_if_needed()?;
+            }
+            Action::FocusPrevious if self.options.multi => {
+                self.toggle_mark();
+                self.move_highlight(-1);
+            }
+            Action::FocusNext | Action::FocusPrevious => {}
+            // A number after `:` goes to that result, queries don't start with one
+            Action::Insert(':') if self.query.is_empty() => self.go_to = Some(GoTo::default()),
+            Action::Insert(c) => self.edit_query(|query| query.push(c)),
+            Action::DeleteChar => self.edit_query(|query| {
+                text::pop_grapheme(query);
+            }),
+            Action::ClearQuery => self.edit_query(String::clear),
+            Action::Undo => self.undo_query(),
+            Action::Redo => self.redo_query(),
+            Action::CycleStatusFilter => self.cycle_status_filter(),
+            Action::CycleSort => self.cycle_sort_mode(),
+            Action::TogglePin => self.toggle_pin()?,
+            Action::ToggleMark => self.toggle_mark(),
+            Action::MarkAll => self.mark_matches(|_| true),
+            Action::UnmarkAll => self.unmark_all(),
+            Action::InvertMarks => self.mark_matches(|marked| !marked),
+            Action::ToggleMarksPanel => self.open_marks_panel(),
+            Action::ToggleArchived => self.toggle_archived(),
+            Action::ToggleMatchMode => self.toggle_match_mode(),
+            Action::GoToTop => self.go_to_top()?,
+            Action::ExportCsv => self.export_csv(),
+            Action::SelectText => self.start_selecting_text(),
+            Action::RecordMacro => self.toggle_macro_recording(),
+            Action::ReplayMacro => self.replay_macro()?,
+            Action::Jump => self.jump = Some(Jump::new(self.visible_matches())),
+            Action::TogglePreview => self.toggle_preview(),
+            Action::OpenExternally => self.request_open(),
+            Action::ShowDetails => self.show_details(),
+            Action::OpenItemMenu => {
+                if let Some(item) = self.highlighted_item() {
+                    let commands = &self.options.item_commands;
+                    let menu = ItemMenu::new(&item.name, commands, self.theme.highlight);
+                    self.item_menu = Some(menu.with_borders(self.borders()));
+                }
+            }
+            Action::Reload => self.reload()?,
+            Action::ReloadConfig => self.reload_config(),
+            Action::NextTab => self.cycle_tab(1)?,
+            Action::PreviousTab => self.cycle_tab(-1)?,
+            Action::SelectTab(index) => self.switch_tab(index)?,
+            Action::ToggleDebug => self.show_debug = !self.show_debug,
+            Action::OpenPalette => {
+                let palette = CommandPalette::new(&mut self.matcher, self.theme.highlight);
+                self.palette = Some(palette.with_borders(self.borders()));
+            }
+            Action::ShowHistory => {
+                let browser =
+                    HistoryBrowser::new(&self.history, &mut self.matcher, self.theme.highlight);
+                self.history_browser = Some(browser.with_borders(self.borders()));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Inserts pasted text into the query in one go, so it's a single search
+    /// (and a single undo step) rather than one per character
+    fn handle_paste(&mut self, text: &str) {
+        if self.confirm.is_some() {
+            return;
+        }
+
+        // The query is a single line, and control characters have no business in it
+        let text: String = text
+            .chars()
+            .map(|c| if c.is_whitespace() { ' ' } else { c })
+            .filter(|c| !c.is_control())
+            .collect();
+        self.edit_query(|query| query.push_str(&text));
+    }
+
     fn exit(&mut self) {
         self.should_exit = true;
     }
 
-    fn increment_counter(&mut self) -> color_eyre::Result<()> {
-        let new_value = self
-            .counter
-            .checked_add(1)
-            .ok_or(format!("Overflow when adding 1 from {}", self.counter))
-            .map_err(color_eyre::eyre::Error::msg)?;
+    /// The item currently under the cursor, if any
+    fn highlighted_item(&self) -> Option<&Item> {
+        let index = self.list_state.selected()?;
+        self.matches.get(index).map(|i| &self.items[*i])
+    }
+
+    /// The full hierarchy path of an item living at the current level
+    fn item_path(&self, item: &Item) -> String {
+        join_path(&self.path, &item.name)
+    }
+
+    /// The names leading to an item living at the current level, itself included
+    fn path_to(&self, item: &Item) -> Vec<String> {
+        self.path.iter().chain([&item.name]).cloned().collect()
+    }
+
+    /// Where the item at `path` lives on disk, or its hierarchy path when it's nowhere,
+    /// for `{path}` in commands
+    fn location_string(&self, path: &[String]) -> String {
+        self.backend
+            .location(path)
+            .map(|location| location.to_string_lossy().into_owned())
+            .unwrap_or_else(|| path.join("/"))
+    }
+
+    /// Fetches the items for the current path and resets the search
+    fn load_items(&mut self) -> color_eyre::Result<()> {
+        self.watch_current_dir();
+        self.items.clear();
+        self.pending_level = None;
+        self.retry = None;
+        // Other levels have other groups
+        self.collapsed.clear();
+        self.streaming = self.backend.streaming(&self.path);
+        self.scan = self.backend.scan(&self.path);
+        if self.scan.is_some() {
+            self.has_more_items = false;
+        } else if let Err(e) = self.fetch_page() {
+            self.retry_later(e, 1)?;
+        }
+        self.query.clear();
+        self.query_undo.clear();
+        self.update_matches();
 
-        self.counter = new_value;
         Ok(())
     }
 
-    fn decrement_counter(&mut self) -> color_eyre::Result<()> {
-        let new_value = self
-            .counter
-            .checked_sub(1)
-            .ok_or(format!(
-                "Underflow when subtracting 1 from {}",
-                self.counter
-            ))
-            .map_err(color_eyre::eyre::Error::msg)?;
+    /// Appends the next page of items from the backend
+    fn fetch_page(&mut self) -> color_eyre::Result<()> {
+        let _span =
+            tracing::debug_span!("fetch_page", path = ?self.path, offset = self.items.len())
+                .entered();
+        let start = Instant::now();
+        let page = self
+            .backend
+            .fetch(&self.path, self.items.len(), PAGE_SIZE)
+            .wrap_err_with(|| format!("failed to list items under {:?}", self.path))?;
+        self.debug_stats.fetch_time = start.elapsed();
+        self.has_more_items = page.len() == PAGE_SIZE;
+        self.add_items(page);
 
-        self.counter = new_value;
         Ok(())
     }
-}
 
-impl Widget for &App {
-    fn render(self, area: Rect, buf: &mut Buffer) {
-        let title = Title::from(" Counter App Tutorial ".bold());
-        let instructions = Title::from(Line::from(vec![
-            " Decrement ".into(),
-            "<Left>".blue().bold().into(),
-            " Increment ".into(),
-            " <Right>".blue().bold().into(),
-            " Quit ".into(),
-            " <Q> ".blue().bold().into(),
-        ]));
+    fn add_items(&mut self, mut items: Vec<Item>) {
+        if let Some(fields) = &self.options.fields {
+            for item in &mut items {
+                item.match_text = Some(fields.select(&item.name));
+            }
+        }
+        self.items.append(&mut items);
+    }
 
-        let block = Block::default()
-            .title(title.alignment(Alignment::Center))
-            .title(
-                instructions
-                    .alignment(Alignment::Center)
-                    .position(Position::Bottom),
-            )
-            .borders(Borders::ALL)
-            .border_set(border::THICK);
+    /// Schedules another attempt at listing the current level after the given
+    /// one failed, when it's worth it and there are attempts left.
+    /// Otherwise the error is returned, or shown when it was transient.
+    fn retry_later(&mut self, error: color_eyre::Report, attempt: u32) -> color_eyre::Result<()> {
+        if !self.backend.is_transient(&error) {
+            return Err(error);
+        }
+        if !self.backoff.can_retry(attempt) {
+            self.retry = None;
+            self.message = Some(format!("gave up after {attempt} attempts: {error:#}"));
+            return Ok(());
+        }
 
-        let counter_text = Text::from(vec![Line::from(vec![
-            "Value: ".into(),
-            self.counter.to_string().yellow(),
-        ])]);
+        tracing::warn!("Attempt {attempt} failed, retrying: {error:#}");
+        self.retry = Some(Retry {
+            attempt,
+            at: Instant::now() + self.backoff.jittered_delay(attempt + 1),
+        });
+        Ok(())
+    }
 
-        Paragraph::new(counter_text)
-            .centered()
-            .block(block)
-            .render(area, buf);
+    fn retry_if_due(&mut self) -> color_eyre::Result<()> {
+        match &self.retry {
+            Some(retry) if retry.at <= Instant::now() => {
+                self.dirty = true;
+                self.retry_now()
+            }
+            _ => Ok(()),
+        }
     }
-}
 
-#[cfg(test)]
-mod tests {
+    /// Tries fetching the page that failed again, without waiting for the backoff.
+    /// The items of the pages before it are kept.
+    fn retry_now(&mut self) -> color_eyre::Result<()> {
+        let Some(retry) = self.retry.take() else {
+            return Ok(());
+        };
+        let selected = self.list_state.selected();
+        match self.fetch_page() {
+            Ok(()) => {
+                self.update_matches();
+                if let Some(level) = self.pending_level.take() {
+                    self.restore_level(level);
+                } else if selected.is_some_and(|index| index < self.matches.len()) {
+                    self.list_state.select(selected);
+                }
+                Ok(())
+            }
+            Err(e) => self.retry_later(e, retry.attempt + 1),
+        }
+    }
 
-    use super::*;
+    /// Adds the items the backend read since the last frame, while it's still
+    /// reading them
+    fn poll_stream(&mut self) -> color_eyre::Result<()> {
+        let Some(read) = self.streaming else {
+            return Ok(());
+        };
+        self.streaming = self.backend.streaming(&self.path);
+        // The count in the status bar moved on, or it's gone
+        self.dirty |= self.streaming != Some(read);
+        let count = self.streaming.unwrap_or(usize::MAX);
+        if count <= self.items.len() {
+            return Ok(());
+        }
 
-    #[test]
-    fn test_handle_counter_interaction() {
-        let mut app = App::default();
-        app.handle_key_event(KeyCode::Right.into());
-        assert_eq!(app.counter, 1);
+        let items = self
+            .backend
+            .fetch(&self.path, self.items.len(), count - self.items.len())
+            .wrap_err_with(|| format!("failed to list items under {:?}", self.path))?;
+        self.has_more_items = false;
+        self.add_items(items);
+        // The new items are ranked with the others, so the highlight goes back to
+        // where it starts, unless it was moved onto an item that still matches
+        let start = self.options.start_at.index(&self.matches, &self.scores);
+        let moved_to = self
+            .list_state
+            .selected()
+            .filter(|index| Some(*index) != start)
+            .and_then(|index| self.matches.get(index).copied());
+        self.update_matches();
+        if let Some(row) = moved_to.and_then(|item| self.matches.iter().position(|i| *i == item)) {
+            self.list_state.select(Some(row));
+        }
 
-        app.handle_key_event(KeyCode::Left.into());
-        assert_eq!(app.counter, 0);
+        Ok(())
     }
 
-    #[test]
-    fn test_handle_exit() -> io::Result<()> {
-        // If a user presses 'q', we should quit
-        let mut app = App::default();
-        app.handle_key_event(KeyCode::Char('q').into());
-        assert_eq!(app.should_exit, true);
+    /// Picks up the progress of the background scan, and its items once it's done
+    fn poll_scan(&mut self) -> color_eyre::Result<()> {
+        let Some(result) = self.scan.as_mut().and_then(Scan::poll) else {
+            return Ok(());
+        };
+        self.scan = None;
+        self.dirty = true;
+        self.finish_scan(result)
+    }
+
+    fn finish_scan(&mut self, result: Result<Vec<Item>, String>) -> color_eyre::Result<()> {
+        let items = result
+            .map_err(|e| eyre!(e))
+            .wrap_err_with(|| format!("failed to list items under {:?}", self.path))?;
+        self.add_items(items);
+        self.update_matches();
+        if let Some(level) = self.pending_level.take() {
+            self.restore_level(level);
+        }
+
+        Ok(())
+    }
+
+    /// Fetches another page when the highlight gets close to the end of the results,
+    /// keeping the highlight where it is. While there's a query, fetches every page
+    /// left instead, since any of them may hold a better match.
+    fn load_more_if_needed(&mut self) -> color_eyre::Result<()> {
+        let selected = self.list_state.selected();
+        let near_end = selected.is_none_or(|index| index + LOAD_MORE_MARGIN >= self.matches.len());
+        let wanted = near_end || !self.query.is_empty();
+        self.load_more_pending = false;
+        // A page that failed is fetched again by the retry, after the backoff
+        if !self.has_more_items || !wanted || self.retry.is_some() {
+            return Ok(());
+        }
+
+        let mut fetched = false;
+        while self.has_more_items {
+            // Requests aren't queued: by the time another page is allowed, the
+            // highlight may have moved away from the end
+            if let Some(rate_limit) = &mut self.rate_limit {
+                if !rate_limit.try_take(Instant::now()) {
+                    self.load_more_pending = true;
+                    break;
+                }
+            }
+            if let Err(e) = self.fetch_page() {
+                self.retry_later(e, 1)?;
+                break;
+            }
+            fetched = true;
+            if self.query.is_empty() {
+                break;
+            }
+        }
+        if !fetched {
+            return Ok(());
+        }
+
+        self.update_matches();
+        if selected.is_some_and(|index| index < self.matches.len()) {
+            self.list_state.select(selected);
+        }
+
+        Ok(())
+    }
+
+    /// Fetches the page that had to wait for the rate limit, if it's still needed
+    fn load_more_if_allowed(&mut self) -> color_eyre::Result<()> {
+        if self.load_more_pending {
+            self.load_more_if_needed()?;
+            self.dirty |= !self.load_more_pending;
+        }
+        Ok(())
+    }
+
+    /// Applies an edit to the query, remembering the previous one for undo
+    fn edit_query(&mut self, edit: impl FnOnce(&mut String)) {
+        let previous = self.query.clone();
+        edit(&mut self.query);
+        if self.query != previous {
+            self.query_undo.record(&previous);
+            self.update_matches();
+        }
+    }
+
+    fn undo_query(&mut self) {
+        if let Some(query) = self.query_undo.undo(&self.query) {
+            self.query = query;
+            self.update_matches();
+        }
+    }
+
+    fn redo_query(&mut self) {
+        if let Some(query) = self.query_undo.redo(&self.query) {
+            self.query = query;
+            self.update_matches();
+        }
+    }
+
+    fn update_matches(&mut self) {
+        self.row_cache.rows.clear();
+        let span = tracing::debug_span!(
+            "update_matches",
+            query = %self.query,
+            items = self.items.len(),
+            matches = tracing::field::Empty,
+        )
+        .entered();
+        let start = Instant::now();
+        let query = Query::parse(&self.query);
+
+        // Narrow down by status and facets first, so we only score what could be shown
+        let candidates: Vec<usize> = (0..self.items.len())
+            .filter(|i| {
+                let item = &self.items[*i];
+                (self.show_archived || !item.archived)
+                    && (!self.options.tabs || self.tab.accepts(item.kind))
+                    && (self.status_filter.is_none() || item.status == self.status_filter)
+                    && query.matches_facets(item)
+                    && query.matches_scopes(&self.path, item)
+            })
+            .collect();
+
+        let names = candidates.iter().map(|i| self.items[*i].match_text());
+        self.query_error = None;
+        let text_matches = match self.match_mode {
+            MatchMode::Fuzzy => {
+                self.matcher
+                    .match_top(&query.text, names, self.options.max_results)
+            }
+            MatchMode::Regex => match matcher::match_regex(&query.text, names) {
+                Ok(matches) => matches,
+                // Keep showing everything while the regex is being typed
+                Err(e) => {
+                    self.query_error = Some(regex_error_summary(&e));
+                    (0..candidates.len()).map(|index| (index, 0)).collect()
+                }
+            },
+        };
+
+        self.scores = text_matches
+            .iter()
+            .map(|(index, score)| (candidates[*index], *score))
+            .collect();
+        self.matches = text_matches
+            .into_iter()
+            .map(|(index, _score)| candidates[index])
+            .collect();
+        self.sort_mode.sort(&mut self.matches, &self.items);
+
+        // Pinned items always float to the top, whatever their score
+        self.matches.sort_by_key(|i| {
+            !self
+                .pins
+                .contains(&join_path(&self.path, &self.items[*i].name))
+        });
+        self.hidden_matches.clear();
+        if self.options.group {
+            self.group_matches();
+        }
+
+        let selected = self.options.start_at.index(&self.matches, &self.scores);
+        self.list_state = ListState::default().with_selected(selected);
+        self.name_scroll = 0;
+        self.debug_stats.match_time = start.elapsed();
+        span.record("matches", self.matches.len());
+
+        // The count would only cover the pages fetched so far, the run loop
+        // fetches the others before drawing it
+        self.load_more_pending |= self.has_more_items && !self.query.is_empty();
+    }
+
+    /// Keeps results of the same kind together, in the order their best match
+    /// comes in, and finds where each group starts
+    fn group_matches(&mut self) {
+        let mut kinds: Vec<ItemKind> = Vec::new();
+        for index in &self.matches {
+            let kind = self.items[*index].kind;
+            if !kinds.contains(&kind) {
+                kinds.push(kind);
+            }
+        }
+        self.groups.clear();
+        if kinds.len() < 2 {
+            return;
+        }
+
+        self.matches
+            .sort_by_key(|i| kinds.iter().position(|kind| *kind == self.items[*i].kind));
+        let mut hidden: HashMap<ItemKind, usize> = HashMap::new();
+        let mut previous = None;
+        let hidden_matches = &mut self.hidden_matches;
+        self.matches.retain(|i| {
+            let kind = self.items[*i].kind;
+            let first = previous != Some(kind);
+            previous = Some(kind);
+            if !first && self.collapsed.contains(&kind) {
+                *hidden.entry(kind).or_default() += 1;
+                hidden_matches.push(*i);
+                return false;
+            }
+            true
+        });
+        for (index, item) in self.matches.iter().enumerate() {
+            let kind = self.items[*item].kind;
+            match self.groups.last_mut() {
+                Some(group) if group.kind == kind => group.len += 1,
+                _ => self.groups.push(Group {
+                    kind,
+                    start: index,
+                    len: 1,
+                    hidden: hidden.get(&kind).copied().unwrap_or(0),
+                    row: index + self.groups.len(),
+                }),
+            }
+        }
+    }
+
+    /// Collapses the group of the highlighted result down to its best one.
+    /// Returns whether there was a group to collapse.
+    fn collapse_group(&mut self) -> bool {
+        // Left scrolls the name back first
+        if self.name_scroll > 0 || self.groups.is_empty() {
+            return false;
+        }
+        let Some(kind) = self.highlighted_item().map(|item| item.kind) else {
+            return false;
+        };
+        if !self.collapsed.insert(kind) {
+            return false;
+        }
+        self.regroup(kind);
+        true
+    }
+
+    /// Shows all the results of the highlighted group again, if it's collapsed
+    fn expand_group(&mut self) -> bool {
+        let Some(kind) = self.highlighted_item().map(|item| item.kind) else {
+            return false;
+        };
+        if !self.collapsed.remove(&kind) {
+            return false;
+        }
+        self.regroup(kind);
+        true
+    }
+
+    /// Matches again after collapsing or expanding the group of `kind`,
+    /// keeping its first result highlighted
+    fn regroup(&mut self, kind: ItemKind) {
+        self.update_matches();
+        if let Some(group) = self.groups.iter().find(|group| group.kind == kind) {
+            self.list_state.select(Some(group.start));
+        }
+    }
+
+    /// The row of the results list the match at `index` is drawn on
+    fn match_row(&self, index: usize) -> usize {
+        index + self.groups.partition_point(|group| group.start <= index)
+    }
+
+    /// What is drawn on the given row of the results list
+    fn list_row(&self, row: usize) -> ListRow {
+        let Some(group) = self
+            .groups
+            .partition_point(|group| group.row <= row)
+            .checked_sub(1)
+        else {
+            return match row.checked_sub(self.matches.len()) {
+                Some(extra) => ListRow::Extra(extra),
+                None => ListRow::Match(row),
+            };
+        };
+        if self.groups[group].row == row {
+            return ListRow::Header(group);
+        }
+        let index = row - group - 1;
+        match index.checked_sub(self.matches.len()) {
+            Some(extra) => ListRow::Extra(extra),
+            None => ListRow::Match(index),
+        }
+    }
+
+    /// The header row of a group, with how many results are in it
+    fn group_header(&self, group: &Group) -> Line<'static> {
+        let mut spans = vec![
+            " ".into(),
+            Span::styled(
+                group.kind.icon(self.options.unicode),
+                self.theme.kind_style(group.kind),
+            ),
+            " ".into(),
+            Span::styled(group.kind.group_label(), self.theme.title),
+            format!(" ({})", group.len + group.hidden).dim(),
+        ];
+        if group.hidden > 0 {
+            spans.push(format!(" {} more hidden", group.hidden).dim());
+        }
+        Line::from(spans)
+    }
+
+    /// Cycles through showing all items, then only the ones of each status
+    fn cycle_status_filter(&mut self) {
+        self.status_filter = match self.status_filter {
+            None => Some(Status::ALL[0]),
+            Some(status) => Status::ALL
+                .iter()
+                .position(|s| *s == status)
+                .and_then(|i| Status::ALL.get(i + 1))
+                .copied(),
+        };
+        self.update_matches();
+    }
+
+    /// Pins or unpins the highlighted item, keeping it highlighted
+    fn toggle_pin(&mut self) -> color_eyre::Result<()> {
+        let Some(item) = self.highlighted_item() else {
+            return Ok(());
+        };
+        let item_path = self.item_path(item);
+        let item_index = self.matches[self.list_state.selected().unwrap_or_default()];
+
+        self.pins.toggle(item_path)?;
+        self.update_matches();
+
+        let position = self.matches.iter().position(|i| *i == item_index);
+        self.list_state.select(position);
+
+        Ok(())
+    }
+
+    /// Marks the highlighted item, or unmarks it if it was
+    fn toggle_mark(&mut self) {
+        if !self.options.multi {
+            self.message = Some("marking needs --multi".to_string());
+            return;
+        }
+        let Some(item) = self.highlighted_item() else {
+            return;
+        };
+        if !item.kind.is_leaf() {
+            self.message = Some(format!("can't mark a {}", item.kind.label()));
+            return;
+        }
+
+        let index = self.matches[self.list_state.selected().unwrap_or_default()];
+        self.marks.toggle(self.selection_for(index));
+    }
+
+    /// Marks or unmarks every leaf item matching the query, depending on
+    /// whether `mark` says so given whether it's marked already.
+    /// Items matching no more, or at other levels, are left alone.
+    fn mark_matches(&mut self, mark: impl Fn(bool) -> bool) {
+        if !self.options.multi {
+            self.message = Some("marking needs --multi".to_string());
+            return;
+        }
+
+        let leaves: Vec<Selection> = self
+            .every_match()
+            .filter(|i| self.items[**i].kind.is_leaf())
+            .map(|i| self.selection_for(*i))
+            .collect();
+        for selection in leaves {
+            if mark(self.marks.contains(&selection.path)) {
+                self.marks.mark(selection);
+            } else {
+                self.marks.unmark(&selection.path);
+            }
+        }
+    }
+
+    /// The items matching the query, those collapsed groups leave out last
+    fn every_match(&self) -> impl Iterator<Item = &usize> {
+        self.matches.iter().chain(&self.hidden_matches)
+    }
+
+    /// The item at the given index in the items, as it would be accepted
+    fn selection_for(&self, index: usize) -> Selection {
+        let item = &self.items[index];
+        Selection {
+            path: self.path_to(item),
+            score: self.scores.get(&index).copied(),
+            metadata: item.metadata.clone(),
+        }
+    }
+
+    fn unmark_all(&mut self) {
+        self.marks.clear();
+    }
+
+    fn open_marks_panel(&mut self) {
+        if !self.options.multi {
+            self.message = Some("marking needs --multi".to_string());
+            return;
+        }
+        let panel = MarksPanel::new(&self.marks, self.theme.highlight);
+        self.marks_panel = Some(
+            panel
+                .with_borders(self.borders())
+                .with_key_style(self.theme.key),
+        );
+    }
+
+    /// Accepts everything marked, if anything is
+    fn accept_marks(&mut self) {
+        if self.marks.is_empty() {
+            return;
+        }
+        let selections: Vec<Selection> = self.marks.selections().cloned().collect();
+        self.marks.clear();
+        for selection in selections {
+            self.accept_selection(selection);
+        }
+    }
+
+    /// Takes note of an accepted leaf item, then either exits or,
+    /// when keeping open, hands it to `--exec` and clears the query for the next one
+    fn accept_selection(&mut self, selection: Selection) {
+        if let Err(e) = self.history.record(&selection.path) {
+            tracing::warn!("Could not add {selection} to the history: {e:#}");
+        }
+        self.recent = self.history.frecent(RECENT_ROWS, history::now());
+
+        if !self.options.keep_open {
+            self.accepted.push(selection);
+            self.exit();
+            return;
+        }
+
+        self.message = Some(match &self.options.exec {
+            Some(command) => match self.run_in_background(command, &selection.path) {
+                Ok(()) => format!("ran command for {selection}"),
+                Err(e) => format!("can't run command for {selection}: {e}"),
+            },
+            None => format!("accepted {selection}"),
+        });
+        self.edit_query(String::clear);
+        self.accepted.push(selection);
+    }
+
+    /// Starts a command like `--exec` for the item at `path`, without waiting
+    /// for it since the terminal is the app's
+    fn run_in_background(&self, command: &str, path: &[String]) -> std::io::Result<()> {
+        let name = path.last().map_or("", String::as_str);
+        let command = preview::expand_command(command, name, &self.location_string(path));
+        let child = process::Command::new("sh")
+            .arg("-c")
+            .arg(&command)
+            .stdin(process::Stdio::null())
+            .stdout(process::Stdio::null())
+            .stderr(process::Stdio::null())
+            .spawn();
+        let mut child = child?;
+        tracing::info!("Running {command:?}");
+        // Reaped in the background, nobody is waiting for the result
+        std::thread::spawn(move || child.wait());
+        Ok(())
+    }
+
+    fn toggle_match_mode(&mut self) {
+        self.match_mode = match self.match_mode {
+            MatchMode::Fuzzy => MatchMode::Regex,
+            MatchMode::Regex => MatchMode::Fuzzy,
+        };
+        self.update_matches();
+    }
+
+    fn toggle_archived(&mut self) {
+        self.show_archived = !self.show_archived;
+        self.update_matches();
+    }
+
+    fn cycle_sort_mode(&mut self) {
+        self.sort_mode = self.sort_mode.next();
+        self.update_matches();
+    }
+
+    /// How many rows a move by `delta` goes, faster while the key is held
+    fn accelerated(&mut self, delta: isize) -> isize {
+        match &mut self.options.acceleration {
+            Some(acceleration) if !self.replaying => acceleration.rows(delta, Instant::now()),
+            _ => delta,
+        }
+    }
+
+    fn move_highlight(&mut self, delta: isize) {
+        let Some(selected) = self.list_state.selected() else {
+            return;
+        };
+        let last = self.matches.len().saturating_sub(1);
+        let new_index = selected.saturating_add_signed(delta).min(last);
+        self.list_state.select(Some(new_index));
+        self.name_scroll = 0;
+    }
+
+    /// What the borders of blocks are drawn with
+    fn borders(&self) -> border::Set {
+        text::borders(self.options.unicode, border::PLAIN)
+    }
+
+    /// Whether the paths accepted lately are offered above the results
+    fn shows_recent(&self) -> bool {
+        !self.recent.is_empty() && self.path.is_empty() && self.query.is_empty()
+    }
+
+    /// The index of the recent path highlighted instead of a result, if any
+    fn highlighted_recent(&self) -> Option<usize> {
+        self.recent_selected.filter(|_| self.shows_recent())
+    }
+
+    /// Moves the highlight through the recent paths, or from the first result up
+    /// into them. Returns whether it did, rather than moving through the results.
+    fn move_through_recent(&mut self, delta: isize) -> bool {
+        if !self.shows_recent() {
+            return false;
+        }
+        match self.recent_selected {
+            Some(index) => {
+                let index = index.saturating_add_signed(delta);
+                if index < self.recent.len() {
+                    self.recent_selected = Some(index);
+                } else {
+                    // Down from the last one goes on to the first result
+                    self.recent_selected = None;
+                    if !self.matches.is_empty() {
+                        self.list_state.select(Some(0));
+                    }
+                }
+                true
+            }
+            None if delta < 0 && self.list_state.selected().unwrap_or(0) == 0 => {
+                self.recent_selected = Some(self.recent.len() - 1);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Goes to the level of a recent path with it highlighted, so that Enter
+    /// again accepts it
+    fn go_to_recent(&mut self, index: usize) -> color_eyre::Result<()> {
+        let Some((name, parent)) = self.recent[index].split_last() else {
+            return Ok(());
+        };
+        let (name, parent) = (name.clone(), parent.to_vec());
+        let mut levels = vec![None; parent.len()];
+        if let Some(top) = levels.first_mut() {
+            *top = Some(self.level_state());
+        }
+        self.recent_selected = None;
+        self.path = parent;
+        self.levels = levels;
+
+        // It may have been renamed or removed since
+        if let Err(e) = self.load_items() {
+            self.message = Some(format!("can't go to {}: {e}", self.path.join("/")));
+            self.path.clear();
+            self.levels.clear();
+            return self.load_items();
+        }
+        self.restore_level(LevelState {
+            highlighted: Some(name),
+            ..LevelState::default()
+        });
+        Ok(())
+    }
+
+    /// Accepts an item from the history again, as the backend has it now.
+    /// It may have been renamed or removed since.
+    fn accept_from_history(&mut self, path: Vec<String>) {
+        let Some((name, parent)) = path.split_last() else {
+            return;
+        };
+        let item = match self.backend.children(parent) {
+            Ok(items) => items
+                .into_iter()
+                .find(|item| item.name == *name && item.kind.is_leaf()),
+            Err(e) => {
+                self.message = Some(format!("can't list {}: {e:#}", parent.join("/")));
+                return;
+            }
+        };
+        match item {
+            Some(item) => self.accept_selection(Selection {
+                path,
+                score: None,
+                metadata: item.metadata,
+            }),
+            None => self.message = Some(format!("{} doesn't exist anymore", path.join("/"))),
+        }
+    }
+
+    /// Scrolls the highlighted name sideways, to read names too long for their row
+    fn scroll_name(&mut self, delta: isize) {
+        let Some(item) = self.highlighted_item() else {
+            return;
+        };
+        // Leave room for the ellipsis marking the hidden start of the name
+        let room = self
+            .name_width
+            .saturating_sub(text::width(&self.options.ellipsis));
+        let max_scroll = text::width(&item.name).saturating_sub(room);
+        self.name_scroll = self
+            .name_scroll
+            .saturating_add_signed(delta)
+            .min(max_scroll);
+    }
+
+    /// Shows the tab `delta` tabs away from the current one, wrapping around
+    fn cycle_tab(&mut self, delta: isize) -> color_eyre::Result<()> {
+        let current = Tab::ALL
+            .iter()
+            .position(|tab| *tab == self.tab)
+            .unwrap_or(0);
+        let count = Tab::ALL.len() as isize;
+        let index = (current as isize + delta).rem_euclid(count) as usize;
+        self.switch_tab(index)
+    }
+
+    /// Shows another tab, putting it back the way it was left
+    fn switch_tab(&mut self, index: usize) -> color_eyre::Result<()> {
+        let Some(tab) = Tab::ALL.get(index).copied() else {
+            return Ok(());
+        };
+        if !self.options.tabs || tab == self.tab {
+            return Ok(());
+        }
+
+        let current = Tab::ALL.iter().position(|t| *t == self.tab).unwrap_or(0);
+        self.tab_states[current] = TabState {
+            path: std::mem::take(&mut self.path),
+            levels: std::mem::take(&mut self.levels),
+            query: std::mem::take(&mut self.query),
+            query_undo: std::mem::take(&mut self.query_undo),
+            selected: self.list_state.selected(),
+        };
+
+        self.tab = tab;
+        let state = std::mem::take(&mut self.tab_states[index]);
+        self.path = state.path;
+        self.levels = state.levels;
+        if let Err(e) = self.load_items() {
+            tracing::warn!("Could not go back to {:?}: {e:#}", self.path);
+            self.path.clear();
+            self.levels.clear();
+            self.load_items()?;
+        }
+        self.query = state.query;
+        self.query_undo = state.query_undo;
+        self.update_matches();
+        if let Some(selected) = state.selected {
+            if selected < self.matches.len() {
+                self.list_state.select(Some(selected));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Does what was picked in the item menu to the highlighted item
+    fn run_menu_entry(&mut self, entry: MenuEntry) -> color_eyre::Result<()> {
+        let Some(item) = self.highlighted_item() else {
+            return Ok(());
+        };
+        let path = self.path_to(item);
+
+        match entry {
+            MenuEntry::CopyPath => {
+                let location = self.location_string(&path);
+                self.message = Some(format!("copied {location}"));
+                self.terminal_request = Some(TerminalRequest::CopyToClipboard(location));
+            }
+            MenuEntry::Open => self.request_open(),
+            MenuEntry::TogglePin => self.toggle_pin()?,
+            MenuEntry::ShowDetails => self.show_details(),
+            MenuEntry::Run { label, command } => {
+                self.message = Some(match self.run_in_background(&command, &path) {
+                    Ok(()) => format!("ran {label} for {}", path.join("/")),
+                    Err(e) => format!("can't run {label}: {e}"),
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Shows what the item knows about itself, then what the backend can tell
+    fn show_details(&mut self) {
+        let Some(item) = self.highlighted_item() else {
+            return;
+        };
+
+        let mut rows = vec![("kind".to_string(), item.kind.label().to_string())];
+        if let Some(status) = item.status {
+            rows.push(("status".to_string(), status.label().to_string()));
+        }
+        if let Some(modified) = item.modified {
+            rows.push(("modified".to_string(), details::format_time(modified)));
+        }
+        rows.extend(item.metadata.clone());
+
+        let popup = match self.backend.details(&self.path_to(item)) {
+            Ok(details) => {
+                rows.extend(details);
+                DetailsPopup::new(&item.name, rows)
+            }
+            Err(e) => DetailsPopup::new(&item.name, rows).with_error(format!("{e:#}")),
+        };
+        self.details = Some(
+            popup
+                .with_key_style(self.theme.title)
+                .with_borders(self.borders()),
+        );
+    }
+
+    /// Asks for the highlighted item to be opened, if it lives on disk
+    fn request_open(&mut self) {
+        let Some(item) = self.highlighted_item() else {
+            return;
+        };
+        let path = self.path_to(item);
+        match self.backend.location(&path) {
+            Some(location) => self.terminal_request = Some(TerminalRequest::Open(location)),
+            None => self.message = Some(format!("{} isn't on disk", item.name)),
+        }
+    }
+
+    /// Suspends the app while `$EDITOR` or the file manager has `location` open.
+    /// Everything is as it was when coming back.
+    fn open_externally<B: TerminalBackend>(
+        &mut self,
+        terminal: &mut Terminal<B>,
+        location: &Path,
+    ) -> color_eyre::Result<()> {
+        let mut command = opener::open_command(location, opener::editor().as_deref());
+        tracing::info!("Opening {location:?} with {command:?}");
+
+        tui::suspend(self.options.tui)?;
+        let status = command.status();
+        tui::resume(self.options.tui)?;
+        // Whatever the editor drew is still on screen
+        terminal.clear()?;
+        self.drawn_thumbnail = None;
+
+        self.message = match status {
+            Ok(status) if status.success() => None,
+            Ok(status) => Some(format!("the editor exited with {status}")),
+            Err(e) => Some(format!("can't open {}: {e}", location.display())),
+        };
+        Ok(())
+    }
+
+    /// Writes the results matching the query to the export file, asking first
+    /// when it would be overwritten
+    fn export_csv(&mut self) {
+        let file = &self.options.export_file;
+        if !file.exists() {
+            self.write_csv();
+            return;
+        }
+        let dialog = ConfirmDialog::new(
+            "Overwrite",
+            format!("{} exists already. Overwrite it?", file.display()),
+            PendingAction::Export,
+        );
+        self.confirm = Some(
+            dialog
+                .with_highlight(self.theme.highlight)
+                .with_borders(self.borders()),
+        );
+    }
+
+    fn write_csv(&mut self) {
+        let rows = self.every_match().map(|i| {
+            let item = &self.items[*i];
+            (self.path_to(item), item)
+        });
+        let csv = export::to_csv(rows);
+        let file = &self.options.export_file;
+        // Pages are only fetched as they're needed
+        let partial = if self.has_more_items {
+            ", more aren't loaded yet"
+        } else {
+            ""
+        };
+        self.message = Some(match std::fs::write(file, csv) {
+            Ok(()) => format!(
+                "exported {} results to {}{partial}",
+                self.every_match().count(),
+                file.display()
+            ),
+            Err(e) => format!("can't export to {}: {e}", file.display()),
+        });
+    }
+
+    fn toggle_macro_recording(&mut self) {
+        self.message = if self.macros.toggle() {
+            None
+        } else {
+            Some(format!(
+                "macro recorded, {} inputs",
+                self.macros.recorded().len()
+            ))
+        };
+    }
+
+    /// Handles the inputs of the last macro again, e.g. on the next project in
+    /// `--loop` mode
+    fn replay_macro(&mut self) -> color_eyre::Result<()> {
+        let inputs = self.macros.recorded();
+        if inputs.is_empty() {
+            self.message = Some("no macro recorded yet (Alt+Q records one)".to_string());
+        }
+        for input in inputs {
+            if self.should_exit {
+                break;
+            }
+            self.handle_replayed_input(input)?;
+        }
+        Ok(())
+    }
+
+    fn handle_replayed_input(&mut self, input: InputEvent) -> color_eyre::Result<()> {
+        let replaying = std::mem::replace(&mut self.replaying, true);
+        let result = self.handle_input(input);
+        self.replaying = replaying;
+        result
+    }
+
+    /// Lets the terminal select text with the mouse, until the next key press
+    fn start_selecting_text(&mut self) {
+        if !self.options.tui.mouse {
+            self.message = Some("the terminal can select text already, without --mouse".into());
+            return;
+        }
+        self.selecting_text = true;
+        self.terminal_request = Some(TerminalRequest::MouseCapture(false));
+        self.message = Some("select text with the mouse, then press any key".to_string());
+    }
+
+    /// Takes the mouse back after [`Self::start_selecting_text`]. Returns whether
+    /// it did, in which case the key pressed shouldn't do anything else.
+    fn stop_selecting_text(&mut self) -> bool {
+        if !self.selecting_text {
+            return false;
+        }
+        self.selecting_text = false;
+        self.terminal_request = Some(TerminalRequest::MouseCapture(true));
+        self.message = None;
+        true
+    }
+
+    fn toggle_preview(&mut self) {
+        if self.previewer.is_none() && !self.options.thumbnails && !self.options.link_targets {
+            self.message = Some("no preview: start with --preview or --thumbnails".to_string());
+            return;
+        }
+        self.preview_hidden = !self.preview_hidden;
+    }
+
+    /// Fetches the current level again, also forgetting the previews and
+    /// thumbnails shown so far, which may be outdated too
+    fn reload(&mut self) -> color_eyre::Result<()> {
+        if self.retry.is_some() {
+            return self.retry_now();
+        }
+        if let Some(previewer) = &mut self.previewer {
+            previewer.clear();
+        }
+        self.preview_path = None;
+        self.drawn_thumbnail = None;
+        self.refresh()?;
+        self.message = Some(format!("reloaded {} items", self.items.len()));
+
+        Ok(())
+    }
+
+    /// Fetches the items of the current level again, keeping the query
+    /// and the highlighted item when it's still there
+    fn refresh(&mut self) -> color_eyre::Result<()> {
+        let highlighted = self.highlighted_item().map(|item| item.name.clone());
+        let query = std::mem::take(&mut self.query);
+        let query_undo = std::mem::take(&mut self.query_undo);
+        let collapsed = std::mem::take(&mut self.collapsed);
+        self.backend.invalidate(&self.path);
+        self.load_items()?;
+        self.query = query;
+        self.query_undo = query_undo;
+        self.collapsed = collapsed;
+        self.update_matches();
+
+        let index = highlighted.and_then(|name| {
+            self.matches
+                .iter()
+                .position(|i| self.items[*i].name == name)
+        });
+        if index.is_some() {
+            self.list_state.select(index);
+        }
+
+        Ok(())
+    }
+
+    /// Refreshes the items if the watched directory changed,
+    /// and applies the config again if its file changed
+    fn refresh_if_changed(&mut self) -> color_eyre::Result<()> {
+        if self.watcher.as_ref().is_some_and(DirWatcher::changed) {
+            self.refresh()?;
+            self.dirty = true;
+        }
+        if self
+            .config_watcher
+            .as_ref()
+            .is_some_and(FileWatcher::changed)
+        {
+            self.reload_config();
+            self.dirty = true;
+        }
+        Ok(())
+    }
+
+    /// Applies the theme, key bindings and layout of the config again.
+    /// A broken config is reported, and the current settings are kept.
+    fn reload_config(&mut self) {
+        let Some(source) = &self.options.config else {
+            self.message = Some("there is no config to reload".to_string());
+            return;
+        };
+        let loaded = source.load().and_then(|(config, cli)| {
+            let theme = config
+                .theme(cli.theme.unwrap_or_default())?
+                .with_depth(cli.color_depth());
+            Ok((theme, config.keymap()?, config, cli))
+        });
+        let (theme, keymap, config, cli) = match loaded {
+            Ok(loaded) => loaded,
+            Err(e) => {
+                self.message = Some(format!("config not reloaded: {e:#}"));
+                return;
+            }
+        };
+
+        self.theme = theme.clone();
+        self.row_cache.rows.clear();
+        self.options.theme = theme;
+        self.options.keymap = keymap;
+        self.options.item_commands = config.item_commands();
+        self.options.unicode = !cli.no_unicode;
+        self.options.ellipsis = cli.ellipsis();
+        self.options.pointer = cli.pointer();
+        self.options.marker = cli.marker();
+        if self.options.tabs != cli.tabs {
+            self.options.tabs = cli.tabs;
+            self.update_matches();
+        }
+        self.message = Some("reloaded the config".to_string());
+    }
+
+    /// Points the watcher, if any, at where the current level lives
+    fn watch_current_dir(&mut self) {
+        let Some(watcher) = &mut self.watcher else {
+            return;
+        };
+        let dir = self.backend.location(&self.path);
+        if let Err(e) = watcher.watch(dir.as_deref()) {
+            tracing::warn!("Could not watch for changes: {e:#}");
+        }
+    }
+
+    /// Collects the preview output streamed so far, and starts previewing
+    /// the highlighted item if it changed
+    fn update_preview(&mut self) {
+        let Some(previewer) = &mut self.previewer else {
+            return;
+        };
+        self.dirty |= previewer.poll();
+
+        let highlighted = self.highlighted_item().map(|item| item.name.clone());
+        let path = highlighted
+            .as_deref()
+            .map(|name| join_path(&self.path, name));
+        if path == self.preview_path {
+            return;
+        }
+
+        self.preview_scroll = 0;
+        if let (Some(path), Some(name)) = (&path, &highlighted) {
+            let mut item_path = self.path.clone();
+            item_path.push(name.clone());
+            let location = self.location_string(&item_path);
+            if let Some(previewer) = &mut self.previewer {
+                previewer.request(path, name, &location);
+            }
+        }
+        self.preview_path = path;
+        self.dirty = true;
+    }
+
+    fn scroll_preview(&mut self, delta: isize) {
+        let lines = self
+            .previewer
+            .as_ref()
+            .zip(self.preview_path.as_deref())
+            .and_then(|(previewer, path)| previewer.get(path))
+            .map_or(0, |preview| preview.lines.len());
+        self.preview_scroll = self
+            .preview_scroll
+            .saturating_add_signed(delta)
+            .min(lines.saturating_sub(1));
+    }
+
+    /// Descends into the highlighted item, or selects it if it's a leaf
+    fn accept(&mut self) -> color_eyre::Result<()> {
+        // With marks, Enter accepts them rather than the highlighted leaf,
+        // but still descends into levels to mark more
+        let descends = self
+            .highlighted_item()
+            .is_some_and(|item| !item.kind.is_leaf());
+        if !self.marks.is_empty() && !descends {
+            self.accept_marks();
+            return Ok(());
+        }
+
+        if let Some(item) = self.highlighted_item().cloned() {
+            return self.enter_item(item);
+        }
+
+        match self.create_candidate() {
+            Some(name) => self.create_item(&name),
+            None => Ok(()),
+        }
+    }
+
+    /// The name of the entity we offer to create, when the query matches nothing
+    fn create_candidate(&self) -> Option<String> {
+        if !self.matches.is_empty() || self.scan.is_some() {
+            return None;
+        }
+
+        let query = Query::parse(&self.query);
+        if !query.facets.is_empty() || query.text.is_empty() {
+            return None;
+        }
+
+        Some(query.text)
+    }
+
+    /// Asks for confirmation before creating a new entity at the current level
+    fn create_item(&mut self, name: &str) -> color_eyre::Result<()> {
+        if let Err(reason) = validate_name(name) {
+            self.message = Some(format!("can't create '{name}': {reason}"));
+            return Ok(());
+        }
+
+        let kind = ItemKind::for_depth(self.path.len());
+        let location = if self.path.is_empty() {
+            "the top level".to_string()
+        } else {
+            format!("'{}'", self.path.join("/"))
+        };
+        let dialog = ConfirmDialog::new(
+            "Create",
+            format!("Create {} '{name}' under {location}?", kind.label()),
+            PendingAction::Create(name.to_string()),
+        );
+        self.confirm = Some(
+            dialog
+                .with_highlight(self.theme.highlight)
+                .with_borders(self.borders()),
+        );
+
+        Ok(())
+    }
+
+    fn run_pending_action(&mut self, action: PendingAction) -> color_eyre::Result<()> {
+        match action {
+            PendingAction::Create(name) => self.create_confirmed_item(&name),
+            PendingAction::Export => {
+                self.write_csv();
+                Ok(())
+            }
+        }
+    }
+
+    /// Asks the backend to create a new entity at the current level, then enters it
+    fn create_confirmed_item(&mut self, name: &str) -> color_eyre::Result<()> {
+        match self.backend.create(&self.path, name) {
+            Ok(item) => {
+                tracing::info!("Created {:?}", join_path(&self.path, &item.name));
+                self.backend.invalidate(&self.path);
+                self.enter_item(item)
+            }
+            Err(e) => {
+                self.message = Some(format!("can't create '{name}': {e:#}"));
+                Ok(())
+            }
+        }
+    }
+
+    fn enter_item(&mut self, item: Item) -> color_eyre::Result<()> {
+        if !item.kind.is_leaf() {
+            let level = self.level_state();
+            self.levels.push(Some(level));
+        }
+        if item.kind.is_leaf() {
+            let score = self
+                .list_state
+                .selected()
+                .and_then(|index| self.matches.get(index))
+                .and_then(|index| self.scores.get(index))
+                .copied();
+            let selection = Selection {
+                path: self.path_to(&item),
+                score,
+                metadata: item.metadata,
+            };
+            self.accept_selection(selection);
+            return Ok(());
+        }
+        self.path.push(item.name);
+
+        self.load_items()
+    }
+
+    /// Jumps straight back to the top level
+    fn go_to_top(&mut self) -> color_eyre::Result<()> {
+        if self.path.is_empty() {
+            return Ok(());
+        }
+
+        let top = self.levels.first().cloned().flatten();
+        self.path.clear();
+        self.levels.clear();
+        self.load_items()?;
+        if let Some(top) = top {
+            self.restore_level(top);
+        }
+        Ok(())
+    }
+
+    /// Goes back to the parent level, or exits if we're already at the top
+    fn go_up(&mut self) -> color_eyre::Result<()> {
+        if self.path.pop().is_none() {
+            self.exit();
+            return Ok(());
+        }
+
+        self.load_items()?;
+        if let Some(level) = self.levels.pop().flatten() {
+            self.restore_level(level);
+        }
+        Ok(())
+    }
+
+    /// What the current level is showing, to restore it with [`App::restore_level`]
+    fn level_state(&self) -> LevelState {
+        LevelState {
+            query: self.query.clone(),
+            highlighted: self.highlighted_item().map(|item| item.name.clone()),
+            offset: self.list_state.offset(),
+        }
+    }
+
+    /// Puts back the query, highlight and scrolling of a level we're back at.
+    /// When its items are still being scanned or retried, that's done once they're in.
+    fn restore_level(&mut self, level: LevelState) {
+        if self.scan.is_some() || self.retry.is_some() {
+            self.pending_level = Some(level);
+            return;
+        }
+        self.query = level.query;
+        self.update_matches();
+        let index = level.highlighted.and_then(|name| {
+            self.matches
+                .iter()
+                .position(|i| self.items[*i].name == name)
+        });
+        if index.is_some() {
+            self.list_state.select(index);
+            *self.list_state.offset_mut() = level.offset;
+        }
+    }
+
+    fn render_header(&self, area: Rect, buf: &mut Buffer) {
+        let breadcrumb = if self.path.is_empty() {
+            "/".to_string()
+        } else {
+            self.path.join(" / ")
+        };
+
+        Paragraph::new(breadcrumb)
+            .block(
+                Block::default()
+                    .title(Title::from(Span::styled(" cgs-go ", self.theme.title)))
+                    .borders(Borders::ALL)
+                    .border_set(text::borders(self.options.unicode, border::THICK)),
+            )
+            .render(area, buf);
+    }
+
+    fn render_tabs(&self, area: Rect, buf: &mut Buffer) {
+        let selected = Tab::ALL.iter().position(|tab| *tab == self.tab);
+        Tabs::new(Tab::ALL.iter().map(Tab::title))
+            .select(selected.unwrap_or(0))
+            .highlight_style(self.theme.highlight)
+            .divider(if self.options.unicode {
+                symbols::line::VERTICAL
+            } else {
+                "|"
+            })
+            .render(area, buf);
+    }
+
+    fn render_search_bar(&self, area: Rect, buf: &mut Buffer) {
+        let title = match self.match_mode {
+            MatchMode::Fuzzy => " Search ",
+            MatchMode::Regex => " Search (regex) ",
+        };
+        let mut block = Block::default()
+            .title(title)
+            .borders(Borders::ALL)
+            .border_set(self.borders());
+        if let Some(status) = self.status_filter {
+            block = block.title(
+                Title::from(Line::from(vec![
+                    " status: ".into(),
+                    Span::styled(status.label(), self.theme.status_style(status)),
+                    " ".into(),
+                ]))
+                .alignment(Alignment::Right),
+            );
+        }
+
+        if let Some(go_to) = &self.go_to {
+            let line = Line::from(vec![
+                "> :".into(),
+                Span::styled(go_to.digits(), self.theme.query),
+                format!("  go to result 1-{} <Enter>", self.matches.len()).dim(),
+            ]);
+            let block = Block::default()
+                .title(" Go to ")
+                .borders(Borders::ALL)
+                .border_set(self.borders());
+            Paragraph::new(line).block(block).render(area, buf);
+            return;
+        }
+
+        let mut line = Line::from(vec![
+            "> ".into(),
+            Span::styled(self.query.as_str(), self.theme.query),
+        ]);
+        if let Some(error) = &self.query_error {
+            line.push_span("  ");
+            line.push_span(Span::styled(error.as_str(), self.theme.error));
+        }
+
+        Paragraph::new(line).block(block).render(area, buf);
+    }
+
+    fn render_status_bar(&self, area: Rect, buf: &mut Buffer) {
+        let archived = if self.show_archived {
+            "shown"
+        } else {
+            "hidden"
+        };
+        let mut line = Line::from(vec![
+            format!(" {}/{}", self.every_match().count(), self.items.len()).into(),
+            " | sort: ".dim(),
+            self.sort_mode.label().into(),
+            " | archived: ".dim(),
+            archived.into(),
+        ]);
+        if let Some(count) = self.streaming {
+            line.push_span(" | ".dim());
+            line.push_span(format!("reading{} {count} items", self.options.ellipsis));
+        }
+        if let Some(retry) = &self.retry {
+            let wait = retry.at.saturating_duration_since(Instant::now());
+            line.push_span(" | ".dim());
+            line.push_span(Span::styled(
+                format!(
+                    "attempt {}/{} failed, retrying in {}s (Ctrl+R: now)",
+                    retry.attempt,
+                    self.backoff.attempts,
+                    wait.as_secs_f64().ceil()
+                ),
+                self.theme.error,
+            ));
+        }
+        if let Some(written) = self.backend.cached_since(&self.path) {
+            let age = SystemTime::now()
+                .duration_since(written)
+                .unwrap_or_default();
+            line.push_span(" | ".dim());
+            line.push_span(Span::styled(
+                format!("offline (cached {})", details::format_age(age)),
+                self.theme.error,
+            ));
+        }
+        if let Some(notice) = self.backend.notice(&self.path) {
+            line.push_span(" | ".dim());
+            line.push_span(Span::styled(notice, self.theme.error));
+        }
+        if self.options.show_timings {
+            line.push_span(" | ".dim());
+            line.push_span(self.debug_stats.summary(self.items.len()).dim());
+        }
+        if self.macros.is_recording() {
+            line.push_span(" | ".dim());
+            line.push_span(Span::styled(
+                "recording macro (Alt+Q: stop)",
+                self.theme.error,
+            ));
+        }
+        if self.options.multi {
+            line.push_span(" | marked: ".dim());
+            line.push_span(self.marks.len().to_string());
+        }
+        if let Some(message) = &self.message {
+            line.push_span(" | ".dim());
+            line.push_span(message.as_str());
+        }
+
+        line.render(area, buf);
+    }
+
+    fn render_preview(&mut self, area: Rect, buf: &mut Buffer) {
+        let mut block = Block::default()
+            .title(" Preview ")
+            .borders(Borders::ALL)
+            .border_set(self.borders());
+        let mut text_area = block.inner(area);
+
+        let link_target = self
+            .highlighted_item()
+            .and_then(|item| item.link_target.as_ref())
+            .filter(|_| self.options.link_targets && text_area.height > 0);
+        if let Some(target) = link_target {
+            let arrow = if self.options.unicode { "→" } else { "->" };
+            let line = Line::from(format!("{arrow} {}", target.display())).dim();
+            buf.set_line(text_area.x, text_area.y, &line, text_area.width);
+            text_area.y += 1;
+            text_area.height -= 1;
+        }
+
+        if self.options.thumbnails {
+            if let Some(thumbnail) = self.highlighted_item().and_then(|i| i.thumbnail.clone()) {
+                let [image_area, rest] =
+                    Layout::vertical([Constraint::Percentage(50), Constraint::Percentage(50)])
+                        .areas(text_area);
+                text_area = rest;
+
+                if self.options.graphics.can_draw(&thumbnail) {
+                    self.thumbnail = Some((thumbnail, image_area));
+                } else {
+                    Paragraph::new(vec![
+                        Line::from("no image support in this terminal").dim(),
+                        Line::from(thumbnail.to_string_lossy().into_owned()).dim(),
+                    ])
+                    .alignment(Alignment::Center)
+                    .render(image_area, buf);
+                }
+            }
+        }
+
+        let Some(preview) = self
+            .previewer
+            .as_ref()
+            .zip(self.preview_path.as_deref())
+            .and_then(|(previewer, path)| previewer.get(path))
+        else {
+            block.render(area, buf);
+            return;
+        };
+
+        let note = if preview.truncated {
+            Some(Span::styled(" truncated ", self.theme.error))
+        } else if !preview.done {
+            Some(" loading.. ".dim())
+        } else {
+            None
+        };
+        if let Some(note) = note {
+            block = block.title(Title::from(note).alignment(Alignment::Right));
+        }
+
+        let mut lines: Vec<Line> = preview
+            .lines
+            .iter()
+            .skip(self.preview_scroll)
+            .take(text_area.height as usize)
+            .map(|line| line.to_line())
+            .collect();
+        if let Some(error) = &preview.error {
+            lines.push(Line::styled(error.as_str(), self.theme.error));
+        }
+
+        block.render(area, buf);
+        Paragraph::new(lines).render(text_area, buf);
+    }
+
+    fn render_marks_panel(&mut self, area: Rect, buf: &mut Buffer) {
+        if let Some(panel) = &mut self.marks_panel {
+            panel.render(&self.marks, area, buf);
+        }
+    }
+
+    /// Whether the results share the screen with a preview pane
+    fn shows_preview(&self) -> bool {
+        (self.previewer.is_some() || self.options.thumbnails || self.options.link_targets)
+            && !self.preview_hidden
+    }
+
+    /// The paths accepted lately, in a box above the results
+    fn render_recent(&self, area: Rect, buf: &mut Buffer) {
+        let highlighted = self.highlighted_recent();
+        let blank = " ".repeat(text::width(&self.options.pointer));
+        let items: Vec<ListItem> = self
+            .recent
+            .iter()
+            .enumerate()
+            .map(|(index, path)| {
+                let gutter = if highlighted == Some(index) {
+                    &self.options.pointer
+                } else {
+                    &blank
+                };
+                ListItem::new(Line::from(vec![
+                    Span::styled(gutter.clone(), self.theme.pointer),
+                    format!(" {}", path.join("/")).into(),
+                ]))
+            })
+            .collect();
+        let list = List::new(items)
+            .block(
+                Block::default()
+                    .title(" Recent ")
+                    .borders(Borders::ALL)
+                    .border_set(self.borders()),
+            )
+            .highlight_style(self.theme.highlight);
+
+        let mut list_state = ListState::default().with_selected(highlighted);
+        StatefulWidget::render(list, area, buf, &mut list_state);
+    }
+
+    fn render_search_items(&mut self, area: Rect, buf: &mut Buffer) {
+        // Leaving the results at least as much room
+        let recent_height = (self.recent.len() as u16 + 2).min(area.height / 2);
+        let area = if self.shows_recent() {
+            let [recent_area, area] =
+                Layout::vertical([Constraint::Length(recent_height), Constraint::Min(0)])
+                    .areas(area);
+            self.render_recent(recent_area, buf);
+            area
+        } else {
+            area
+        };
+
+        let key = self.theme.key;
+        let mut instructions = Line::from(vec![
+            " Navigate ".into(),
+            Span::styled("<Up/Down>", key),
+            " Scroll ".into(),
+            Span::styled("<Left/Right>", key),
+            " Select ".into(),
+            Span::styled("<Enter>", key),
+            " Back ".into(),
+            Span::styled("<Esc>", key),
+            " Top ".into(),
+            Span::styled("<Ctrl+G>", key),
+            " Jump ".into(),
+            Span::styled("<Ctrl+J>", key),
+            " Undo ".into(),
+            Span::styled("<Ctrl+Z>", key),
+            " Regex ".into(),
+            Span::styled("<Ctrl+/>", key),
+            " Status ".into(),
+            Span::styled("<Ctrl+F>", key),
+            " Sort ".into(),
+            Span::styled("<Ctrl+S>", key),
+            " Pin ".into(),
+            Span::styled("<Ctrl+B>", key),
+            " Archived ".into(),
+            Span::styled("<Ctrl+X>", key),
+            " Reload ".into(),
+            Span::styled("<Ctrl+R>", key),
+            " Commands ".into(),
+            Span::styled("<Ctrl+P>", key),
+            " History ".into(),
+            Span::styled("<Alt+H>", key),
+        ]);
+        if self.options.tabs {
+            instructions.push_span(" Tabs ");
+            instructions.push_span(Span::styled("<Ctrl+Left/Right>", key));
+        }
+        if !self.groups.is_empty() {
+            instructions.push_span(" Collapse/Expand ");
+            instructions.push_span(Span::styled("<Left/Right>", key));
+        }
+        if self.options.multi {
+            instructions.push_span(" Mark ");
+            instructions.push_span(Span::styled("<Tab>", key));
+            instructions.push_span(" Marked ");
+            instructions.push_span(Span::styled("<Ctrl+L>", key));
+        }
+        if self.previewer.is_some() {
+            instructions.push_span(" Preview ");
+            instructions.push_span(Span::styled("<Shift+Up/Down>", key));
+        }
+        instructions.push_span(" Quit ");
+        instructions.push_span(Span::styled("<Ctrl+C> ", key));
+        let instructions = Title::from(instructions);
+        let block = Block::default()
+            .title(
+                instructions
+                    .alignment(Alignment::Center)
+                    .position(Position::Bottom),
+            )
+            .borders(Borders::ALL)
+            .border_set(self.borders());
+
+        if let Some(scan) = &self.scan {
+            let inner = block.inner(area);
+            block.render(area, buf);
+            let gauge_area = Rect {
+                y: inner.y + inner.height / 2,
+                height: inner.height.min(1),
+                ..inner
+            };
+            Gauge::default()
+                .ratio(scan.ratio())
+                .label(format!("scanned {}/{} entries", scan.scanned, scan.total))
+                .gauge_style(self.theme.highlight)
+                .render(gauge_area, buf);
+            // The gauge is filled with blocks, whatever the terminal can draw
+            if !self.options.unicode && gauge_area.height > 0 {
+                for x in gauge_area.left()..gauge_area.right() {
+                    let cell = buf.get_mut(x, gauge_area.y);
+                    if cell.symbol() == symbols::block::FULL {
+                        cell.set_symbol("#");
+                    }
+                }
+            }
+            return;
+        }
+
+        // Borders, pointer, mark and pin markers, icon and the spaces in between
+        let row_width = area.width.saturating_sub(2) as usize;
+        let prefix_width = text::width(&self.options.pointer)
+            + text::width(&self.options.marker)
+            + 3
+            + if self.options.unicode { 2 } else { 4 };
+
+        let highlighted = self
+            .list_state
+            .selected()
+            .filter(|_| self.highlighted_recent().is_none());
+        self.name_width = row_width.saturating_sub(prefix_width);
+
+        // Rows shown after the results
+        let mut selected = highlighted.map(|index| self.match_row(index));
+        let mut extra_rows = Vec::new();
+        if let Some(name) = self.create_candidate() {
+            // Offer to create what the user typed, when nothing matches
+            let row = match validate_name(&name) {
+                Ok(()) => Line::styled(format!("  + create '{name}'"), self.theme.create),
+                Err(reason) => Line::styled(
+                    format!("  can't create '{name}': {reason}"),
+                    self.theme.error,
+                ),
+            };
+            extra_rows.push(ListItem::new(row));
+            selected = Some(self.matches.len() + self.groups.len());
+        }
+        if self.has_more_items {
+            let loading = format!("  loading more{}", self.options.ellipsis);
+            extra_rows.push(ListItem::new(Line::from(loading).dim()));
+        }
+
+        // Only build the rows that can be seen, there may be hundreds of thousands
+        let total = self.matches.len() + self.groups.len() + extra_rows.len();
+        let height = area.height.saturating_sub(2) as usize;
+        let mut offset = visible_offset(
+            self.list_state.offset(),
+            selected,
+            height,
+            total,
+            self.options.scroll_off,
+        );
+        // Don't hide the highlighted row under the header stuck on top
+        if selected == Some(offset) && offset > 0 && self.sticky_header(offset).is_some() {
+            offset -= 1;
+        }
+        let start = offset.saturating_sub(LIST_OVERSCAN);
+        let end = (offset + height + LIST_OVERSCAN).min(total);
+        if self.row_cache.marks_revision != self.marks.revision() {
+            self.row_cache.rows.clear();
+            self.row_cache.marks_revision = self.marks.revision();
+        }
+        let mut previous = std::mem::take(&mut self.row_cache.rows);
+        for row in start..end {
+            let ListRow::Match(index) = self.list_row(row) else {
+                continue;
+            };
+            let key = self.row_key(index, highlighted);
+            let row = previous.remove(&key).unwrap_or_else(|| {
+                let row = self.result_row(index, &self.items[key.item], highlighted);
+                owned_line(row)
+            });
+            self.row_cache.rows.insert(key, row);
+        }
+        let items: Vec<ListItem> = (start..end)
+            .map(|row| match self.list_row(row) {
+                ListRow::Header(group) => ListItem::new(self.group_header(&self.groups[group])),
+                ListRow::Match(index) => {
+                    let row = &self.row_cache.rows[&self.row_key(index, highlighted)];
+                    ListItem::new(borrowed_line(row))
+                }
+                ListRow::Extra(index) => extra_rows[index].clone(),
+            })
+            .collect();
+        let mut list_state = ListState::default()
+            .with_offset(offset - start)
+            .with_selected(selected.and_then(|index| index.checked_sub(start)));
+
+        let inner = block.inner(area);
+        let list = List::new(items)
+            .block(block)
+            .highlight_style(self.theme.highlight);
+
+        StatefulWidget::render(list, area, buf, &mut list_state);
+        let offset = start + list_state.offset();
+        *self.list_state.offset_mut() = offset;
+        self.list_height = height;
+
+        // Jump hints and result numbers go over the gutter of their rows, and
+        // numbers past what fits in it over the start of the names
+        let gutter_width = text::width(&self.options.pointer) + text::width(&self.options.marker);
+        let last_number = (offset..offset + inner.height as usize)
+            .filter_map(|row| match self.list_row(row) {
+                ListRow::Match(index) => Some(index + 1),
+                _ => None,
+            })
+            .max()
+            .unwrap_or_default();
+        let number_width = gutter_width.max(last_number.to_string().len());
+        for (y, row) in (inner.y..inner.bottom()).zip(offset..) {
+            let ListRow::Match(index) = self.list_row(row) else {
+                continue;
+            };
+            let hint = match (&self.jump, &self.go_to) {
+                (Some(jump), _) => jump.hint_for(index).map(|(typed, rest)| {
+                    Line::from(vec![
+                        Span::styled(typed.to_string(), self.theme.pointer),
+                        Span::styled(rest.to_string(), self.theme.pointer.reversed().bold()),
+                    ])
+                }),
+                (None, Some(_)) => Some(Line::styled(
+                    format!("{:>number_width$}", index + 1),
+                    self.theme.pointer,
+                )),
+                (None, None) => None,
+            };
+            if let Some(hint) = hint {
+                buf.set_line(inner.x, y, &hint, inner.width);
+            }
+        }
+
+        // The header of the group scrolled through stays on the first row
+        if let Some(group) = self.sticky_header(offset) {
+            let top = Rect {
+                height: inner.height.min(1),
+                ..inner
+            };
+            Clear.render(top, buf);
+            buf.set_line(top.x, top.y, &self.group_header(&group), top.width);
+        }
+    }
+
+    /// The indices of the results on screen, leaving out the one under a sticky header
+    fn visible_matches(&self) -> Vec<usize> {
+        let offset = self.list_state.offset();
+        let first = offset + usize::from(self.sticky_header(offset).is_some());
+        (first..offset + self.list_height)
+            .filter_map(|row| match self.list_row(row) {
+                ListRow::Match(index) => Some(index),
+                ListRow::Header(_) | ListRow::Extra(_) => None,
+            })
+            .collect()
+    }
+
+    /// The group whose header should stick to the top of the results, when
+    /// the first visible row is one of its results rather than its header
+    fn sticky_header(&self, offset: usize) -> Option<Group> {
+        match self.list_row(offset) {
+            ListRow::Match(index) => self
+                .groups
+                .iter()
+                .find(|group| (group.start..group.start + group.len).contains(&index))
+                .copied(),
+            ListRow::Header(_) | ListRow::Extra(_) => None,
+        }
+    }
+
+    fn row_key(&self, index: usize, highlighted: Option<usize>) -> RowKey {
+        RowKey {
+            index,
+            item: self.matches[index],
+            highlighted: highlighted == Some(index),
+            scroll: if highlighted == Some(index) {
+                self.name_scroll
+            } else {
+                0
+            },
+            name_width: self.name_width,
+        }
+    }
+
+    /// The row showing one of the results, at `index` in the list
+    fn result_row<'a>(
+        &'a self,
+        index: usize,
+        item: &'a Item,
+        highlighted: Option<usize>,
+    ) -> Line<'a> {
+        let badge = item.status.map(|status| format!(" [{}]", status.label()));
+        let count = item
+            .child_count
+            .map(|count| format!(" ({})", item.kind.children_label(count)));
+        let name_width = self
+            .name_width
+            .saturating_sub(badge.as_deref().map_or(0, text::width))
+            .saturating_sub(count.as_deref().map_or(0, text::width));
+        let scroll = if highlighted == Some(index) {
+            self.name_scroll
+        } else {
+            0
+        };
+        let name_style = if item.archived {
+            self.theme.archived
+        } else {
+            Style::new()
+        };
+        let name = styled_name(item, scroll, name_width, &self.options.ellipsis, name_style);
+
+        // The gutter: a pointer on the highlighted row, a bar on marked ones
+        let blank = |symbol: &str| " ".repeat(text::width(symbol));
+        let pointer = if highlighted == Some(index) {
+            self.options.pointer.clone()
+        } else {
+            blank(&self.options.pointer)
+        };
+        let marked = self.options.multi && self.marks.contains(&self.path_to(item));
+        let marker = if marked {
+            self.options.marker.clone()
+        } else {
+            blank(&self.options.marker)
+        };
+        let pin_marker = if self.pins.contains(&join_path(&self.path, &item.name)) {
+            pin_icon(self.options.unicode)
+        } else {
+            " "
+        };
+        let mut spans = vec![
+            Span::styled(pointer, self.theme.pointer),
+            Span::styled(marker, self.theme.mark),
+            Span::styled(pin_marker, self.theme.pin),
+            " ".into(),
+            Span::styled(
+                item.kind.icon(self.options.unicode),
+                self.theme.kind_style(item.kind),
+            ),
+            " ".into(),
+        ];
+        spans.extend(name);
+        if let (Some(status), Some(badge)) = (item.status, badge) {
+            spans.push(Span::styled(badge, self.theme.status_style(status)));
+        }
+        if let Some(count) = count {
+            spans.push(count.dim());
+        }
+        Line::from(spans)
+    }
+}
+
+/// Copies what `line` borrows, to keep it around
+fn owned_line(line: Line) -> Line<'static> {
+    let spans: Vec<Span<'static>> = line
+        .spans
+        .into_iter()
+        .map(|span| Span::styled(span.content.into_owned(), span.style))
+        .collect();
+    Line::from(spans).style(line.style)
+}
+
+/// A line borrowing the text of `line`, cheaper to build than a clone
+fn borrowed_line<'a>(line: &'a Line<'static>) -> Line<'a> {
+    let spans: Vec<Span> = line
+        .spans
+        .iter()
+        .map(|span| Span::styled(span.content.as_ref(), span.style))
+        .collect();
+    Line::from(spans).style(line.style)
+}
+
+/// Regex errors span several lines pointing at the culprit: keep the gist
+fn regex_error_summary(error: &regex::Error) -> String {
+    let message = error.to_string();
+    message
+        .lines()
+        .last()
+        .unwrap_or_default()
+        .trim()
+        .to_string()
+}
+
+/// How often the screen is redrawn while a preview is streaming in
+const PREVIEW_REFRESH_INTERVAL: Duration = Duration::from_millis(50);
+
+/// How often the watched directory is checked for changes, while idle
+const WATCH_INTERVAL: Duration = Duration::from_millis(250);
+
+/// How many events already waiting are handled before drawing the next frame
+const MAX_EVENTS_PER_FRAME: usize = 64;
+
+/// How many items are fetched from the backend at a time
+const PAGE_SIZE: usize = 1000;
+
+/// How close to the end of the results the highlight gets before the next page is fetched
+const LOAD_MORE_MARGIN: usize = 20;
+
+/// How many of the paths accepted lately are offered above the results
+const RECENT_ROWS: usize = 5;
+
+/// Rows built above and below the visible ones when drawing the results
+const LIST_OVERSCAN: usize = 5;
+
+/// The index of the first visible row of a list of `total` rows, `height` of
+/// which fit on screen, so that the `selected` one is visible with `scroll_off`
+/// rows around it, like vim's `scrolloff`. Starts from the previous `offset` so
+/// the list doesn't jump around while moving in it.
+fn visible_offset(
+    offset: usize,
+    selected: Option<usize>,
+    height: usize,
+    total: usize,
+    scroll_off: usize,
+) -> usize {
+    let mut offset = offset;
+    if let Some(selected) = selected {
+        // Too much context for the height would leave nowhere for the highlight to go
+        let margin = scroll_off.min(height.saturating_sub(1) / 2);
+        if selected < offset + margin {
+            offset = selected.saturating_sub(margin);
+        } else if height > 0 && selected + margin >= offset + height {
+            offset = selected + margin + 1 - height;
+        }
+    }
+
+    offset.min(total.saturating_sub(height))
+}
+
+/// How many columns Left/Right scroll the highlighted name by
+const NAME_SCROLL_STEP: isize = 4;
+
+/// Clips the name of an item to fit in `max_width` columns, keeping its own styles
+fn styled_name<'a>(
+    item: &'a Item,
+    scroll: usize,
+    max_width: usize,
+    ellipsis: &'a str,
+    base_style: Style,
+) -> Vec<Span<'a>> {
+    let clipped = text::clip_range(&item.name, scroll, max_width, ellipsis);
+    let mut spans = Vec::new();
+    if clipped.leading {
+        spans.push(Span::styled(ellipsis, base_style));
+    }
+
+    // Split the visible part wherever the style changes
+    let visible = clipped.visible;
+    let mut position = visible.start;
+    for (range, style) in &item.name_styles {
+        let start = range.start.clamp(position, visible.end);
+        let end = range.end.clamp(start, visible.end);
+        if start > position {
+            spans.push(Span::styled(&item.name[position..start], base_style));
+        }
+        if end > start {
+            spans.push(Span::styled(
+                &item.name[start..end],
+                base_style.patch(*style),
+            ));
+        }
+        position = position.max(end);
+    }
+    if position < visible.end {
+        spans.push(Span::styled(&item.name[position..visible.end], base_style));
+    }
+
+    if clipped.trailing {
+        spans.push(Span::styled(ellipsis, base_style));
+    }
+    spans
+}
+
+/// Joins a level path and the name of an item living there, e.g. `project/sequence`
+fn join_path(path: &[String], name: &str) -> String {
+    path.iter()
+        .map(String::as_str)
+        .chain([name])
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+fn pin_icon(unicode: bool) -> &'static str {
+    if unicode {
+        "\u{f08d}"
+    } else {
+        "*"
+    }
+}
+
+/// Splits the screen into the header, tab bar, search bar, results and status bar areas.
+/// The header and search bar keep their height, the results get what's left.
+/// The tab bar has no height when there are no tabs.
+fn layout(area: Rect, show_tabs: bool) -> [Rect; 5] {
+    Layout::vertical([
+        Constraint::Length(3),
+        Constraint::Length(if show_tabs { 1 } else { 0 }),
+        Constraint::Length(3),
+        Constraint::Min(0),
+        Constraint::Length(1),
+    ])
+    .areas(area)
+}
+
+/// Below this many columns, the preview pane is hidden to leave room for the results
+const MIN_PREVIEW_WIDTH: u16 = 80;
+
+/// Splits the results area between the results and the preview pane, if there's room
+fn split_preview(items_area: Rect) -> (Rect, Option<Rect>) {
+    if items_area.width < MIN_PREVIEW_WIDTH {
+        return (items_area, None);
+    }
+
+    let [items_area, preview_area] =
+        Layout::horizontal([Constraint::Percentage(50), Constraint::Percentage(50)])
+            .areas(items_area);
+    (items_area, Some(preview_area))
+}
+
+/// Below this many columns or rows the layout collapses, so the UI isn't drawn at all
+const MIN_WIDTH: u16 = 20;
+const MIN_HEIGHT: u16 = 10;
+
+fn is_too_small(area: Rect) -> bool {
+    area.width < MIN_WIDTH || area.height < MIN_HEIGHT
+}
+
+/// Asks for a bigger terminal, in place of the UI. Drawing happens on every
+/// resize, so the UI comes back as soon as there's room again.
+fn render_too_small(area: Rect, buf: &mut Buffer) {
+    let lines = vec![
+        Line::from("Terminal too small").bold(),
+        Line::from(format!("{}x{}", area.width, area.height)),
+        Line::from(format!("need {MIN_WIDTH}x{MIN_HEIGHT}")).dim(),
+    ];
+    let height = (lines.len() as u16).min(area.height);
+    let area = Rect {
+        y: area.y + (area.height - height) / 2,
+        height,
+        ..area
+    };
+
+    Paragraph::new(lines)
+        .alignment(Alignment::Center)
+        .render(area, buf);
+}
+
+impl Widget for &mut App {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        // Set again when the preview pane is drawn with a thumbnail in it
+        self.thumbnail = None;
+        if is_too_small(area) {
+            render_too_small(area, buf);
+            return;
+        }
+
+        let [header_area, tabs_area, search_area, items_area, status_area] =
+            layout(area, self.options.tabs);
+
+        self.render_header(header_area, buf);
+        if self.options.tabs {
+            self.render_tabs(tabs_area, buf);
+        }
+        self.render_search_bar(search_area, buf);
+        match split_preview(items_area) {
+            (items_area, Some(panel_area)) if self.marks_panel.is_some() => {
+                self.render_search_items(items_area, buf);
+                self.render_marks_panel(panel_area, buf);
+            }
+            (items_area, None) if self.marks_panel.is_some() => {
+                self.render_marks_panel(items_area, buf)
+            }
+            (items_area, Some(preview_area)) if self.shows_preview() => {
+                self.render_search_items(items_area, buf);
+                self.render_preview(preview_area, buf);
+            }
+            _ => self.render_search_items(items_area, buf),
+        }
+        self.render_status_bar(status_area, buf);
+
+        if self.show_debug {
+            DebugOverlay {
+                stats: &self.debug_stats,
+                items: self.items.len(),
+                matches: self.matches.len(),
+                borders: self.borders(),
+            }
+            .render(area, buf);
+        }
+        if let Some(palette) = &self.palette {
+            palette.render(area, buf);
+        }
+        if let Some(browser) = &self.history_browser {
+            browser.render(area, buf);
+        }
+        if let Some(menu) = &self.item_menu {
+            menu.render(area, buf);
+        }
+        if let Some(details) = &self.details {
+            details.render(area, buf);
+        }
+        if let Some(dialog) = &self.confirm {
+            dialog.render(area, buf);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::backend::{FsBackend, LineOptions, MockBackend, StdinBackend};
+    use crate::theme::ColorDepth;
+    use crossterm::event::KeyModifiers;
+    use ratatui::style::Color;
+
+    /// The accepted path, like `project/sequence/shot`
+    fn selected(app: &App) -> Option<String> {
+        app.accepted.last().map(Selection::to_string)
+    }
+
+    fn mock_app() -> App {
+        App::new(Box::new(MockBackend), Options::default()).unwrap()
+    }
+
+    fn type_text(app: &mut App, text: &str) {
+        for c in text.chars() {
+            app.handle_key_event(KeyCode::Char(c).into()).unwrap();
+        }
+    }
+
+    /// Polls until the items being streamed in have all arrived
+    fn wait_for_stream(app: &mut App) -> color_eyre::Result<()> {
+        while app.streaming.is_some() {
+            std::thread::sleep(Duration::from_millis(5));
+            app.poll_stream()?;
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_typing_filters_items() {
+        let mut app = mock_app();
+        assert_eq!(app.matches.len(), 8);
+
+        type_text(&mut app, "bee");
+        assert!(app.matches.len() < 8);
+        assert!(app.highlighted_item().unwrap().name.contains("bee"));
+
+        app.handle_key_event(KeyCode::Backspace.into()).unwrap();
+        assert_eq!(app.query, "be");
+
+        // Backspace removes whole graphemes, not just code points
+        type_text(&mut app, "e\u{301}");
+        app.handle_key_event(KeyCode::Backspace.into()).unwrap();
+        assert_eq!(app.query, "be");
+    }
+
+    #[test]
+    fn test_navigate_down_and_select_leaf() -> color_eyre::Result<()> {
+        let mut app = mock_app();
+        type_text(&mut app, "man_vs_bee");
+        app.handle_key_event(KeyCode::Enter.into())?;
+        assert_eq!(app.path, vec!["man_vs_bee"]);
+        assert!(app.query.is_empty());
+
+        app.handle_key_event(KeyCode::Enter.into())?;
+        app.handle_key_event(KeyCode::Down.into())?;
+        app.handle_key_event(KeyCode::Enter.into())?;
+
+        assert!(app.should_exit);
+        assert_eq!(
+            selected(&app).as_deref(),
+            Some("man_vs_bee/sequence_001/sh020")
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_cycle_status_filter() -> color_eyre::Result<()> {
+        let mut app = mock_app();
+        app.handle_key_event(KeyCode::Enter.into())?;
+        app.handle_key_event(KeyCode::Enter.into())?;
+        assert_eq!(app.matches.len(), 5);
+
+        let ctrl_f = KeyEvent::new(KeyCode::Char('f'), KeyModifiers::CONTROL);
+        app.handle_key_event(ctrl_f)?;
+        assert_eq!(app.status_filter, Some(Status::Wip));
+        assert!(app
+            .matches
+            .iter()
+            .all(|i| app.items[*i].status == Some(Status::Wip)));
+        assert!(app.query.is_empty());
+
+        for _ in 0..Status::ALL.len() {
+            app.handle_key_event(ctrl_f)?;
+        }
+        assert_eq!(app.status_filter, None);
+        assert_eq!(app.matches.len(), 5);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_facets_narrow_before_fuzzy_matching() -> color_eyre::Result<()> {
+        let mut app = mock_app();
+        app.handle_key_event(KeyCode::Enter.into())?;
+        app.handle_key_event(KeyCode::Enter.into())?;
+
+        type_text(&mut app, "artist:anna");
+        assert!(!app.matches.is_empty());
+        assert!(app
+            .matches
+            .iter()
+            .all(|i| app.items[*i].metadata["artist"] == "anna"));
+
+        type_text(&mut app, " sh999");
+        assert!(app.matches.is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_cycle_sort_mode() -> color_eyre::Result<()> {
+        let mut app = mock_app();
+        type_text(&mut app, "project");
+        let by_score = app.matches.clone();
+
+        let ctrl_s = KeyEvent::new(KeyCode::Char('s'), KeyModifiers::CONTROL);
+        app.handle_key_event(ctrl_s)?;
+        assert_eq!(app.sort_mode, SortMode::Alphabetical);
+        let names: Vec<&str> = app
+            .matches
+            .iter()
+            .map(|i| app.items[*i].name.as_str())
+            .collect();
+        assert_eq!(names, vec!["project_001", "project_002"]);
+
+        app.handle_key_event(ctrl_s)?;
+        app.handle_key_event(ctrl_s)?;
+        assert_eq!(app.sort_mode, SortMode::Score);
+        assert_eq!(app.matches, by_score);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_pinned_items_stay_on_top() -> color_eyre::Result<()> {
+        let mut app = mock_app();
+        let ctrl_b = KeyEvent::new(KeyCode::Char('b'), KeyModifiers::CONTROL);
+
+        app.handle_key_event(KeyCode::Down.into())?;
+        app.handle_key_event(KeyCode::Down.into())?;
+        let pinned = app.highlighted_item().unwrap().name.clone();
+        app.handle_key_event(ctrl_b)?;
+        assert_eq!(app.highlighted_item().unwrap().name, pinned);
+        assert_eq!(app.items[app.matches[0]].name, pinned);
+
+        // Even when other items score better
+        type_text(&mut app, "_");
+        assert_eq!(app.items[app.matches[0]].name, pinned);
+
+        app.handle_key_event(ctrl_b)?;
+        assert!(!app.pins.contains(&pinned));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_toggle_archived() -> color_eyre::Result<()> {
+        let mut app = mock_app();
+        assert!(app.matches.iter().all(|i| !app.items[*i].archived));
+
+        let ctrl_x = KeyEvent::new(KeyCode::Char('x'), KeyModifiers::CONTROL);
+        app.handle_key_event(ctrl_x)?;
+        assert_eq!(app.matches.len(), 10);
+
+        app.handle_key_event(ctrl_x)?;
+        assert_eq!(app.matches.len(), 8);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_create_when_nothing_matches() -> color_eyre::Result<()> {
+        let mut app = mock_app();
+        app.handle_key_event(KeyCode::Enter.into())?;
+
+        type_text(&mut app, "bad name");
+        assert!(app.matches.is_empty());
+        app.handle_key_event(KeyCode::Enter.into())?;
+        assert_eq!(app.path.len(), 1);
+        assert!(app.message.is_some());
+
+        app.query.clear();
+        type_text(&mut app, "sequence_999");
+        assert_eq!(app.create_candidate().as_deref(), Some("sequence_999"));
+        app.handle_key_event(KeyCode::Enter.into())?;
+
+        // Nothing happens until the user confirms
+        assert!(app.confirm.is_some());
+        app.handle_key_event(KeyCode::Esc.into())?;
+        assert!(app.confirm.is_none());
+        assert_eq!(app.path.len(), 1);
+
+        app.handle_key_event(KeyCode::Enter.into())?;
+        app.handle_key_event(KeyCode::Enter.into())?;
+        assert_eq!(app.path.last().map(String::as_str), Some("sequence_999"));
+        assert!(app.query.is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_start_path_and_go_to_top() -> color_eyre::Result<()> {
+        let options = Options {
+            start_path: vec!["man_vs_bee".into(), "sequence_002".into()],
+            ..Options::default()
+        };
+        let mut app = App::new(Box::new(MockBackend), options)?;
+        assert_eq!(app.path.len(), 2);
+        assert!(app.items.iter().all(|i| i.kind == ItemKind::Shot));
+
+        let ctrl_g = KeyEvent::new(KeyCode::Char('g'), KeyModifiers::CONTROL);
+        app.handle_key_event(ctrl_g)?;
+        assert!(app.path.is_empty());
+        assert_eq!(app.items.len(), 10);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_levels_remember_their_query() -> color_eyre::Result<()> {
+        let mut app = mock_app();
+        type_text(&mut app, "bee");
+        app.handle_key_event(KeyCode::Down.into())?;
+        let project = app.highlighted_item().unwrap().name.clone();
+
+        app.handle_key_event(KeyCode::Enter.into())?;
+        type_text(&mut app, "seq");
+        app.handle_key_event(KeyCode::Down.into())?;
+        app.handle_key_event(KeyCode::Enter.into())?;
+        assert_eq!(app.path.len(), 2);
+
+        app.handle_key_event(KeyCode::Esc.into())?;
+        assert_eq!(app.query, "seq");
+        assert_eq!(app.highlighted_item().unwrap().name, "sequence_002");
+
+        app.handle_key_event(KeyCode::Esc.into())?;
+        assert_eq!(app.query, "bee");
+        assert_eq!(app.highlighted_item().unwrap().name, project);
+
+        // Levels above the starting one start out empty
+        let options = Options {
+            start_path: vec!["man_vs_bee".into()],
+            ..Options::default()
+        };
+        let mut app = App::new(Box::new(MockBackend), options)?;
+        app.handle_key_event(KeyCode::Enter.into())?;
+        app.handle_key_event(KeyEvent::new(KeyCode::Char('g'), KeyModifiers::CONTROL))?;
+        assert!(app.path.is_empty());
+        assert_eq!(app.query, "");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_mark_all_clear_and_invert() -> color_eyre::Result<()> {
+        let ctrl = |c| KeyEvent::new(KeyCode::Char(c), KeyModifiers::CONTROL);
+        let options = Options {
+            start_path: vec!["man_vs_bee".into(), "sequence_001".into()],
+            multi: true,
+            ..Options::default()
+        };
+        let mut app = App::new(Box::new(MockBackend), options)?;
+        let shots = app.matches.len();
+        app.handle_key_event(ctrl('a'))?;
+        assert_eq!(app.marks.len(), shots);
+
+        // Only what matches the query is inverted
+        type_text(&mut app, "sh030");
+        app.handle_key_event(ctrl('t'))?;
+        assert_eq!(app.marks.len(), shots - 1);
+        app.handle_key_event(ctrl('d'))?;
+        assert!(app.marks.is_empty());
+        app.handle_key_event(ctrl('t'))?;
+        assert_eq!(app.marks.len(), 1);
+
+        // Marks survive changing the query, and are what gets accepted
+        app.handle_key_event(ctrl('u'))?;
+        app.handle_key_event(KeyCode::Tab.into())?;
+        let screen = app.run_scripted(Vec::new())?.screen;
+        let status_bar: String = (0..screen.area.width)
+            .map(|x| screen.get(x, screen.area.height - 1).symbol())
+            .collect();
+        assert!(status_bar.contains("marked: 2"));
+
+        app.handle_key_event(KeyCode::Enter.into())?;
+        let accepted: Vec<String> = app.accepted.iter().map(Selection::to_string).collect();
+        assert_eq!(
+            accepted,
+            [
+                "man_vs_bee/sequence_001/sh010",
+                "man_vs_bee/sequence_001/sh030"
+            ]
+        );
+
+        // Without --multi, nothing gets marked
+        let mut app = mock_app();
+        app.handle_key_event(ctrl('a'))?;
+        assert!(app.marks.is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_rows_are_drawn_again_when_marked() -> color_eyre::Result<()> {
+        let options = Options {
+            start_path: vec!["man_vs_bee".into(), "sequence_001".into()],
+            multi: true,
+            ..Options::default()
+        };
+        let mut app = App::new(Box::new(MockBackend), options)?;
+        app.run_scripted(Vec::new())?;
+        assert_eq!(app.row_cache.rows.len(), app.matches.len());
+
+        app.handle_key_event(KeyEvent::new(KeyCode::Char('a'), KeyModifiers::CONTROL))?;
+        let screen = app.run_scripted(Vec::new())?.screen;
+        let row = (0..screen.area.height)
+            .map(|y| {
+                (0..screen.area.width)
+                    .map(|x| screen.get(x, y).symbol())
+                    .collect::<String>()
+            })
+            .find(|row| row.contains("sh010"))
+            .unwrap();
+        assert!(row.contains(&app.options.marker), "{row}");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_unmark_from_the_marks_panel() -> color_eyre::Result<()> {
+        let ctrl = |c| KeyEvent::new(KeyCode::Char(c), KeyModifiers::CONTROL);
+        let options = Options {
+            start_path: vec!["man_vs_bee".into(), "sequence_001".into()],
+            multi: true,
+            ..Options::default()
+        };
+        let mut app = App::new(Box::new(MockBackend), options)?;
+        app.handle_key_event(KeyCode::Tab.into())?;
+        app.handle_key_event(KeyCode::Tab.into())?;
+        // Marks are kept across levels
+        app.handle_key_event(KeyCode::Esc.into())?;
+        type_text(&mut app, "sequence_002");
+        app.handle_key_event(KeyCode::Enter.into())?;
+        app.handle_key_event(KeyCode::Tab.into())?;
+        assert_eq!(app.marks.len(), 3);
+
+        app.handle_key_event(ctrl('l'))?;
+        let screen = app.run_scripted(Vec::new())?.screen;
+        let screen: String = screen.content.iter().map(|c| c.symbol()).collect();
+        assert!(screen.contains("Marked (3)"));
+
+        // Typing doesn't reach the query while the panel is open
+        type_text(&mut app, "x");
+        app.handle_key_event(KeyCode::Tab.into())?;
+        assert_eq!(app.query, "");
+        assert_eq!(app.marks.len(), 2);
+        app.handle_key_event(KeyCode::Enter.into())?;
+        assert_eq!(app.accepted.len(), 2);
+        assert!(app.should_exit);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_loop_keeps_accepting() -> color_eyre::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let log = dir.path().join("accepted.txt");
+        let options = Options {
+            start_path: vec!["man_vs_bee".into(), "sequence_001".into()],
+            keep_open: true,
+            exec: Some(format!("echo {{name}} >> '{}'", log.display())),
+            ..Options::default()
+        };
+        let mut app = App::new(Box::new(MockBackend), options)?;
+        type_text(&mut app, "sh020");
+        app.handle_key_event(KeyCode::Enter.into())?;
+        assert!(!app.should_exit);
+        assert_eq!(app.query, "");
+        assert_eq!(app.path, ["man_vs_bee", "sequence_001"]);
+
+        type_text(&mut app, "sh040");
+        app.handle_key_event(KeyCode::Enter.into())?;
+        app.handle_key_event(KeyEvent::new(KeyCode::Char('c'), KeyModifiers::CONTROL))?;
+        let Outcome::Accepted(selections) = app.outcome() else {
+            panic!("expected selections");
+        };
+        let accepted: Vec<String> = selections.iter().map(Selection::to_string).collect();
+        assert_eq!(
+            accepted,
+            [
+                "man_vs_bee/sequence_001/sh020",
+                "man_vs_bee/sequence_001/sh040"
+            ]
+        );
+
+        // The commands run in the background
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while std::fs::read_to_string(&log)
+            .unwrap_or_default()
+            .lines()
+            .count()
+            < 2
+        {
+            assert!(Instant::now() < deadline, "--exec didn't run");
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        let mut names: Vec<String> = std::fs::read_to_string(&log)?
+            .lines()
+            .map(String::from)
+            .collect();
+        names.sort();
+        assert_eq!(names, ["sh020", "sh040"]);
+
+        Ok(())
+    }
+
+    /// Times out listing the top level a few times before working
+    struct FlakyBackend {
+        failures: std::cell::Cell<u32>,
+    }
+
+    impl Backend for FlakyBackend {
+        fn children(&self, path: &[String]) -> color_eyre::Result<Vec<Item>> {
+            if path.is_empty() && self.failures.get() > 0 {
+                self.failures.set(self.failures.get() - 1);
+                let timeout = std::io::Error::from(std::io::ErrorKind::TimedOut);
+                return Err(timeout).wrap_err("Failed to list the projects");
+            }
+            MockBackend.children(path)
+        }
+    }
+
+    #[test]
+    fn test_transient_errors_are_retried() -> color_eyre::Result<()> {
+        let flaky = |failures| FlakyBackend {
+            failures: std::cell::Cell::new(failures),
+        };
+        let mut app = App::new(Box::new(flaky(2)), Options::default())?;
+        assert!(app.matches.is_empty());
+        assert_eq!(app.retry.as_ref().map(|r| r.attempt), Some(1));
+
+        // Retrying right away doesn't wait for the backoff
+        let ctrl_r = KeyEvent::new(KeyCode::Char('r'), KeyModifiers::CONTROL);
+        app.handle_key_event(ctrl_r)?;
+        assert_eq!(app.retry.as_ref().map(|r| r.attempt), Some(2));
+        app.handle_key_event(ctrl_r)?;
+        assert!(app.retry.is_none());
+        assert_eq!(app.matches.len(), 8);
+
+        let mut app = App::new(Box::new(flaky(10)), Options::default())?;
+        for _ in 1..app.backoff.attempts {
+            app.handle_key_event(ctrl_r)?;
+        }
+        assert!(app.retry.is_none());
+        assert!(app
+            .message
+            .as_deref()
+            .unwrap()
+            .starts_with("gave up after 5 attempts"));
+
+        Ok(())
+    }
+
+    /// Times out fetching the pages after the first one a few times
+    struct FlakyPages {
+        lines: StdinBackend,
+        failures: std::cell::Cell<u32>,
+    }
+
+    impl Backend for FlakyPages {
+        fn children(&self, path: &[String]) -> color_eyre::Result<Vec<Item>> {
+            self.lines.children(path)
+        }
+
+        fn fetch(
+            &self,
+            path: &[String],
+            offset: usize,
+            limit: usize,
+        ) -> color_eyre::Result<Vec<Item>> {
+            if offset > 0 && self.failures.get() > 0 {
+                self.failures.set(self.failures.get() - 1);
+                let timeout = std::io::Error::from(std::io::ErrorKind::TimedOut);
+                return Err(timeout).wrap_err("Failed to list more lines");
+            }
+            self.lines.fetch(path, offset, limit)
+        }
+    }
+
+    #[test]
+    fn test_later_pages_are_retried() -> color_eyre::Result<()> {
+        let lines: String = (0..1500).map(|i| format!("line_{i:04}\n")).collect();
+        let backend = FlakyPages {
+            lines: StdinBackend::from_reader(lines.as_bytes(), LineOptions::default())?,
+            failures: std::cell::Cell::new(1),
+        };
+        let mut app = App::new(Box::new(backend), Options::default())?;
+        app.list_state.select(Some(PAGE_SIZE - LOAD_MORE_MARGIN));
+        app.handle_key_event(KeyCode::Down.into())?;
+        assert_eq!(app.retry.as_ref().map(|r| r.attempt), Some(1));
+        assert_eq!(app.items.len(), PAGE_SIZE);
+
+        // The pages already there stay, and so does the highlight
+        let ctrl_r = KeyEvent::new(KeyCode::Char('r'), KeyModifiers::CONTROL);
+        app.handle_key_event(ctrl_r)?;
+        assert!(app.retry.is_none());
+        assert_eq!(app.items.len(), 1500);
+        assert_eq!(
+            app.list_state.selected(),
+            Some(PAGE_SIZE - LOAD_MORE_MARGIN + 1)
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_ascii_only() -> color_eyre::Result<()> {
+        let options = Options {
+            unicode: false,
+            tabs: true,
+            ..Options::default()
+        };
+        let mut app = App::new(Box::new(MockBackend), options.clone())?;
+        app.handle_key_event(KeyEvent::new(KeyCode::Char('p'), KeyModifiers::CONTROL))?;
+        assert!(app.palette.is_some());
+        let screen = app.run_scripted(Vec::new())?.screen;
+        assert_eq!(screen.get(0, 0).symbol(), "+");
+        assert!(screen.content.iter().all(|cell| cell.symbol().is_ascii()));
+
+        // What the items say is shown as it is
+        let backend =
+            StdinBackend::from_reader("sh010 │ anna\n".as_bytes(), LineOptions::default())?;
+        let mut app = App::new(Box::new(backend), options)?;
+        let screen = app.run_scripted(Vec::new())?.screen;
+        let screen: String = screen.content.iter().map(|c| c.symbol()).collect();
+        assert!(screen.contains("sh010 │ anna"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_pointer_in_the_gutter() -> color_eyre::Result<()> {
+        let options = Options {
+            pointer: "->".to_string(),
+            ..Options::default()
+        };
+        let mut app = App::new(Box::new(MockBackend), options)?;
+        app.handle_key_event(KeyCode::Down.into())?;
+        let screen = app.run_scripted(Vec::new())?.screen;
+        let gutters: Vec<String> = (0..screen.area.height)
+            .map(|y| (1..3).map(|x| screen.get(x, y).symbol()).collect())
+            .collect();
+        assert_eq!(gutters.iter().filter(|gutter| *gutter == "->").count(), 1);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_group_headers_stick_while_scrolling() -> color_eyre::Result<()> {
+        let options = Options {
+            start_path: vec!["man_vs_bee".into()],
+            group: true,
+            ..Options::default()
+        };
+        let mut app = App::new(Box::new(MockBackend), options)?;
+        let kinds: Vec<ItemKind> = app.groups.iter().map(|group| group.kind).collect();
+        assert_eq!(
+            kinds,
+            [ItemKind::Sequence, ItemKind::Asset, ItemKind::Render]
+        );
+        assert_eq!(app.list_row(0), ListRow::Header(0));
+        assert_eq!(app.list_row(1), ListRow::Match(0));
+        assert_eq!(app.match_row(4), 6);
+
+        let mut events = vec![AppEvent::Resize(80, 12)];
+        events.extend([
+            KeyCode::Down.into(),
+            KeyCode::Down.into(),
+            KeyCode::Down.into(),
+        ]);
+        let screen = app.run_scripted(events)?.screen;
+        let lines: Vec<String> = (0..screen.area.height)
+            .map(|y| {
+                (0..screen.area.width)
+                    .map(|x| screen.get(x, y).symbol())
+                    .collect()
+            })
+            .collect();
+        // The first sequences scrolled away, but not their header
+        assert!(!lines.iter().any(|line| line.contains("sequence_001")));
+        let header = lines.iter().position(|line| line.contains("sequences (4)"));
+        let highlighted = lines
+            .iter()
+            .position(|line| line.contains("sequence_éclair"));
+        assert!(header.is_some_and(|header| header + 2 == highlighted.unwrap()));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_collapse_and_expand_groups() -> color_eyre::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let options = Options {
+            start_path: vec!["man_vs_bee".into()],
+            group: true,
+            export_file: dir.path().join("results.csv"),
+            ..Options::default()
+        };
+        let mut app = App::new(Box::new(MockBackend), options)?;
+        let total = app.matches.len();
+        app.handle_key_event(KeyCode::Down.into())?;
+        app.handle_key_event(KeyCode::Left.into())?;
+        assert_eq!(app.matches.len(), total - 3);
+        assert_eq!(app.groups[0].hidden, 3);
+        assert_eq!(app.groups[1].start, 1);
+        assert_eq!(app.list_state.selected(), Some(0));
+
+        // Moving to another group and back, the first one stays collapsed
+        app.handle_key_event(KeyCode::Down.into())?;
+        app.handle_key_event(KeyCode::Up.into())?;
+        let screen = app.run_scripted(Vec::new())?.screen;
+        let lines: Vec<String> = (0..screen.area.height)
+            .map(|y| {
+                (0..screen.area.width)
+                    .map(|x| screen.get(x, y).symbol())
+                    .collect()
+            })
+            .collect();
+        assert!(lines
+            .iter()
+            .any(|line| line.contains("sequences (4) 3 more hidden")));
+
+        app.handle_key_event(KeyCode::Right.into())?;
+        assert_eq!(app.matches.len(), total);
+        assert_eq!(app.groups[0].hidden, 0);
+        assert_eq!(app.highlighted_item().unwrap().name, "sequence_001");
+
+        // What's collapsed is still exported
+        app.handle_key_event(KeyCode::Left.into())?;
+        let alt_e = KeyEvent::new(KeyCode::Char('e'), KeyModifiers::ALT);
+        app.handle_key_event(alt_e)?;
+        let csv = std::fs::read_to_string(dir.path().join("results.csv"))?;
+        assert_eq!(csv.lines().count(), total + 1);
+
+        // Other levels start with every group expanded
+        app.handle_key_event(KeyCode::Enter.into())?;
+        assert!(app.collapsed.is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_jump_to_a_visible_item() -> color_eyre::Result<()> {
+        let mut app = mock_app();
+        let ctrl_j = KeyEvent::new(KeyCode::Char('j'), KeyModifiers::CONTROL);
+        let screen = app.run_scripted(vec![ctrl_j.into()])?.screen;
+        let hints: String = (0..screen.area.height)
+            .map(|y| screen.get(1, y).symbol())
+            .collect();
+        assert!(hints.contains("asdf"));
+
+        app.handle_key_event(KeyCode::Char('f').into())?;
+        assert!(app.jump.is_none());
+        assert_eq!(app.list_state.selected(), Some(3));
+
+        // Anything but a hint gives up on jumping
+        app.handle_key_event(ctrl_j)?;
+        app.handle_key_event(KeyCode::Down.into())?;
+        assert!(app.jump.is_none());
+        assert_eq!(app.list_state.selected(), Some(3));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_go_to_a_result_by_number() -> color_eyre::Result<()> {
+        let mut app = mock_app();
+        type_text(&mut app, ":5");
+        assert_eq!(app.query, "");
+        let screen = app.run_scripted(Vec::new())?.screen;
+        let numbers: Vec<String> = (0..screen.area.height)
+            .map(|y| (1..3).map(|x| screen.get(x, y).symbol()).collect())
+            .collect();
+        assert!(numbers.iter().any(|number| number == " 5"));
+        app.handle_key_event(KeyCode::Enter.into())?;
+        assert_eq!(app.list_state.selected(), Some(4));
+        assert!(app.accepted.is_empty());
+
+        // Past the end goes to the last one, Esc gives up
+        type_text(&mut app, ":99");
+        app.handle_key_event(KeyCode::Enter.into())?;
+        assert_eq!(app.list_state.selected(), Some(app.matches.len() - 1));
+        type_text(&mut app, ":1");
+        app.handle_key_event(KeyCode::Esc.into())?;
+        assert_eq!(app.list_state.selected(), Some(app.matches.len() - 1));
+        assert_eq!(app.path, Vec::<String>::new());
+
+        // Nor does it keep from quitting
+        type_text(&mut app, ":1");
+        app.handle_key_event(KeyEvent::new(KeyCode::Char('c'), KeyModifiers::CONTROL))?;
+        assert!(app.should_exit);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_export_results_to_csv() -> color_eyre::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let options = Options {
+            start_path: vec!["man_vs_bee".into()],
+            export_file: dir.path().join("shots.csv"),
+            ..Options::default()
+        };
+        let mut app = App::new(Box::new(MockBackend), options)?;
+        type_text(&mut app, "seq");
+        let alt_e = KeyEvent::new(KeyCode::Char('e'), KeyModifiers::ALT);
+        app.handle_key_event(alt_e)?;
+
+        let csv = std::fs::read_to_string(dir.path().join("shots.csv"))?;
+        assert_eq!(csv.lines().count(), app.matches.len() + 1);
+        assert!(csv.contains("man_vs_bee/sequence_001,sequence_001,sequence,"));
+        assert!(app
+            .message
+            .as_deref()
+            .unwrap()
+            .starts_with("exported 4 results"));
+
+        // Nothing is overwritten until the user confirms
+        type_text(&mut app, "_001");
+        app.handle_key_event(alt_e)?;
+        assert!(app.confirm.is_some());
+        app.handle_key_event(KeyCode::Esc.into())?;
+        let csv = std::fs::read_to_string(dir.path().join("shots.csv"))?;
+        assert_eq!(csv.lines().count(), 5);
+        app.handle_key_event(alt_e)?;
+        app.handle_key_event(KeyCode::Enter.into())?;
+        let csv = std::fs::read_to_string(dir.path().join("shots.csv"))?;
+        assert_eq!(csv.lines().count(), app.matches.len() + 1);
+        assert!(app.matches.len() < 4);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_selection_keeps_score_and_metadata() -> color_eyre::Result<()> {
+        let options = Options {
+            start_path: vec!["man_vs_bee".into(), "sequence_001".into()],
+            ..Options::default()
+        };
+        let mut app = App::new(Box::new(MockBackend), options)?;
+        type_text(&mut app, "sh02");
+        let metadata = app.highlighted_item().unwrap().metadata.clone();
+        app.handle_key_event(KeyCode::Enter.into())?;
+
+        let selection = &app.accepted[0];
+        assert_eq!(selection.path.last().unwrap(), "sh020");
+        assert!(selection.score.is_some_and(|score| score > 0));
+        assert_eq!(selection.metadata, metadata);
+
+        // Marked items keep what they had when they were marked
+        let options = Options {
+            start_path: vec!["man_vs_bee".into(), "sequence_001".into()],
+            multi: true,
+            ..Options::default()
+        };
+        let mut app = App::new(Box::new(MockBackend), options)?;
+        type_text(&mut app, "sh02");
+        app.handle_key_event(KeyCode::Tab.into())?;
+        app.handle_key_event(KeyEvent::new(KeyCode::Char('u'), KeyModifiers::CONTROL))?;
+        app.handle_key_event(KeyEvent::new(KeyCode::Char('l'), KeyModifiers::CONTROL))?;
+        app.handle_key_event(KeyCode::Enter.into())?;
+        assert_eq!(app.accepted[0].path.last().unwrap(), "sh020");
+        assert!(app.accepted[0].score.is_some_and(|score| score > 0));
+        assert_eq!(app.accepted[0].metadata, metadata);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_items_streamed_from_a_command() -> color_eyre::Result<()> {
+        let backend = StdinBackend::from_command(
+            "printf 'sh010\\n'; sleep 0.1; printf 'sh020\\n'",
+            LineOptions::default(),
+        )?;
+        let mut app = App::new(Box::new(backend), Options::default())?;
+        assert!(app.streaming.is_some());
+        assert_eq!(app.count_all_matches()?, 2);
+        assert!(app.streaming.is_none());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_reload_walks_again() -> color_eyre::Result<()> {
+        let ctrl_r = KeyEvent::new(KeyCode::Char('r'), KeyModifiers::CONTROL);
+        let root = tempfile::tempdir()?;
+        std::fs::create_dir_all(root.path().join("man_vs_bee/sequence_001"))?;
+        let backend = FsBackend::new(root.path()).with_walk_depth(2);
+        let mut app = App::new(Box::new(backend), Options::default())?;
+        wait_for_stream(&mut app)?;
+        assert_eq!(app.items.len(), 1);
+
+        std::fs::create_dir_all(root.path().join("project_001/sequence_002"))?;
+        app.handle_key_event(ctrl_r)?;
+        wait_for_stream(&mut app)?;
+        let mut names: Vec<&str> = app.items.iter().map(|i| i.name.as_str()).collect();
+        names.sort();
+        assert_eq!(
+            names,
+            ["man_vs_bee/sequence_001", "project_001/sequence_002"]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_start_at() -> color_eyre::Result<()> {
+        let options = Options {
+            start_path: vec!["man_vs_bee".into(), "sequence_001".into()],
+            start_at: StartAt::Last,
+            ..Options::default()
+        };
+        let mut app = App::new(Box::new(MockBackend), options)?;
+        assert_eq!(app.list_state.selected(), Some(app.matches.len() - 1));
+
+        app.options.start_at = StartAt::QueryBest;
+        app.sort_mode = SortMode::Alphabetical;
+        type_text(&mut app, "sh03");
+        assert_eq!(app.highlighted_item().unwrap().name, "sh030");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_select_text_until_a_key_is_pressed() -> color_eyre::Result<()> {
+        let alt_s = KeyEvent::new(KeyCode::Char('s'), KeyModifiers::ALT);
+        let mut app = mock_app();
+        app.handle_key_event(alt_s)?;
+        assert_eq!(app.terminal_request, None);
+        assert!(!app.selecting_text);
+
+        let options = Options {
+            tui: tui::Options {
+                mouse: true,
+                ..tui::Options::default()
+            },
+            ..Options::default()
+        };
+        let mut app = App::new(Box::new(MockBackend), options)?;
+        app.handle_key_event(alt_s)?;
+        assert_eq!(
+            app.terminal_request.take(),
+            Some(TerminalRequest::MouseCapture(false))
+        );
+
+        // The key press only takes the mouse back
+        type_text(&mut app, "x");
+        assert!(app.query.is_empty());
+        assert_eq!(
+            app.terminal_request.take(),
+            Some(TerminalRequest::MouseCapture(true))
+        );
+        type_text(&mut app, "x");
+        assert_eq!(app.query, "x");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_record_and_replay_a_macro() -> color_eyre::Result<()> {
+        let mut app = mock_app();
+        let alt = |c| KeyEvent::new(KeyCode::Char(c), KeyModifiers::ALT);
+
+        app.handle_key_event(alt('q'))?;
+        type_text(&mut app, "bee");
+        app.handle_key_event(KeyCode::Down.into())?;
+        app.handle_key_event(alt('q'))?;
+        assert_eq!(app.message.as_deref(), Some("macro recorded, 4 inputs"));
+
+        app.handle_key_event(KeyEvent::new(KeyCode::Char('u'), KeyModifiers::CONTROL))?;
+        assert!(app.query.is_empty());
+        app.handle_key_event(alt('@'))?;
+        assert_eq!(app.query, "bee");
+        assert_eq!(app.list_state.selected(), Some(1));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_replayed_moves_are_not_sped_up() -> color_eyre::Result<()> {
+        let lines: String = (0..100).map(|i| format!("line_{i:02}\n")).collect();
+        let backend = StdinBackend::from_reader(lines.as_bytes(), LineOptions::default())?;
+        let options = Options {
+            acceleration: Some(Acceleration::new(
+                crate::repeat::DEFAULT_AFTER,
+                crate::repeat::DEFAULT_STEP,
+            )),
+            ..Options::default()
+        };
+        let mut app = App::new(Box::new(backend), options)?;
+        let alt = |c| KeyEvent::new(KeyCode::Char(c), KeyModifiers::ALT);
+
+        app.handle_key_event(alt('q'))?;
+        for _ in 0..12 {
+            app.handle_key_event(KeyCode::Down.into())?;
+        }
+        app.handle_key_event(alt('q'))?;
+
+        app.list_state.select(Some(0));
+        app.handle_key_event(alt('@'))?;
+        assert_eq!(app.list_state.selected(), Some(12));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_record_and_replay_a_session() -> color_eyre::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let transcript = dir.path().join("session.jsonl");
+        let options = Options {
+            record: Some(transcript.clone()),
+            ..Options::default()
+        };
+        let mut app = App::new(Box::new(MockBackend), options)?;
+        for c in "bee".chars() {
+            app.handle_event(event::Event::Key(KeyCode::Char(c).into()))?;
+        }
+        app.handle_event(event::Event::Key(KeyCode::Down.into()))?;
+        let recorded = app.snapshot();
+
+        let options = Options {
+            replay: Some(transcript),
+            replay_speed: 0.0,
+            ..Options::default()
+        };
+        let mut app = App::new(Box::new(MockBackend), options)?;
+        while app.replay.is_some() {
+            app.handle_events()?;
+        }
+        assert_eq!(app.snapshot().query, "bee");
+        assert_eq!(app.snapshot().highlighted, recorded.highlighted);
+        assert_eq!(app.message.as_deref(), Some("replay finished"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_focus_changes() -> color_eyre::Result<()> {
+        let mut app = mock_app();
+        app.handle_event(event::Event::FocusLost)?;
+        assert!(!app.focused);
+
+        app.handle_event(event::Event::FocusGained)?;
+        assert!(app.focused);
+        assert_eq!(app.terminal_request, Some(TerminalRequest::Redraw));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_held_down_key_speeds_up() -> color_eyre::Result<()> {
+        let options = Options {
+            start_path: vec!["man_vs_bee".into(), "sequence_001".into()],
+            acceleration: Some(Acceleration::new(1, 2)),
+            ..Options::default()
+        };
+        let mut app = App::new(Box::new(MockBackend), options)?;
+        app.handle_key_event(KeyCode::Down.into())?;
+        app.handle_key_event(KeyCode::Down.into())?;
+        assert_eq!(app.list_state.selected(), Some(3));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_recent_selections_on_empty_query() -> color_eyre::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let file = dir.path().join("history.jsonl");
+        let now = history::now();
+        let line =
+            format!("{{\"at\":{now},\"path\":[\"man_vs_bee\",\"sequence_001\",\"sh020\"]}}\n");
+        std::fs::write(&file, line.repeat(2))?;
+        let options = Options {
+            history_file: Some(file.clone()),
+            ..Options::default()
+        };
+        let mut app = App::new(Box::new(MockBackend), options)?;
+        assert_eq!(app.highlighted_recent(), Some(0));
+
+        // Out of the recent paths and back
+        app.handle_key_event(KeyCode::Down.into())?;
+        assert_eq!(app.highlighted_recent(), None);
+        app.handle_key_event(KeyCode::Up.into())?;
+        assert_eq!(app.highlighted_recent(), Some(0));
+
+        // Typing hides them
+        type_text(&mut app, "m");
+        assert_eq!(app.highlighted_recent(), None);
+        app.handle_key_event(KeyCode::Backspace.into())?;
+
+        // Enter twice gets back to the shot
+        app.handle_key_event(KeyCode::Enter.into())?;
+        assert_eq!(app.path, ["man_vs_bee", "sequence_001"]);
+        assert_eq!(app.highlighted_item().unwrap().name, "sh020");
+        app.handle_key_event(KeyCode::Enter.into())?;
+        assert_eq!(
+            app.accepted[0].path,
+            ["man_vs_bee", "sequence_001", "sh020"]
+        );
+        assert_eq!(std::fs::read_to_string(&file)?.lines().count(), 3);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_accept_from_the_history() -> color_eyre::Result<()> {
+        let mut app = mock_app();
+        let alt_h = KeyEvent::new(KeyCode::Char('h'), KeyModifiers::ALT);
+
+        // Nothing accepted yet
+        app.handle_key_event(alt_h)?;
+        app.handle_key_event(KeyCode::Enter.into())?;
+        assert!(app.history_browser.is_some());
+        app.handle_key_event(KeyCode::Esc.into())?;
+        assert!(app.history_browser.is_none());
+
+        app.options.keep_open = true;
+        app.history
+            .record(&["man_vs_bee".into(), "sequence_001".into(), "sh020".into()])?;
+        app.history
+            .record(&["project_001".into(), "seq".into(), "sh010".into()])?;
+        app.handle_key_event(alt_h)?;
+        type_text(&mut app, "bee");
+        app.handle_key_event(KeyCode::Enter.into())?;
+        assert!(app.history_browser.is_none());
+        assert_eq!(
+            selected(&app).as_deref(),
+            Some("man_vs_bee/sequence_001/sh020")
+        );
+        assert!(app.accepted[0].metadata.contains_key("artist"));
+        assert_eq!(app.history.entries().len(), 3);
+
+        // Gone since
+        app.history
+            .record(&["man_vs_bee".into(), "sequence_001".into(), "sh999".into()])?;
+        app.handle_key_event(alt_h)?;
+        app.handle_key_event(KeyCode::Enter.into())?;
+        assert_eq!(app.accepted.len(), 1);
+        assert_eq!(
+            app.message.as_deref(),
+            Some("man_vs_bee/sequence_001/sh999 doesn't exist anymore")
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_initial_query() -> color_eyre::Result<()> {
+        let options = Options {
+            start_path: vec!["man_vs_bee".into()],
+            query: "hero".to_string(),
+            ..Options::default()
+        };
+        let app = App::new(Box::new(MockBackend), options)?;
+        assert_eq!(app.query, "hero");
+        assert_eq!(app.highlighted_item().unwrap().name, "hero_bee");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_undo_query_edits() -> color_eyre::Result<()> {
+        let mut app = mock_app();
+        let ctrl = |c| KeyEvent::new(KeyCode::Char(c), KeyModifiers::CONTROL);
+
+        type_text(&mut app, "status:wip bee");
+        app.handle_key_event(ctrl('u'))?;
+        assert!(app.query.is_empty());
+
+        app.handle_key_event(ctrl('z'))?;
+        assert_eq!(app.query, "status:wip bee");
+        app.handle_key_event(ctrl('z'))?;
+        assert_eq!(app.query, "status:wip be");
+
+        let ctrl_shift_z = KeyEvent::new(
+            KeyCode::Char('Z'),
+            KeyModifiers::CONTROL | KeyModifiers::SHIFT,
+        );
+        app.handle_key_event(ctrl_shift_z)?;
+        assert_eq!(app.query, "status:wip bee");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_scroll_highlighted_name() -> color_eyre::Result<()> {
+        let mut app = mock_app();
+        type_text(&mut app, "asset_library");
+        app.name_width = 10;
+
+        app.handle_key_event(KeyCode::Right.into())?;
+        assert_eq!(app.name_scroll, 4);
+        for _ in 0..5 {
+            app.handle_key_event(KeyCode::Right.into())?;
+        }
+        assert_eq!(app.name_scroll, "asset_library_2024".len() - 9);
+
+        app.handle_key_event(KeyCode::Left.into())?;
+        assert_eq!(app.name_scroll, 5);
+        app.handle_key_event(KeyCode::Backspace.into())?;
+        assert_eq!(app.name_scroll, 0);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_paste_is_a_single_edit() -> color_eyre::Result<()> {
+        let mut app = mock_app();
+        app.handle_paste("man_vs\tbee\n");
+        assert_eq!(app.query, "man_vs bee ");
+
+        let ctrl_z = KeyEvent::new(KeyCode::Char('z'), KeyModifiers::CONTROL);
+        app.handle_key_event(ctrl_z)?;
+        assert!(app.query.is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_regex_mode_with_invalid_pattern() -> color_eyre::Result<()> {
+        let mut app = mock_app();
+        let ctrl_slash = KeyEvent::new(KeyCode::Char('/'), KeyModifiers::CONTROL);
+        app.handle_key_event(ctrl_slash)?;
+        assert_eq!(app.match_mode, MatchMode::Regex);
+
+        type_text(&mut app, "^project_00[12]$");
+        assert_eq!(app.matches.len(), 2);
+        assert!(app.query_error.is_none());
+
+        type_text(&mut app, "(");
+        assert!(app.query_error.is_some());
+        assert_eq!(app.matches.len(), 8);
+
+        app.handle_key_event(ctrl_slash)?;
+        assert!(app.query_error.is_none());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_match_only_selected_fields() -> color_eyre::Result<()> {
+        let backend = StdinBackend::from_reader(
+            "sh010\tanna\nsh020\tbob\nanna_sh\tcarla\n".as_bytes(),
+            LineOptions::default(),
+        )?;
+        let options = Options {
+            fields: Some(FieldSelector::parse("2", Some("\t")).unwrap()),
+            ..Options::default()
+        };
+        let mut app = App::new(Box::new(backend), options)?;
+
+        type_text(&mut app, "anna");
+        assert_eq!(app.matches.len(), 1);
+        app.handle_key_event(KeyCode::Enter.into())?;
+        assert_eq!(selected(&app).as_deref(), Some("sh010\tanna"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_styled_name_keeps_ansi_styles_when_clipped() {
+        let parsed = crate::ansi::parse("\x1b[31mred\x1b[0m_plain");
+        let item = Item::new(parsed.text, ItemKind::Line).with_name_styles(parsed.styles);
+
+        let spans = styled_name(&item, 0, 6, "~", Style::new());
+        let texts: Vec<&str> = spans.iter().map(|s| s.content.as_ref()).collect();
+        assert_eq!(texts, vec!["red", "_p", "~"]);
+        assert_eq!(spans[0].style.fg, Some(ratatui::style::Color::Indexed(1)));
+
+        let spans = styled_name(&item, 2, 20, "~", Style::new());
+        let texts: Vec<&str> = spans.iter().map(|s| s.content.as_ref()).collect();
+        assert_eq!(texts, vec!["~", "d", "_plain"]);
+    }
+
+    #[test]
+    fn test_preview_follows_highlight_and_scrolls() -> color_eyre::Result<()> {
+        let options = Options {
+            preview: Some("echo {path}; echo {name}; echo done".to_string()),
+            ..Options::default()
+        };
+        let mut app = App::new(Box::new(MockBackend), options)?;
+        app.update_preview();
+        assert_eq!(app.preview_path.as_deref(), Some("man_vs_bee"));
+
+        let start = std::time::Instant::now();
+        while app.previewer.as_ref().is_some_and(Previewer::is_busy)
+            && start.elapsed() < Duration::from_secs(5)
+        {
+            app.update_preview();
+            std::thread::sleep(Duration::from_millis(10));
+        }
+
+        let shift_down = KeyEvent::new(KeyCode::Down, KeyModifiers::SHIFT);
+        for _ in 0..5 {
+            app.handle_key_event(shift_down)?;
+        }
+        assert_eq!(app.preview_scroll, 2);
+        assert_eq!(app.list_state.selected(), Some(0));
+
+        app.handle_key_event(KeyCode::Down.into())?;
+        app.update_preview();
+        assert_eq!(app.preview_path.as_deref(), Some("asset_library_2024"));
+        assert_eq!(app.preview_scroll, 0);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_thumbnail_placeholder_without_graphics() -> color_eyre::Result<()> {
+        let backend = StdinBackend::from_reader("sh010\n".as_bytes(), LineOptions::default())?;
+        let options = Options {
+            thumbnails: true,
+            ..Options::default()
+        };
+        let mut app = App::new(Box::new(backend), options)?;
+        app.items[0].thumbnail = Some(PathBuf::from("sh010/thumbnail.png"));
+
+        let area = Rect::new(0, 0, 80, 30);
+        let mut buf = Buffer::empty(area);
+        app.render(area, &mut buf);
+        assert_eq!(app.thumbnail, None);
+        let screen: String = buf.content.iter().map(|cell| cell.symbol()).collect();
+        assert!(screen.contains("no image support"));
+
+        app.options.graphics = GraphicsProtocol::Kitty;
+        app.render(area, &mut buf);
+        let (path, image_area) = app.thumbnail.clone().unwrap();
+        assert_eq!(path, PathBuf::from("sh010/thumbnail.png"));
+        assert!(image_area.x >= 40 && image_area.height > 0);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_link_target_in_the_preview() -> color_eyre::Result<()> {
+        let backend = StdinBackend::from_reader("sh010\n".as_bytes(), LineOptions::default())?;
+        let options = Options {
+            link_targets: true,
+            ..Options::default()
+        };
+        let mut app = App::new(Box::new(backend), options)?;
+        app.items[0].link_target = Some(PathBuf::from("/mnt/shared/sh010"));
+
+        let area = Rect::new(0, 0, 80, 30);
+        let mut buf = Buffer::empty(area);
+        app.render(area, &mut buf);
+        let screen: String = buf.content.iter().map(|cell| cell.symbol()).collect();
+        assert!(screen.contains("→ /mnt/shared/sh010"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_key_hints_follow_the_color_depth() {
+        let mut app = mock_app();
+        app.theme = Theme::default().with_depth(ColorDepth::None);
+        let area = Rect::new(0, 0, 400, 20);
+        let mut buf = Buffer::empty(area);
+        app.render(area, &mut buf);
+
+        let row = |y| (0..area.width).map(|x| buf.get(x, y)).collect::<Vec<_>>();
+        let footer = (0..area.height)
+            .map(row)
+            .find(|cells| {
+                let line: String = cells.iter().map(|cell| cell.symbol()).collect();
+                line.contains("<Alt+H>")
+            })
+            .expect("the key hints are drawn");
+        assert!(footer.iter().all(|cell| cell.fg == Color::Reset));
+    }
+
+    #[test]
+    fn test_terminal_too_small() {
+        let mut app = mock_app();
+        let render = |app: &mut App, area: Rect| {
+            let mut buf = Buffer::empty(area);
+            app.render(area, &mut buf);
+            buf.content
+                .iter()
+                .map(|cell| cell.symbol())
+                .collect::<String>()
+        };
+
+        let screen = render(&mut app, Rect::new(0, 0, 30, 5));
+        assert!(screen.contains("Terminal too small"));
+        assert!(!screen.contains("man_vs_bee"));
+
+        let screen = render(&mut app, Rect::new(0, 0, 40, 20));
+        assert!(!screen.contains("Terminal too small"));
+        assert!(screen.contains("man_vs_bee"));
+    }
+
+    #[test]
+    fn test_layout_adapts_to_the_terminal() {
+        let [header, tabs, search, items, status] = layout(Rect::new(0, 0, 120, 40), false);
+        assert_eq!((header.height, search.height, status.height), (3, 3, 1));
+        assert_eq!((tabs.height, items.height), (0, 33));
+        let [_, tabs, _, items_with_tabs, _] = layout(Rect::new(0, 0, 120, 40), true);
+        assert_eq!((tabs.height, items_with_tabs.height), (1, 32));
+
+        assert!(split_preview(items).1.is_some());
+        let [_, _, _, narrow_items, _] = layout(Rect::new(0, 0, 60, 40), false);
+        assert_eq!(split_preview(narrow_items), (narrow_items, None));
+    }
+
+    #[test]
+    fn test_run_scripted_session() -> color_eyre::Result<()> {
+        let mut events = AppEvent::typed("man_vs");
+        events.extend([KeyCode::Enter.into(), KeyCode::Enter.into()]);
+        events.push(AppEvent::Paste("sh030".to_string()));
+        events.push(AppEvent::Resize(100, 30));
+
+        let run = mock_app().run_scripted(events.clone())?;
+        assert_eq!(run.outcome, None);
+        assert_eq!(run.screen.area, Rect::new(0, 0, 100, 30));
+        let screen: String = run.screen.content.iter().map(|c| c.symbol()).collect();
+        assert!(screen.contains("man_vs_bee / sequence_001"));
+
+        events.push(KeyCode::Enter.into());
+        // Ignored, since the app already exited
+        events.push(KeyCode::Char('Q').into());
+        let run = mock_app().run_scripted(events)?;
+        let Some(Outcome::Accepted(selections)) = run.outcome else {
+            panic!("expected a selection, got {:?}", run.outcome);
+        };
+        let [selection] = selections.as_slice() else {
+            panic!("expected a single selection, got {selections:?}");
+        };
+        assert_eq!(selection.to_string(), "man_vs_bee/sequence_001/sh030");
+        assert_eq!(selection.context(), ["man_vs_bee", "sequence_001"]);
+
+        let run = mock_app().run_scripted(vec![KeyCode::Esc.into()])?;
+        assert_eq!(run.outcome, Some(Outcome::Aborted));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_uppercase_q_is_typed() -> color_eyre::Result<()> {
+        let mut app = mock_app();
+        type_text(&mut app, "Q");
+        assert_eq!(app.query, "Q");
+        assert!(!app.should_exit);
+
+        app.handle_key_event(KeyEvent::new(KeyCode::Char('q'), KeyModifiers::CONTROL))?;
+        assert!(app.should_exit);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_actions_without_key_events() -> color_eyre::Result<()> {
+        let mut app = mock_app();
+        for action in [
+            Action::Insert('b'),
+            Action::Insert('e'),
+            Action::Insert('e'),
+        ] {
+            app.handle_input(InputEvent::Action(action))?;
+        }
+        app.handle_input(InputEvent::Paste("_movie".to_string()))?;
+        assert_eq!(app.query, "bee_movie");
+        assert_eq!(app.highlighted_item().unwrap().name, "bee_movie_2");
+
+        app.handle_input(InputEvent::Action(Action::Quit))?;
+        assert!(app.should_exit);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_command_palette_runs_actions() -> color_eyre::Result<()> {
+        let mut app = mock_app();
+        let sort_mode = app.sort_mode;
+
+        app.handle_key_event(KeyEvent::new(KeyCode::Char('p'), KeyModifiers::CONTROL))?;
+        assert!(app.palette.is_some());
+        type_text(&mut app, "sort");
+        assert_eq!(app.query, "");
+        app.handle_key_event(KeyCode::Enter.into())?;
+        assert!(app.palette.is_none());
+        assert_eq!(app.sort_mode, sort_mode.next());
+
+        app.handle_key_event(KeyEvent::new(KeyCode::Char('p'), KeyModifiers::CONTROL))?;
+        type_text(&mut app, "preview");
+        app.handle_key_event(KeyCode::Enter.into())?;
+        assert!(app.message.is_some());
+        assert!(!app.preview_hidden);
+
+        app.handle_key_event(KeyEvent::new(KeyCode::Char('p'), KeyModifiers::CONTROL))?;
+        app.handle_key_event(KeyCode::Esc.into())?;
+        assert!(app.palette.is_none());
+        assert!(!app.should_exit);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_tabs_keep_their_own_state() -> color_eyre::Result<()> {
+        let options = Options {
+            tabs: true,
+            ..Options::default()
+        };
+        let mut app = App::new(Box::new(MockBackend), options)?;
+        let ctrl_right = KeyEvent::new(KeyCode::Right, KeyModifiers::CONTROL);
+
+        // Shots tab: inside a project, only sequences are listed
+        app.handle_key_event(KeyCode::Enter.into())?;
+        assert!(app
+            .matches
+            .iter()
+            .all(|i| app.items[*i].kind == ItemKind::Sequence));
+        type_text(&mut app, "002");
+
+        app.handle_key_event(ctrl_right)?;
+        assert_eq!(app.tab, Tab::Assets);
+        assert!(app.path.is_empty());
+        assert_eq!(app.query, "");
+        app.handle_key_event(KeyCode::Enter.into())?;
+        let names: Vec<&str> = app
+            .matches
+            .iter()
+            .map(|i| app.items[*i].name.as_str())
+            .collect();
+        assert_eq!(names, vec!["hero_bee", "hive_env"]);
+
+        app.handle_key_event(KeyEvent::new(KeyCode::Char('1'), KeyModifiers::ALT))?;
+        assert_eq!(app.tab, Tab::Shots);
+        assert_eq!(app.path, vec!["man_vs_bee"]);
+        assert_eq!(app.query, "002");
+        assert_eq!(app.highlighted_item().unwrap().name, "sequence_002");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_items_are_fetched_page_by_page() -> color_eyre::Result<()> {
+        let lines: String = (0..2500).map(|i| format!("line_{i:04}\n")).collect();
+        let backend = StdinBackend::from_reader(lines.as_bytes(), LineOptions::default())?;
+        let mut app = App::new(Box::new(backend), Options::default())?;
+        assert_eq!(app.items.len(), PAGE_SIZE);
+        assert!(app.has_more_items);
+
+        app.list_state
+            .select(Some(PAGE_SIZE - LOAD_MORE_MARGIN - 2));
+        app.handle_key_event(KeyCode::Down.into())?;
+        assert_eq!(app.items.len(), PAGE_SIZE);
+        app.handle_key_event(KeyCode::Down.into())?;
+        assert_eq!(app.items.len(), 2 * PAGE_SIZE);
+        assert_eq!(
+            app.list_state.selected(),
+            Some(PAGE_SIZE - LOAD_MORE_MARGIN)
+        );
+
+        app.list_state.select(Some(2 * PAGE_SIZE - 1));
+        app.handle_key_event(KeyCode::Down.into())?;
+        assert_eq!(app.items.len(), 2500);
+        assert!(!app.has_more_items);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_queries_match_every_page() -> color_eyre::Result<()> {
+        let lines: String = (0..2500).map(|i| format!("line_{i:04}\n")).collect();
+        let backend = StdinBackend::from_reader(lines.as_bytes(), LineOptions::default())?;
+        let mut app = App::new(Box::new(backend), Options::default())?;
+        assert_eq!(app.items.len(), PAGE_SIZE);
+
+        for c in "2400".chars() {
+            app.handle_key_event(KeyCode::Char(c).into())?;
+        }
+        assert!(app.load_more_pending);
+        app.load_more_if_allowed()?;
+        assert_eq!(app.items.len(), 2500);
+        assert_eq!(app.matches.len(), 1);
+        assert_eq!(app.highlighted_item().unwrap().name, "line_2400");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_fetching_pages_is_rate_limited() -> color_eyre::Result<()> {
+        let lines: String = (0..2500).map(|i| format!("line_{i:04}\n")).collect();
+        let backend = StdinBackend::from_reader(lines.as_bytes(), LineOptions::default())?;
+        let options = Options {
+            rate_limit: Some(1),
+            ..Options::default()
+        };
+        let mut app = App::new(Box::new(backend), options)?;
+
+        app.list_state.select(Some(PAGE_SIZE - LOAD_MORE_MARGIN));
+        app.handle_key_event(KeyCode::Down.into())?;
+        assert_eq!(app.items.len(), 2 * PAGE_SIZE);
+
+        app.list_state.select(Some(2 * PAGE_SIZE - 1));
+        app.handle_key_event(KeyCode::Down.into())?;
+        assert_eq!(app.items.len(), 2 * PAGE_SIZE);
+        assert!(app.load_more_pending);
+
+        app.rate_limit = Some(TokenBucket::new(1, Instant::now()));
+        app.load_more_if_allowed()?;
+        assert_eq!(app.items.len(), 2500);
+        assert!(!app.load_more_pending);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_visible_offset() {
+        assert_eq!(visible_offset(0, Some(5), 10, 100, 0), 0);
+        assert_eq!(visible_offset(0, Some(15), 10, 100, 0), 6);
+        assert_eq!(visible_offset(20, Some(15), 10, 100, 0), 15);
+        assert_eq!(visible_offset(50, Some(2), 10, 5, 0), 0);
+        assert_eq!(visible_offset(0, None, 0, 5, 0), 0);
+
+        // Keeping 3 rows of context, unless the list ends
+        assert_eq!(visible_offset(0, Some(7), 10, 100, 3), 1);
+        assert_eq!(visible_offset(20, Some(22), 10, 100, 3), 19);
+        assert_eq!(visible_offset(20, Some(1), 10, 100, 3), 0);
+        assert_eq!(visible_offset(0, Some(98), 10, 100, 3), 90);
+        // Not more than half the height
+        assert_eq!(visible_offset(0, Some(4), 5, 100, 9), 2);
+    }
+
+    #[test]
+    fn test_only_visible_rows_are_drawn() -> color_eyre::Result<()> {
+        let lines: String = (0..5000).map(|i| format!("line_{i:04}\n")).collect();
+        let backend = StdinBackend::from_reader(lines.as_bytes(), LineOptions::default())?;
+        let mut app = App::new(Box::new(backend), Options::default())?;
+        app.list_state.select(Some(500));
+
+        let area = Rect::new(0, 0, 80, 24);
+        let mut buf = Buffer::empty(area);
+        app.render(area, &mut buf);
+        let screen: String = buf.content.iter().map(|cell| cell.symbol()).collect();
+        assert!(screen.contains("line_0500"));
+        assert!(!screen.contains("line_0485"));
+        // 15 rows fit between the borders, with the highlighted one at the bottom
+        assert_eq!(app.list_state.offset(), 486);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_max_results() -> color_eyre::Result<()> {
+        let options = Options {
+            max_results: Some(3),
+            ..Options::default()
+        };
+        let mut app = App::new(Box::new(MockBackend), options)?;
+        assert_eq!(app.matches.len(), 3);
+        type_text(&mut app, "bee");
+        assert_eq!(app.matches.len(), 2);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_debug_overlay_toggles() -> color_eyre::Result<()> {
+        let screen = |run: ScriptedRun| -> String {
+            run.screen.content.iter().map(|c| c.symbol()).collect()
+        };
+
+        let run = mock_app().run_scripted(vec![KeyCode::F(10).into()])?;
+        assert!(screen(run).contains("matches  "));
+
+        let run = mock_app().run_scripted(vec![KeyCode::F(10).into(), KeyCode::F(10).into()])?;
+        assert!(!screen(run).contains("matches  "));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_reload_keeps_query() -> color_eyre::Result<()> {
+        let mut app = mock_app();
+        type_text(&mut app, "bee");
+        app.handle_key_event(KeyEvent::new(KeyCode::Char('r'), KeyModifiers::CONTROL))?;
+        assert_eq!(app.query, "bee");
+        assert_eq!(app.matches.len(), 2);
+        assert_eq!(
+            app.message.as_deref(),
+            Some(format!("reloaded {} items", app.items.len()).as_str())
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_reload_config() -> color_eyre::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let file = dir.path().join("config.toml");
+        let options = Options {
+            config: Some(ConfigSource {
+                file: Some(file.clone()),
+                flags: crate::cli::Cli::default(),
+            }),
+            ..Options::default()
+        };
+        let mut app = App::new(Box::new(MockBackend), options)?;
+
+        std::fs::write(
+            &file,
+            "[layout]\nunicode = false\ntabs = true\n[keymap]\n\"ctrl-o\" = \"toggle-debug\"\n",
+        )?;
+        app.handle_action(Action::ReloadConfig)?;
+        assert_eq!(app.message.as_deref(), Some("reloaded the config"));
+        assert!(!app.options.unicode);
+        assert_eq!(app.options.ellipsis, "...");
+        assert!(app.options.tabs);
+        app.handle_key_event(KeyEvent::new(KeyCode::Char('o'), KeyModifiers::CONTROL))?;
+        assert!(app.show_debug);
+
+        std::fs::write(&file, "[layout]\nunicode = \"maybe\"\n")?;
+        app.handle_action(Action::ReloadConfig)?;
+        assert!(app
+            .message
+            .as_deref()
+            .is_some_and(|message| message.starts_with("config not reloaded")));
+        assert!(!app.options.unicode);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_open_needs_a_location() -> color_eyre::Result<()> {
+        let ctrl_o = KeyEvent::new(KeyCode::Char('o'), KeyModifiers::CONTROL);
+        let mut app = mock_app();
+        app.handle_key_event(ctrl_o)?;
+        assert_eq!(app.terminal_request, None);
+        assert_eq!(app.message.as_deref(), Some("man_vs_bee isn't on disk"));
+
+        let root = tempfile::tempdir()?;
+        std::fs::create_dir(root.path().join("sh010"))?;
+        let mut app = App::new(Box::new(FsBackend::new(root.path())), Options::default())?;
+        app.handle_key_event(ctrl_o)?;
+        assert_eq!(
+            app.terminal_request,
+            Some(TerminalRequest::Open(root.path().join("sh010")))
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_item_menu() -> color_eyre::Result<()> {
+        let alt_enter = KeyEvent::new(KeyCode::Enter, KeyModifiers::ALT);
+        let options = Options {
+            start_path: vec!["man_vs_bee".into(), "sequence_001".into()],
+            item_commands: vec![("Queue render".into(), "true {path}".into())],
+            ..Options::default()
+        };
+        let mut app = App::new(Box::new(MockBackend), options)?;
+
+        app.handle_key_event(alt_enter)?;
+        let screen = app.run_scripted(Vec::new())?.screen;
+        let screen: String = screen.content.iter().map(|c| c.symbol()).collect();
+        assert!(screen.contains("Show details"));
+        assert!(screen.contains("Queue render"));
+        app.handle_key_event(KeyCode::Enter.into())?;
+        assert_eq!(
+            app.terminal_request,
+            Some(TerminalRequest::CopyToClipboard(
+                "man_vs_bee/sequence_001/sh010".into()
+            ))
+        );
+
+        app.handle_key_event(alt_enter)?;
+        for _ in 0..3 {
+            app.handle_key_event(KeyCode::Down.into())?;
+        }
+        app.handle_key_event(KeyCode::Enter.into())?;
+        assert!(app.details.is_some());
+        app.handle_key_event(KeyCode::Esc.into())?;
+        assert!(app.details.is_none());
+
+        app.handle_key_event(alt_enter)?;
+        for _ in 0..4 {
+            app.handle_key_event(KeyCode::Down.into())?;
+        }
+        app.handle_key_event(KeyCode::Enter.into())?;
+        assert_eq!(
+            app.message.as_deref(),
+            Some("ran Queue render for man_vs_bee/sequence_001/sh010")
+        );
+        assert!(app.item_menu.is_none());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_details_popup() -> color_eyre::Result<()> {
+        let options = Options {
+            start_path: vec!["man_vs_bee".into(), "sequence_001".into()],
+            ..Options::default()
+        };
+        let mut app = App::new(Box::new(MockBackend), options)?;
+        app.handle_key_event(KeyEvent::new(KeyCode::Char('e'), KeyModifiers::CONTROL))?;
+        type_text(&mut app, "sh");
+        let screen = app.run_scripted(Vec::new())?.screen;
+        let screen: String = screen.content.iter().map(|c| c.symbol()).collect();
+        for text in ["status", "artist", "frame range", "1001-1096"] {
+            assert!(screen.contains(text), "{text:?} isn't shown");
+        }
+        assert_eq!(app.query, "");
+
+        app.handle_key_event(KeyCode::Enter.into())?;
+        assert!(app.details.is_none());
+        assert!(!app.should_exit);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_child_counts_end_the_rows() -> color_eyre::Result<()> {
+        let run = mock_app().run_scripted(AppEvent::typed("man_vs"))?;
+        let row: String = (0..run.screen.area.width)
+            .map(|x| run.screen.get(x, 7).symbol())
+            .collect();
+        assert!(row.contains("man_vs_bee (4 seqs)"), "{row:?}");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_scanned_levels_load_in_the_background() -> color_eyre::Result<()> {
+        let root = tempfile::tempdir()?;
+        for name in ["sh010", "sh020", "sh030"] {
+            std::fs::create_dir_all(root.path().join("man_vs_bee").join(name))?;
+        }
+        let backend = FsBackend::new(root.path()).with_scan_threshold(2);
+        let mut app = App::new(Box::new(backend), Options::default())?;
+        let wait_for_scan = |app: &mut App| {
+            let start = Instant::now();
+            while app.scan.is_some() && start.elapsed() < Duration::from_secs(5) {
+                std::thread::sleep(Duration::from_millis(10));
+                app.poll_scan().unwrap();
+            }
+        };
+
+        // Too small to be scanned
+        assert!(app.scan.is_none());
+        app.handle_key_event(KeyCode::Enter.into())?;
+        type_text(&mut app, "sh030");
+        wait_for_scan(&mut app);
+        assert_eq!(app.items.len(), 3);
+        assert_eq!(app.highlighted_item().unwrap().name, "sh030");
+
+        // Coming back puts the level back once it's scanned again
+        app.handle_key_event(KeyCode::Enter.into())?;
+        assert_eq!(app.path, ["man_vs_bee", "sh030"]);
+        app.handle_key_event(KeyCode::Esc.into())?;
+        wait_for_scan(&mut app);
+        assert_eq!(app.query, "sh030");
+        assert_eq!(app.highlighted_item().unwrap().name, "sh030");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_watch_refreshes_in_place() -> color_eyre::Result<()> {
+        let root = tempfile::tempdir()?;
+        for name in ["sh010", "sh020", "sh030"] {
+            std::fs::create_dir(root.path().join(name))?;
+        }
+        let options = Options {
+            watch: true,
+            ..Options::default()
+        };
+        let mut app = App::new(Box::new(FsBackend::new(root.path())), options)?;
+        type_text(&mut app, "sh0");
+        app.handle_key_event(KeyCode::Down.into())?;
+        assert_eq!(app.highlighted_item().unwrap().name, "sh020");
+
+        std::fs::create_dir(root.path().join("sh005"))?;
+        let start = std::time::Instant::now();
+        while app.items.len() < 4 && start.elapsed() < Duration::from_secs(5) {
+            std::thread::sleep(Duration::from_millis(10));
+            app.refresh_if_changed()?;
+        }
+        assert_eq!(app.items.len(), 4);
+        assert_eq!(app.query, "sh0");
+        assert_eq!(app.highlighted_item().unwrap().name, "sh020");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_select_only_match() -> color_eyre::Result<()> {
+        let mut app = mock_app();
+        assert_eq!(app.select_only_match()?, None);
+
+        let backend =
+            StdinBackend::from_reader("sh010\nsh020\n".as_bytes(), LineOptions::default())?;
+        let mut app = App::new(Box::new(backend), Options::default())?;
+        assert_eq!(app.count_all_matches()?, 2);
+        type_text(&mut app, "sh02");
+        let selection = app.select_only_match()?.map(|s| s.to_string());
+        assert_eq!(selection.as_deref(), Some("sh020"));
+
+        let mut app = mock_app();
+        type_text(&mut app, "zzz");
+        assert_eq!(app.count_all_matches()?, 0);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_handle_exit() -> color_eyre::Result<()> {
+        // If a user presses Esc at the top level, we should quit
+        let mut app = mock_app();
+        app.handle_key_event(KeyCode::Enter.into())?;
+        app.handle_key_event(KeyCode::Esc.into())?;
+        assert!(app.path.is_empty());
+        assert!(!app.should_exit);
+
+        app.handle_key_event(KeyCode::Esc.into())?;
+        assert!(app.should_exit);
 
         Ok(())
     }
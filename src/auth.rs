@@ -0,0 +1,169 @@
+//! Credentials for backends talking to a server, e.g. a production tracking API.
+//! Nothing in here knows about HTTP: backends tell [`Auth::send`] when a request
+//! was rejected by returning an [`Unauthorized`] error.
+
+use std::{fmt, fs, path::PathBuf, process::Command};
+
+use color_eyre::eyre::{bail, WrapErr};
+
+/// Where the token sent along with requests comes from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TokenSource {
+    /// The value of an environment variable
+    Env(String),
+    /// The contents of a file, e.g. written by a login tool
+    File(PathBuf),
+    /// What a shell command prints, e.g. `vault read -field=token ...`
+    Command(String),
+}
+
+impl TokenSource {
+    /// Gets a token, without the surrounding whitespace
+    pub fn read(&self) -> color_eyre::Result<Secret> {
+        let token = match self {
+            TokenSource::Env(name) => std::env::var(name)
+                .wrap_err_with(|| format!("failed to read the token from ${name}"))?,
+            TokenSource::File(file) => fs::read_to_string(file)
+                .wrap_err_with(|| format!("failed to read the token from {file:?}"))?,
+            TokenSource::Command(command) => {
+                let output = Command::new("sh")
+                    .arg("-c")
+                    .arg(command)
+                    .output()
+                    .wrap_err_with(|| format!("failed to run `{command}`"))?;
+                if !output.status.success() {
+                    bail!("`{command}` exited with {}", output.status);
+                }
+                String::from_utf8(output.stdout)
+                    .wrap_err_with(|| format!("`{command}` printed an invalid token"))?
+            }
+        };
+
+        let token = token.trim();
+        if token.is_empty() {
+            bail!("the token from {self:?} is empty");
+        }
+        Ok(Secret(token.to_string()))
+    }
+}
+
+/// A value that must not end up in logs or error messages.
+/// It's only printed as `[redacted]`.
+#[derive(Clone, PartialEq, Eq)]
+pub struct Secret(String);
+
+impl Secret {
+    pub fn expose(&self) -> &str {
+        &self.0
+    }
+
+    /// Hides the secret in `text`, e.g. a URL or a response echoing it back
+    pub fn redact(&self, text: &str) -> String {
+        text.replace(&self.0, REDACTED)
+    }
+}
+
+const REDACTED: &str = "[redacted]";
+
+impl fmt::Debug for Secret {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(REDACTED)
+    }
+}
+
+impl fmt::Display for Secret {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(REDACTED)
+    }
+}
+
+/// The server refused the token sent, e.g. with a 401
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Unauthorized;
+
+impl fmt::Display for Unauthorized {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("the server refused the token")
+    }
+}
+
+impl std::error::Error for Unauthorized {}
+
+/// Hands out the token from a [`TokenSource`], reading it again when it expired
+#[derive(Debug)]
+pub struct Auth {
+    source: TokenSource,
+    token: Option<Secret>,
+}
+
+impl Auth {
+    pub fn new(source: TokenSource) -> Self {
+        Self {
+            source,
+            token: None,
+        }
+    }
+
+    /// The token to send, read the first time it's needed
+    pub fn token(&mut self) -> color_eyre::Result<&Secret> {
+        if self.token.is_none() {
+            tracing::debug!("Reading the token from {:?}", self.source);
+            self.token = Some(self.source.read()?);
+        }
+        Ok(self.token.as_ref().expect("the token was just read"))
+    }
+
+    /// Makes a request with the token. When it's rejected with [`Unauthorized`],
+    /// the token is read again and the request made once more.
+    pub fn send<T>(
+        &mut self,
+        mut request: impl FnMut(&Secret) -> color_eyre::Result<T>,
+    ) -> color_eyre::Result<T> {
+        match request(self.token()?) {
+            Err(e) if e.downcast_ref::<Unauthorized>().is_some() => {
+                tracing::info!("The token was refused, reading it again");
+                self.token = None;
+                request(self.token()?)
+            }
+            result => result,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_token_is_refreshed_when_refused() -> color_eyre::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let file = dir.path().join("token");
+        fs::write(&file, "old-token\n")?;
+        let mut auth = Auth::new(TokenSource::File(file.clone()));
+        assert_eq!(format!("{:?}", auth.token()?), "[redacted]");
+
+        // The login tool wrote a new token since
+        fs::write(&file, "new-token")?;
+        let mut sent = Vec::new();
+        let answer = auth.send(|token| {
+            sent.push(token.expose().to_string());
+            match token.expose() {
+                "new-token" => Ok(42),
+                _ => Err(Unauthorized.into()),
+            }
+        })?;
+        assert_eq!(answer, 42);
+        assert_eq!(sent, ["old-token", "new-token"]);
+        assert_eq!(
+            auth.token()?.redact("GET /shots?token=new-token"),
+            "GET /shots?token=[redacted]"
+        );
+
+        let command = TokenSource::Command("echo '  from-command '".into());
+        assert_eq!(command.read()?.expose(), "from-command");
+        assert!(TokenSource::Command("true".into()).read().is_err());
+
+        Ok(())
+    }
+}
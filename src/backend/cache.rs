@@ -0,0 +1,287 @@
+use std::{
+    cell::RefCell,
+    collections::{BTreeMap, HashMap},
+    fs,
+    path::PathBuf,
+    time::{Duration, SystemTime},
+};
+
+use color_eyre::eyre::{self, WrapErr};
+use serde::{Deserialize, Serialize};
+
+use super::{Backend, Item, ItemKind, Scan, Status};
+use crate::auth::Auth;
+
+/// Wraps a backend that may not always be reachable (e.g. living on a network share)
+/// to remember the last items it listed on disk, and list those when it's unreachable.
+/// Levels listed with [`Backend::scan`] aren't cached.
+pub struct CachedBackend {
+    inner: Box<dyn Backend>,
+    /// Where the items are kept, one file per hierarchy path
+    dir: PathBuf,
+    /// The paths last listed from the cache, with when it was written
+    stale: RefCell<HashMap<Vec<String>, SystemTime>>,
+}
+
+/// What's kept of an item in the cache. The rest is only for showing it.
+#[derive(Debug, Serialize, Deserialize)]
+struct CachedItem {
+    name: String,
+    kind: ItemKind,
+    status: Option<Status>,
+    #[serde(default)]
+    metadata: BTreeMap<String, String>,
+    /// Seconds since the epoch
+    modified: Option<u64>,
+    #[serde(default)]
+    archived: bool,
+    thumbnail: Option<PathBuf>,
+    child_count: Option<usize>,
+    link_target: Option<PathBuf>,
+}
+
+impl From<&Item> for CachedItem {
+    fn from(item: &Item) -> Self {
+        Self {
+            name: item.name.clone(),
+            kind: item.kind,
+            status: item.status,
+            metadata: item.metadata.clone(),
+            modified: item
+                .modified
+                .and_then(|time| time.duration_since(SystemTime::UNIX_EPOCH).ok())
+                .map(|since_epoch| since_epoch.as_secs()),
+            archived: item.archived,
+            thumbnail: item.thumbnail.clone(),
+            child_count: item.child_count,
+            link_target: item.link_target.clone(),
+        }
+    }
+}
+
+impl From<CachedItem> for Item {
+    fn from(cached: CachedItem) -> Self {
+        let mut item = Item::new(cached.name, cached.kind).with_archived(cached.archived);
+        item.status = cached.status;
+        item.metadata = cached.metadata;
+        item.modified = cached
+            .modified
+            .map(|seconds| SystemTime::UNIX_EPOCH + Duration::from_secs(seconds));
+        item.thumbnail = cached.thumbnail;
+        item.child_count = cached.child_count;
+        item.link_target = cached.link_target;
+        item
+    }
+}
+
+impl CachedBackend {
+    /// Caches what `inner` lists in `dir`, which should only be used for that backend
+    pub fn new(inner: Box<dyn Backend>, dir: impl Into<PathBuf>) -> Self {
+        Self {
+            inner,
+            dir: dir.into(),
+            stale: RefCell::new(HashMap::new()),
+        }
+    }
+
+    /// The file keeping the items under `path`. Names are hashed, since they
+    /// could be anything: `..`, too long for a file name...
+    fn file_for(&self, path: &[String]) -> PathBuf {
+        self.dir.join(format!("{:016x}.json", cache_key(path)))
+    }
+
+    /// The items kept under `path`, with when they were written
+    fn read(&self, path: &[String]) -> Option<(Vec<Item>, SystemTime)> {
+        let file = self.file_for(path);
+        let written = fs::metadata(&file).and_then(|m| m.modified()).ok()?;
+        let contents = fs::read_to_string(&file).ok()?;
+        let items: Vec<CachedItem> = match serde_json::from_str(&contents) {
+            Ok(items) => items,
+            Err(e) => {
+                tracing::warn!("Ignoring the broken cache in {file:?}: {e}");
+                return None;
+            }
+        };
+        Some((items.into_iter().map(Item::from).collect(), written))
+    }
+
+    /// Keeps the page of items fetched under `path` from `offset`.
+    /// Pages after the first one are added to what's kept, as long as they follow it.
+    fn write(&self, path: &[String], offset: usize, page: &[Item]) -> color_eyre::Result<()> {
+        let mut items: Vec<CachedItem> = match (offset, self.read(path)) {
+            (0, _) => Vec::new(),
+            (_, Some((kept, _))) if kept.len() == offset => {
+                kept.iter().map(CachedItem::from).collect()
+            }
+            _ => return Ok(()),
+        };
+        items.extend(page.iter().map(CachedItem::from));
+
+        let file = self.file_for(path);
+        fs::create_dir_all(&self.dir)
+            .wrap_err_with(|| format!("Failed to create {:?}", self.dir))?;
+        fs::write(&file, serde_json::to_string(&items)?)
+            .wrap_err_with(|| format!("Failed to write {file:?}"))
+    }
+}
+
+/// FNV-1a of the path, which unlike the std hashers is the same from one run to the next
+fn cache_key(path: &[String]) -> u64 {
+    path.iter()
+        .flat_map(|name| name.bytes().chain([b'/']))
+        .fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+            (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+        })
+}
+
+impl Backend for CachedBackend {
+    fn children(&self, path: &[String]) -> color_eyre::Result<Vec<Item>> {
+        self.fetch(path, 0, usize::MAX)
+    }
+
+    fn fetch(&self, path: &[String], offset: usize, limit: usize) -> color_eyre::Result<Vec<Item>> {
+        let error = match self.inner.fetch(path, offset, limit) {
+            Ok(page) => {
+                self.stale.borrow_mut().remove(path);
+                if let Err(e) = self.write(path, offset, &page) {
+                    tracing::warn!("Failed to cache the items under {path:?}: {e:#}");
+                }
+                return Ok(page);
+            }
+            Err(e) => e,
+        };
+        if !self.inner.is_transient(&error) {
+            return Err(error);
+        }
+
+        let Some((items, written)) = self.read(path) else {
+            return Err(error);
+        };
+        tracing::warn!("Listing the items under {path:?} from the cache: {error:#}");
+        self.stale.borrow_mut().insert(path.to_vec(), written);
+        Ok(items.into_iter().skip(offset).take(limit).collect())
+    }
+
+    fn is_transient(&self, error: &eyre::Report) -> bool {
+        self.inner.is_transient(error)
+    }
+
+    fn scan(&self, path: &[String]) -> Option<Scan> {
+        self.inner.scan(path)
+    }
+
+    fn streaming(&self, path: &[String]) -> Option<usize> {
+        self.inner.streaming(path)
+    }
+
+    fn notice(&self, path: &[String]) -> Option<String> {
+        self.inner.notice(path)
+    }
+
+    fn invalidate(&self, path: &[String]) {
+        self.inner.invalidate(path)
+    }
+
+    fn authenticate(&mut self, auth: Auth) {
+        self.inner.authenticate(auth)
+    }
+
+    fn cached_since(&self, path: &[String]) -> Option<SystemTime> {
+        self.stale.borrow().get(path).copied()
+    }
+
+    fn create(&self, path: &[String], name: &str) -> color_eyre::Result<Item> {
+        self.inner.create(path, name)
+    }
+
+    fn location(&self, path: &[String]) -> Option<PathBuf> {
+        self.inner.location(path)
+    }
+
+    fn details(&self, path: &[String]) -> color_eyre::Result<Vec<(String, String)>> {
+        self.inner.details(path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use std::{cell::Cell, io, rc::Rc};
+
+    use super::*;
+    use crate::backend::MockBackend;
+
+    /// Lists the mock items until it's unplugged
+    struct Unplugged(Rc<Cell<bool>>);
+
+    impl Backend for Unplugged {
+        fn children(&self, path: &[String]) -> color_eyre::Result<Vec<Item>> {
+            if self.0.get() {
+                let timeout = io::Error::from(io::ErrorKind::TimedOut);
+                return Err(timeout).wrap_err("Failed to reach the server");
+            }
+            MockBackend.children(path)
+        }
+    }
+
+    #[test]
+    fn test_cached_items_are_listed_when_unreachable() -> color_eyre::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let unplugged = Rc::new(Cell::new(false));
+        let backend = CachedBackend::new(Box::new(Unplugged(unplugged.clone())), dir.path());
+        let path = vec!["man_vs_bee".to_string()];
+
+        let names =
+            |items: Vec<Item>| -> Vec<String> { items.into_iter().map(|i| i.name).collect() };
+        let listed = names(backend.fetch(&path, 0, 3)?);
+        backend.fetch(&path, 3, 100)?;
+        assert_eq!(backend.cached_since(&path), None);
+
+        unplugged.set(true);
+        assert_eq!(names(backend.fetch(&path, 0, 3)?), listed);
+        assert_eq!(backend.children(&path)?, MockBackend.children(&path)?);
+        assert!(backend.cached_since(&path).is_some());
+        // Nothing was ever listed there
+        assert!(backend.children(&[]).is_err());
+
+        unplugged.set(false);
+        backend.children(&path)?;
+        assert_eq!(backend.cached_since(&path), None);
+
+        Ok(())
+    }
+
+    /// Lists the mock items for requests sending the right token
+    #[derive(Default)]
+    struct Guarded(RefCell<Option<Auth>>);
+
+    impl Backend for Guarded {
+        fn children(&self, path: &[String]) -> color_eyre::Result<Vec<Item>> {
+            let mut auth = self.0.borrow_mut();
+            let Some(auth) = auth.as_mut() else {
+                return Err(crate::auth::Unauthorized.into());
+            };
+            auth.send(|token| match token.expose() {
+                "letmein" => MockBackend.children(path),
+                _ => Err(crate::auth::Unauthorized.into()),
+            })
+        }
+
+        fn authenticate(&mut self, auth: Auth) {
+            *self.0.get_mut() = Some(auth);
+        }
+    }
+
+    #[test]
+    fn test_credentials_reach_the_inner_backend() -> color_eyre::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let mut backend = CachedBackend::new(Box::<Guarded>::default(), dir.path());
+        assert!(backend.children(&[]).is_err());
+
+        let source = crate::auth::TokenSource::Command("echo letmein".into());
+        backend.authenticate(Auth::new(source));
+        assert_eq!(backend.children(&[])?, MockBackend.children(&[])?);
+
+        Ok(())
+    }
+}
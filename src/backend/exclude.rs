@@ -0,0 +1,117 @@
+use std::{fs, io, path::Path};
+
+use color_eyre::eyre::WrapErr;
+use regex::RegexSet;
+
+/// The file at the root of a tree listing directories to leave out, one glob per line
+pub const IGNORE_FILE: &str = ".cgsignore";
+
+/// Directories left out when listing or walking a tree, like `renders` or `tmp`.
+/// Globs are written like in a `.gitignore`: `*` and `?` match within a name and
+/// `**` across them. Globs without a `/` match the name of a directory at any
+/// level, the others its path from the root, like `*/renders`.
+#[derive(Debug, Clone, Default)]
+pub struct Excludes {
+    names: Option<RegexSet>,
+    paths: Option<RegexSet>,
+}
+
+impl Excludes {
+    /// Blank lines and lines starting with `#` are skipped
+    pub fn new<S: AsRef<str>>(globs: impl IntoIterator<Item = S>) -> Self {
+        let mut names = Vec::new();
+        let mut paths = Vec::new();
+        for glob in globs {
+            let glob = glob.as_ref().trim();
+            if glob.is_empty() || glob.starts_with('#') {
+                continue;
+            }
+            // Only directories are listed anyway
+            let glob = glob.trim_end_matches('/');
+            match glob.strip_prefix('/') {
+                Some(anchored) => paths.push(glob_regex(anchored)),
+                None if glob.contains('/') => paths.push(glob_regex(glob)),
+                None => names.push(glob_regex(glob)),
+            }
+        }
+
+        // Everything but the wildcards is escaped, so they're always valid
+        let set = |patterns: Vec<String>| {
+            (!patterns.is_empty()).then(|| RegexSet::new(patterns).expect("escaped globs"))
+        };
+        Self {
+            names: set(names),
+            paths: set(paths),
+        }
+    }
+
+    /// `globs` and the ones in the [`IGNORE_FILE`] at `root`, if there's one
+    pub fn load(root: &Path, globs: &[String]) -> color_eyre::Result<Self> {
+        let file = root.join(IGNORE_FILE);
+        let contents = match fs::read_to_string(&file) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => String::new(),
+            Err(e) => return Err(e).wrap_err_with(|| format!("failed to read {file:?}")),
+        };
+
+        Ok(Self::new(
+            contents.lines().chain(globs.iter().map(String::as_str)),
+        ))
+    }
+
+    /// Whether the directory at `path`, like `man_vs_bee/renders`, is left out
+    pub fn is_excluded(&self, path: &str) -> bool {
+        let name = path.rsplit('/').next().unwrap_or(path);
+        self.names.as_ref().is_some_and(|set| set.is_match(name))
+            || self.paths.as_ref().is_some_and(|set| set.is_match(path))
+    }
+}
+
+/// A regex matching the same paths as `glob`
+fn glob_regex(glob: &str) -> String {
+    let mut regex = String::from("^");
+    let mut rest = glob;
+    while let Some(c) = rest.chars().next() {
+        let (pattern, len) = if rest.starts_with("**/") {
+            ("(?:.*/)?".to_string(), 3)
+        } else if rest.starts_with("**") {
+            (".*".to_string(), 2)
+        } else if c == '*' {
+            ("[^/]*".to_string(), 1)
+        } else if c == '?' {
+            ("[^/]".to_string(), 1)
+        } else {
+            (regex::escape(&c.to_string()), c.len_utf8())
+        };
+        regex.push_str(&pattern);
+        rest = &rest[len..];
+    }
+    regex.push('$');
+    regex
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_excludes() {
+        let excludes = Excludes::new([
+            "# scratch space",
+            "renders/",
+            "tmp*",
+            "/man_vs_bee/sequence_00?",
+            "**/old/**",
+        ]);
+        assert!(excludes.is_excluded("renders"));
+        assert!(excludes.is_excluded("man_vs_bee/sequence_001/renders"));
+        assert!(excludes.is_excluded("project_001/tmp_2024"));
+        assert!(excludes.is_excluded("man_vs_bee/sequence_002"));
+        assert!(excludes.is_excluded("project_001/old/sh010"));
+        assert!(!excludes.is_excluded("man_vs_bee/sequence_010"));
+        assert!(!excludes.is_excluded("project_001/sequence_002"));
+        assert!(!excludes.is_excluded("my_renders"));
+        assert!(!Excludes::default().is_excluded("renders"));
+    }
+}
@@ -0,0 +1,465 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+
+use color_eyre::eyre::WrapErr;
+
+use super::{exclude::Excludes, walk::Walk, Backend, Item, ItemKind, Scan};
+use crate::details::format_time;
+
+/// Files looked for in shot directories to use as their thumbnail, in order
+const THUMBNAIL_NAMES: [&str; 3] = ["thumbnail.png", "thumbnail.six", "thumbnail.jpg"];
+
+/// Directories with at least this many entries are listed in the background
+const DEFAULT_SCAN_THRESHOLD: usize = 5000;
+
+/// Browses a directory tree laid out as `root/project/sequence/shot`
+#[derive(Debug)]
+pub struct FsBackend {
+    root: PathBuf,
+    /// How many entries make a directory worth listing in the background
+    scan_threshold: usize,
+    /// What's listed of each directory, shared with the walk and background scans
+    options: Arc<ListOptions>,
+    /// How many levels under the root the walk lists, when walking
+    walk_depth: Option<usize>,
+    /// What's listed at the top level instead of the projects, once it's shown
+    /// and until it's invalidated
+    walk: Mutex<Option<Arc<Walk>>>,
+}
+
+impl FsBackend {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self {
+            root: root.into(),
+            scan_threshold: DEFAULT_SCAN_THRESHOLD,
+            options: Arc::default(),
+            walk_depth: None,
+            walk: Mutex::default(),
+        }
+    }
+
+    pub fn with_scan_threshold(mut self, scan_threshold: usize) -> Self {
+        self.scan_threshold = scan_threshold;
+        self
+    }
+
+    /// Lists every directory `depth` levels under the root at the top level instead
+    /// of the projects, e.g. every shot with 3, named like `project/sequence/shot`.
+    /// They're shown as they're found, by reading directories in parallel.
+    pub fn with_walk_depth(mut self, depth: usize) -> Self {
+        self.walk_depth = Some(depth);
+        self
+    }
+
+    /// Leaves out the directories matching `excludes`, wherever they are
+    pub fn with_excludes(mut self, excludes: Excludes) -> Self {
+        Arc::make_mut(&mut self.options).excludes = excludes;
+        self
+    }
+
+    /// Lists symbolic links to directories too, and walks through them. Unless
+    /// `keep_cycles`, links back to a directory they were reached through are left out.
+    pub fn with_follow_symlinks(mut self, keep_cycles: bool) -> Self {
+        let options = Arc::make_mut(&mut self.options);
+        options.follow_symlinks = true;
+        options.keep_cycles = keep_cycles;
+        self
+    }
+
+    fn dir_for(&self, path: &[String]) -> PathBuf {
+        path.iter()
+            .fold(self.root.clone(), |dir, name| dir.join(name))
+    }
+
+    /// The walk, when it's what the given path lists. It starts the first time
+    /// it's asked for, and again after it's invalidated.
+    fn walk_for(&self, path: &[String]) -> Option<Arc<Walk>> {
+        let depth = self.walk_depth.filter(|_| path.is_empty())?;
+        let mut walk = self.walk.lock().unwrap_or_else(|e| e.into_inner());
+        let walk = walk.get_or_insert_with(|| {
+            Walk::start(self.root.clone(), depth, Arc::clone(&self.options))
+        });
+        Some(Arc::clone(walk))
+    }
+}
+
+/// The path of `name` under `parent`, both relative to the root
+pub(super) fn join(parent: &str, name: &str) -> String {
+    if parent.is_empty() {
+        name.to_string()
+    } else {
+        format!("{parent}/{name}")
+    }
+}
+
+/// How deep in the hierarchy a path goes. Walked items are several levels at once.
+fn depth(path: &[String]) -> usize {
+    path.iter().map(|name| name.split('/').count()).sum()
+}
+
+/// What's listed of a directory
+#[derive(Debug, Clone, Default)]
+pub(super) struct ListOptions {
+    /// Directories never listed, nor walked into
+    pub excludes: Excludes,
+    /// Whether symbolic links to directories are listed, as if they were directories
+    pub follow_symlinks: bool,
+    /// Whether links back to a directory they were reached through are listed too
+    pub keep_cycles: bool,
+}
+
+/// Whether following `link`, found in `dir`, leads back to `dir` or to a directory
+/// it was reached through, which would go round in circles
+fn links_back(dir: &Path, link: &Path) -> bool {
+    let Ok(target) = fs::canonicalize(link) else {
+        return false;
+    };
+    dir.ancestors()
+        .filter_map(|dir| fs::canonicalize(dir).ok())
+        .any(|dir| dir.starts_with(&target))
+}
+
+/// Lists the directories in `dir`, at `under` relative to the root, as items of the
+/// given kind sorted by name. `progress` is called with how many entries were
+/// looked at so far.
+pub(super) fn list_dir(
+    dir: &Path,
+    under: &str,
+    kind: ItemKind,
+    options: &ListOptions,
+    progress: &mut dyn FnMut(usize),
+) -> io::Result<Vec<Item>> {
+    let mut items = Vec::new();
+    for (scanned, entry) in fs::read_dir(dir)?.enumerate() {
+        progress(scanned);
+        let entry = entry?;
+        let name = entry.file_name().to_string_lossy().into_owned();
+        if name.starts_with('.') || options.excludes.is_excluded(&join(under, &name)) {
+            continue;
+        }
+
+        // The entry's own metadata doesn't follow links
+        let link_target = if entry.file_type()?.is_symlink() {
+            if !options.follow_symlinks {
+                continue;
+            }
+            fs::read_link(entry.path()).ok()
+        } else {
+            None
+        };
+        let metadata = match &link_target {
+            Some(_) => match fs::metadata(entry.path()) {
+                Ok(metadata) => metadata,
+                // Pointing to nothing, or nothing that can be read
+                Err(_) => continue,
+            },
+            None => entry.metadata()?,
+        };
+        if !metadata.is_dir() {
+            continue;
+        }
+        if link_target.is_some() && !options.keep_cycles && links_back(dir, &entry.path()) {
+            tracing::debug!("Not following {:?}, it links back up", entry.path());
+            continue;
+        }
+
+        // Children aren't counted: that's a directory read per item,
+        // which adds up on network shares
+        let mut item = Item::new(name, kind);
+        if let Some(target) = link_target {
+            item = item.with_link_target(target);
+        }
+        if let Ok(modified) = metadata.modified() {
+            item = item.with_modified(modified);
+        }
+        if kind == ItemKind::Shot {
+            let thumbnail = THUMBNAIL_NAMES
+                .iter()
+                .map(|name| entry.path().join(name))
+                .find(|path| path.is_file());
+            if let Some(thumbnail) = thumbnail {
+                item = item.with_thumbnail(thumbnail);
+            }
+        }
+        items.push(item);
+    }
+    items.sort_by(|a, b| a.name.cmp(&b.name));
+
+    Ok(items)
+}
+
+impl Backend for FsBackend {
+    fn children(&self, path: &[String]) -> color_eyre::Result<Vec<Item>> {
+        if let Some(walk) = self.walk_for(path) {
+            return Ok(walk.items().clone());
+        }
+        let dir = self.dir_for(path);
+        let kind = ItemKind::for_depth(depth(path));
+        list_dir(&dir, &path.join("/"), kind, &self.options, &mut |_| {})
+            .wrap_err_with(|| format!("failed to read {dir:?}"))
+    }
+
+    fn fetch(&self, path: &[String], offset: usize, limit: usize) -> color_eyre::Result<Vec<Item>> {
+        // Not to copy everything found so far for every page
+        if let Some(walk) = self.walk_for(path) {
+            let items = walk.items();
+            return Ok(items.iter().skip(offset).take(limit).cloned().collect());
+        }
+        Ok(self
+            .children(path)?
+            .into_iter()
+            .skip(offset)
+            .take(limit)
+            .collect())
+    }
+
+    fn scan(&self, path: &[String]) -> Option<Scan> {
+        if self.walk_for(path).is_some() {
+            return None;
+        }
+        let dir = self.dir_for(path);
+        // Small directories, the most common, aren't read through just to count them
+        let entries = fs::read_dir(&dir).ok()?;
+        if entries.take(self.scan_threshold).count() < self.scan_threshold {
+            return None;
+        }
+        let total = fs::read_dir(&dir).ok()?.count();
+
+        let kind = ItemKind::for_depth(depth(path));
+        let under = path.join("/");
+        let options = Arc::clone(&self.options);
+        Some(Scan::start(total, move |progress| {
+            list_dir(&dir, &under, kind, &options, progress)
+                .map_err(|e| format!("failed to read {dir:?}: {e}"))
+        }))
+    }
+
+    fn create(&self, path: &[String], name: &str) -> color_eyre::Result<Item> {
+        let dir = self.dir_for(path).join(name);
+        fs::create_dir(&dir).wrap_err_with(|| format!("failed to create {dir:?}"))?;
+
+        Ok(Item::new(name, ItemKind::for_depth(depth(path))))
+    }
+
+    fn streaming(&self, path: &[String]) -> Option<usize> {
+        let walk = self.walk_for(path).filter(|walk| !walk.is_done())?;
+        let found = walk.items().len();
+        Some(found)
+    }
+
+    fn invalidate(&self, path: &[String]) {
+        // The walk goes through every level down to its depth
+        if self
+            .walk_depth
+            .is_some_and(|walk_depth| depth(path) < walk_depth)
+        {
+            let walk = self.walk.lock().unwrap_or_else(|e| e.into_inner()).take();
+            if let Some(walk) = walk {
+                walk.cancel();
+            }
+        }
+    }
+
+    fn notice(&self, path: &[String]) -> Option<String> {
+        match self.walk_for(path)?.unreadable() {
+            0 => None,
+            1 => Some("1 directory couldn't be read".to_string()),
+            unreadable => Some(format!("{unreadable} directories couldn't be read")),
+        }
+    }
+
+    fn location(&self, path: &[String]) -> Option<PathBuf> {
+        Some(self.dir_for(path))
+    }
+
+    fn details(&self, path: &[String]) -> color_eyre::Result<Vec<(String, String)>> {
+        let dir = self.dir_for(path);
+        let metadata = fs::metadata(&dir).wrap_err_with(|| format!("failed to read {dir:?}"))?;
+
+        let mut details = vec![("location".to_string(), dir.display().to_string())];
+        if let Ok(target) = fs::read_link(&dir) {
+            details.push(("links to".to_string(), target.display().to_string()));
+        }
+        // Not every filesystem records it
+        if let Ok(created) = metadata.created() {
+            details.push(("created".to_string(), format_time(created)));
+        }
+        let entries = fs::read_dir(&dir)
+            .wrap_err_with(|| format!("failed to read {dir:?}"))?
+            .count();
+        details.push(("entries".to_string(), entries.to_string()));
+
+        Ok(details)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::backend::IGNORE_FILE;
+
+    #[test]
+    fn test_list_and_create() -> color_eyre::Result<()> {
+        let root = tempfile::tempdir()?;
+        fs::create_dir_all(root.path().join("man_vs_bee").join("sequence_001"))?;
+        fs::create_dir(root.path().join(".snapshots"))?;
+        fs::write(root.path().join("notes.txt"), "not a project")?;
+
+        let backend = FsBackend::new(root.path());
+        let projects = backend.children(&[])?;
+        assert_eq!(projects.len(), 1);
+        assert_eq!(projects[0].kind, ItemKind::Project);
+
+        let path = vec!["man_vs_bee".to_string()];
+        let sequence = backend.create(&path, "sequence_002")?;
+        assert_eq!(sequence.kind, ItemKind::Sequence);
+
+        let names: Vec<String> = backend
+            .children(&path)?
+            .into_iter()
+            .map(|i| i.name)
+            .collect();
+        assert_eq!(names, vec!["sequence_001", "sequence_002"]);
+
+        let shot_dir = root.path().join("man_vs_bee/sequence_001/sh010");
+        fs::create_dir_all(&shot_dir)?;
+        fs::write(shot_dir.join("thumbnail.png"), "")?;
+        let shots = backend.children(&["man_vs_bee".to_string(), "sequence_001".to_string()])?;
+        assert_eq!(shots[0].thumbnail, Some(shot_dir.join("thumbnail.png")));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_walk() -> color_eyre::Result<()> {
+        let root = tempfile::tempdir()?;
+        for dir in [
+            "man_vs_bee/sequence_001/sh010",
+            "man_vs_bee/sequence_001/sh020",
+        ] {
+            fs::create_dir_all(root.path().join(dir))?;
+        }
+        fs::create_dir_all(root.path().join("project_001/sequence_002/sh010"))?;
+        fs::create_dir_all(root.path().join("project_001/.snapshots/sh010"))?;
+
+        let backend = FsBackend::new(root.path()).with_walk_depth(3);
+        while backend.streaming(&[]).is_some() {
+            std::thread::sleep(std::time::Duration::from_millis(5));
+        }
+        let mut shots = backend.children(&[])?;
+        shots.sort_by(|a, b| a.name.cmp(&b.name));
+        let names: Vec<&str> = shots.iter().map(|i| i.name.as_str()).collect();
+        assert_eq!(
+            names,
+            [
+                "man_vs_bee/sequence_001/sh010",
+                "man_vs_bee/sequence_001/sh020",
+                "project_001/sequence_002/sh010"
+            ]
+        );
+        assert_eq!(shots[0].kind, ItemKind::Shot);
+        assert!(backend.location(&[shots[0].name.clone()]).unwrap().is_dir());
+
+        // Walking to the sequences, their shots are still shots
+        let backend = FsBackend::new(root.path()).with_walk_depth(2);
+        while backend.streaming(&[]).is_some() {
+            std::thread::sleep(std::time::Duration::from_millis(5));
+        }
+        assert_eq!(backend.fetch(&[], 0, 10)?.len(), 2);
+        let shots = backend.children(&["man_vs_bee/sequence_001".to_string()])?;
+        assert_eq!(shots[0].kind, ItemKind::Shot);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_excluded_directories() -> color_eyre::Result<()> {
+        let root = tempfile::tempdir()?;
+        for dir in ["man_vs_bee/sequence_001/sh010", "man_vs_bee/renders/sh010"] {
+            fs::create_dir_all(root.path().join(dir))?;
+        }
+        fs::create_dir_all(root.path().join("man_vs_bee/sequence_001/tmp"))?;
+        fs::write(root.path().join(IGNORE_FILE), "renders/\n")?;
+
+        let excludes = Excludes::load(root.path(), &["*/*/tmp".to_string()])?;
+        let backend = FsBackend::new(root.path()).with_excludes(excludes);
+        let path = ["man_vs_bee".to_string()];
+        let sequences: Vec<String> = backend
+            .children(&path)?
+            .into_iter()
+            .map(|i| i.name)
+            .collect();
+        assert_eq!(sequences, ["sequence_001"]);
+
+        // Nor walked into
+        let backend = backend.with_walk_depth(3);
+        while backend.streaming(&[]).is_some() {
+            std::thread::sleep(std::time::Duration::from_millis(5));
+        }
+        let shots: Vec<String> = backend.children(&[])?.into_iter().map(|i| i.name).collect();
+        assert_eq!(shots, ["man_vs_bee/sequence_001/sh010"]);
+
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_follow_symlinks() -> color_eyre::Result<()> {
+        use std::os::unix::fs::symlink;
+
+        let root = tempfile::tempdir()?;
+        let project = root.path().join("man_vs_bee");
+        fs::create_dir_all(project.join("sequence_001/sh010"))?;
+        fs::create_dir_all(root.path().join("shared/sequence_009"))?;
+        let shared = root.path().join("shared/sequence_009");
+        symlink(&shared, project.join("sequence_009"))?;
+        symlink(&project, project.join("sequence_001/back_up"))?;
+        symlink(root.path().join("missing"), project.join("dangling"))?;
+
+        let names = |backend: &FsBackend, path: &[&str]| -> color_eyre::Result<Vec<String>> {
+            let path: Vec<String> = path.iter().map(|name| name.to_string()).collect();
+            Ok(backend
+                .children(&path)?
+                .into_iter()
+                .map(|i| i.name)
+                .collect())
+        };
+        let backend = FsBackend::new(root.path());
+        assert_eq!(names(&backend, &["man_vs_bee"])?, ["sequence_001"]);
+
+        let backend = FsBackend::new(root.path()).with_follow_symlinks(false);
+        let sequences = backend.children(&["man_vs_bee".to_string()])?;
+        assert_eq!(sequences[1].name, "sequence_009");
+        assert_eq!(sequences[1].link_target, Some(shared));
+        assert_eq!(names(&backend, &["man_vs_bee", "sequence_001"])?, ["sh010"]);
+
+        let backend = FsBackend::new(root.path()).with_follow_symlinks(true);
+        assert_eq!(
+            names(&backend, &["man_vs_bee", "sequence_001"])?,
+            ["back_up", "sh010"]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_big_directories_are_scanned() -> color_eyre::Result<()> {
+        let root = tempfile::tempdir()?;
+        for name in ["man_vs_bee", "bee_movie_2", "project_001"] {
+            fs::create_dir(root.path().join(name))?;
+        }
+
+        assert!(FsBackend::new(root.path()).scan(&[]).is_none());
+        let backend = FsBackend::new(root.path()).with_scan_threshold(3);
+        let scan = backend.scan(&[]).expect("3 entries should be scanned");
+        assert_eq!(scan.total, 3);
+        let names: Vec<String> = scan.wait().unwrap().into_iter().map(|i| i.name).collect();
+        assert_eq!(names, ["bee_movie_2", "man_vs_bee", "project_001"]);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,120 @@
+use std::time::{Duration, SystemTime};
+
+use super::{Backend, Item, ItemKind, Status};
+use crate::details::format_time;
+
+/// A hard-coded hierarchy, useful for trying out the UI
+#[derive(Debug, Default)]
+pub struct MockBackend;
+
+const MOCK_PROJECTS: [&str; 10] = [
+    "man_vs_bee",
+    "asset_library_2024",
+    "project_001",
+    "project_002",
+    "the_long_night",
+    "bee_movie_2",
+    "commercial_shoe",
+    "commercial_car",
+    "rnd_fluids",
+    "training_2023",
+];
+
+const MOCK_ARCHIVED_PROJECTS: [&str; 2] = ["rnd_fluids", "training_2023"];
+
+/// A fixed point in time (seconds since the epoch), so the mock stays deterministic
+const MOCK_NOW: u64 = 1_700_000_000;
+
+/// How many sequences every project has, besides its assets and renders
+const MOCK_SEQUENCES: usize = 4;
+
+const MOCK_SHOTS: usize = 5;
+
+const MOCK_ARTISTS: [&str; 3] = ["anna", "bob", "carla"];
+
+impl Backend for MockBackend {
+    fn children(&self, path: &[String]) -> color_eyre::Result<Vec<Item>> {
+        let items = match path.len() {
+            // Pretend projects further down the list were touched longer ago
+            0 => MOCK_PROJECTS
+                .iter()
+                .enumerate()
+                .map(|(i, name)| {
+                    Item::new(*name, ItemKind::Project)
+                        .with_child_count(MOCK_SEQUENCES)
+                        .with_archived(MOCK_ARCHIVED_PROJECTS.contains(name))
+                        .with_modified(
+                            SystemTime::UNIX_EPOCH
+                                + Duration::from_secs(MOCK_NOW - 3600 * i as u64),
+                        )
+                })
+                .collect(),
+            1 => (1..=3)
+                .map(|i| format!("sequence_{i:03}"))
+                .chain(["sequence_éclair".to_string()])
+                .map(|name| Item::new(name, ItemKind::Sequence).with_child_count(MOCK_SHOTS))
+                .chain(
+                    ["hero_bee", "hive_env"]
+                        .iter()
+                        .map(|name| Item::new(*name, ItemKind::Asset)),
+                )
+                .chain(
+                    ["comp_v001", "comp_v002"]
+                        .iter()
+                        .map(|name| Item::new(*name, ItemKind::Render)),
+                )
+                .collect(),
+            2 => (1..=MOCK_SHOTS)
+                .map(|i| {
+                    Item::new(format!("sh{:03}", i * 10), ItemKind::Shot)
+                        .with_status(Status::ALL[i % Status::ALL.len()])
+                        .with_metadata("artist", MOCK_ARTISTS[i % MOCK_ARTISTS.len()])
+                })
+                .collect(),
+            _ => Vec::new(),
+        };
+
+        Ok(items)
+    }
+
+    /// Shots pretend to be 4 seconds long at 24fps, starting at the usual frame 1001
+    fn details(&self, path: &[String]) -> color_eyre::Result<Vec<(String, String)>> {
+        let created = SystemTime::UNIX_EPOCH + Duration::from_secs(MOCK_NOW);
+        let mut details = vec![("created".to_string(), format_time(created))];
+        if path.len() == 3 {
+            details.push(("frame range".to_string(), "1001-1096".to_string()));
+        }
+        Ok(details)
+    }
+
+    /// Pretends to create the item: the mock hierarchy never changes
+    fn create(&self, path: &[String], name: &str) -> color_eyre::Result<Item> {
+        Ok(Item::new(name, ItemKind::for_depth(path.len())))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_mock_hierarchy() -> color_eyre::Result<()> {
+        let backend = MockBackend;
+        let projects = backend.children(&[])?;
+        assert_eq!(projects.len(), 10);
+        assert_eq!(projects.iter().filter(|i| i.archived).count(), 2);
+
+        let path = vec![projects[0].name.clone()];
+        let sequences = backend.children(&path)?;
+        assert!(sequences.iter().any(|i| i.kind == ItemKind::Asset));
+        assert!(sequences.iter().any(|i| i.kind == ItemKind::Render));
+
+        let path = vec![path[0].clone(), "sequence_001".to_string()];
+        let shots = backend.children(&path)?;
+        assert!(shots.iter().all(|i| i.kind.is_leaf()));
+        assert!(shots.iter().all(|i| i.status.is_some()));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,386 @@
+use std::{collections::BTreeMap, io, ops::Range, path::PathBuf, time::SystemTime};
+
+use color_eyre::eyre;
+use ratatui::style::Style;
+use serde::{Deserialize, Serialize};
+
+use crate::auth::Auth;
+
+mod cache;
+mod exclude;
+mod fs;
+mod mock;
+mod scan;
+mod stdin;
+mod synthetic;
+mod walk;
+
+pub use cache::CachedBackend;
+pub use exclude::{Excludes, IGNORE_FILE};
+pub use fs::FsBackend;
+pub use mock::MockBackend;
+pub use scan::Scan;
+pub use stdin::{LineOptions, StdinBackend};
+pub use synthetic::SyntheticBackend;
+
+/// The longest name accepted when creating a new entity
+pub const MAX_NAME_LEN: usize = 64;
+
+/// The kind of entity an item represents in the studio hierarchy
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ItemKind {
+    Project,
+    Sequence,
+    Shot,
+    Asset,
+    /// A rendered output, e.g. a comp version
+    Render,
+    /// A plain line of text, e.g. read from stdin
+    Line,
+}
+
+impl ItemKind {
+    /// Returns the nerd-font glyph for this kind, or a short ASCII badge
+    /// when the terminal can't be trusted with unicode
+    pub fn icon(&self, unicode: bool) -> &'static str {
+        match (self, unicode) {
+            (ItemKind::Project, true) => "\u{f07b}",
+            (ItemKind::Sequence, true) => "\u{f008}",
+            (ItemKind::Shot, true) => "\u{f03d}",
+            (ItemKind::Asset, true) => "\u{f1b2}",
+            (ItemKind::Render, true) => "\u{f03e}",
+            (ItemKind::Line, true) => "\u{f15c}",
+            (ItemKind::Project, false) => "PRJ",
+            (ItemKind::Sequence, false) => "SEQ",
+            (ItemKind::Shot, false) => "SHT",
+            (ItemKind::Asset, false) => "AST",
+            (ItemKind::Render, false) => "RND",
+            (ItemKind::Line, false) => "TXT",
+        }
+    }
+
+    /// A human readable name for this kind
+    pub fn label(&self) -> &'static str {
+        match self {
+            ItemKind::Project => "project",
+            ItemKind::Sequence => "sequence",
+            ItemKind::Shot => "shot",
+            ItemKind::Asset => "asset",
+            ItemKind::Render => "render",
+            ItemKind::Line => "line",
+        }
+    }
+
+    /// The name of a group of items of this kind, like `sequences`
+    pub fn group_label(&self) -> &'static str {
+        match self {
+            ItemKind::Project => "projects",
+            ItemKind::Sequence => "sequences",
+            ItemKind::Shot => "shots",
+            ItemKind::Asset => "assets",
+            ItemKind::Render => "renders",
+            ItemKind::Line => "lines",
+        }
+    }
+
+    /// Says how many children an item of this kind has, like `12 seqs`
+    pub fn children_label(&self, count: usize) -> String {
+        let child = match self {
+            ItemKind::Project => "seq",
+            ItemKind::Sequence => "shot",
+            _ => "item",
+        };
+        let plural = if count == 1 { "" } else { "s" };
+        format!("{count} {child}{plural}")
+    }
+
+    /// The kind of the items found at the given depth of the hierarchy
+    pub fn for_depth(depth: usize) -> Self {
+        match depth {
+            0 => ItemKind::Project,
+            1 => ItemKind::Sequence,
+            _ => ItemKind::Shot,
+        }
+    }
+
+    /// Whether accepting an item of this kind ends the navigation
+    pub fn is_leaf(&self) -> bool {
+        matches!(
+            self,
+            ItemKind::Shot | ItemKind::Asset | ItemKind::Render | ItemKind::Line
+        )
+    }
+}
+
+/// The production status of an item, as tracked by the studio
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Status {
+    Wip,
+    Review,
+    Approved,
+    Omitted,
+}
+
+impl Status {
+    pub const ALL: [Status; 4] = [
+        Status::Wip,
+        Status::Review,
+        Status::Approved,
+        Status::Omitted,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Status::Wip => "wip",
+            Status::Review => "review",
+            Status::Approved => "approved",
+            Status::Omitted => "omitted",
+        }
+    }
+}
+
+/// A single entry that can be listed, searched and selected
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Item {
+    pub name: String,
+    pub kind: ItemKind,
+    /// Not every item has a status, e.g. projects usually don't
+    pub status: Option<Status>,
+    /// Free-form key/value pairs (artist, frame range...) that can be filtered on
+    pub metadata: BTreeMap<String, String>,
+    /// When the item was last modified, if the backend can tell
+    pub modified: Option<SystemTime>,
+    /// Archived items are hidden unless explicitly asked for
+    pub archived: bool,
+    /// What the query is matched against, when it's not the whole name
+    pub match_text: Option<String>,
+    /// Byte ranges of the name to draw with a specific style, e.g. from ANSI colors
+    pub name_styles: Vec<(Range<usize>, Style)>,
+    /// An image showing what the entity looks like, e.g. a frame of a shot
+    pub thumbnail: Option<PathBuf>,
+    /// How many items live under this one, when the backend can count them cheaply
+    pub child_count: Option<usize>,
+    /// Where the item points to, when it's a link to another one, e.g. a symbolic link
+    pub link_target: Option<PathBuf>,
+}
+
+impl Item {
+    pub fn new(name: impl Into<String>, kind: ItemKind) -> Self {
+        Self {
+            name: name.into(),
+            kind,
+            status: None,
+            metadata: BTreeMap::new(),
+            modified: None,
+            archived: false,
+            match_text: None,
+            name_styles: Vec::new(),
+            thumbnail: None,
+            child_count: None,
+            link_target: None,
+        }
+    }
+
+    pub fn with_status(mut self, status: Status) -> Self {
+        self.status = Some(status);
+        self
+    }
+
+    pub fn with_metadata(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.metadata.insert(key.into(), value.into());
+        self
+    }
+
+    pub fn with_modified(mut self, modified: SystemTime) -> Self {
+        self.modified = Some(modified);
+        self
+    }
+
+    pub fn with_archived(mut self, archived: bool) -> Self {
+        self.archived = archived;
+        self
+    }
+
+    pub fn with_name_styles(mut self, name_styles: Vec<(Range<usize>, Style)>) -> Self {
+        self.name_styles = name_styles;
+        self
+    }
+
+    pub fn with_thumbnail(mut self, thumbnail: impl Into<PathBuf>) -> Self {
+        self.thumbnail = Some(thumbnail.into());
+        self
+    }
+
+    pub fn with_child_count(mut self, child_count: usize) -> Self {
+        self.child_count = Some(child_count);
+        self
+    }
+
+    pub fn with_link_target(mut self, link_target: impl Into<PathBuf>) -> Self {
+        self.link_target = Some(link_target.into());
+        self
+    }
+
+    /// The text the query should be matched against
+    pub fn match_text(&self) -> &str {
+        self.match_text.as_deref().unwrap_or(&self.name)
+    }
+}
+
+/// A source of items, organized as a hierarchy of projects, sequences and shots
+pub trait Backend {
+    /// Returns the items living directly under the given hierarchy path.
+    /// An empty path means the top level.
+    fn children(&self, path: &[String]) -> color_eyre::Result<Vec<Item>>;
+
+    /// Returns at most `limit` of the items under the given path, skipping the
+    /// first `offset` ones. Fewer than `limit` items means there are no more.
+    /// Backends with huge listings should override this to avoid listing everything.
+    fn fetch(&self, path: &[String], offset: usize, limit: usize) -> color_eyre::Result<Vec<Item>> {
+        Ok(self
+            .children(path)?
+            .into_iter()
+            .skip(offset)
+            .take(limit)
+            .collect())
+    }
+
+    /// Whether listing items failed for a reason that may go away by itself,
+    /// like a timeout, so it's worth trying again.
+    /// By default, that's when it comes from such an I/O error.
+    fn is_transient(&self, error: &eyre::Report) -> bool {
+        error.chain().any(|cause| {
+            cause.downcast_ref::<io::Error>().is_some_and(|e| {
+                matches!(
+                    e.kind(),
+                    io::ErrorKind::TimedOut
+                        | io::ErrorKind::Interrupted
+                        | io::ErrorKind::WouldBlock
+                        | io::ErrorKind::ConnectionRefused
+                        | io::ErrorKind::ConnectionReset
+                        | io::ErrorKind::ConnectionAborted
+                        | io::ErrorKind::NotConnected
+                )
+            })
+        })
+    }
+
+    /// Starts listing the items under the given path in the background, for
+    /// levels where that takes long enough to show progress.
+    /// `None` means the items are fetched with [`Backend::fetch`] instead.
+    fn scan(&self, path: &[String]) -> Option<Scan> {
+        let _ = path;
+        None
+    }
+
+    /// How many items there are under the given path so far, while the backend is
+    /// still reading them in the background, e.g. from a command's output.
+    /// `None` once they're all there, or when the backend doesn't stream them.
+    fn streaming(&self, path: &[String]) -> Option<usize> {
+        let _ = path;
+        None
+    }
+
+    /// Something worth telling about the items under the given path, like
+    /// some of them being left out
+    fn notice(&self, path: &[String]) -> Option<String> {
+        let _ = path;
+        None
+    }
+
+    /// Forgets what was read under the given path, so it's read again the next
+    /// time it's listed, e.g. after it changed on disk
+    fn invalidate(&self, path: &[String]) {
+        let _ = path;
+    }
+
+    /// Hands over the credentials to send along with requests, for backends
+    /// talking to a server, which make them through [`Auth::send`]. Backends
+    /// reading local data have no use for them.
+    fn authenticate(&mut self, auth: Auth) {
+        tracing::debug!("The backend doesn't use a token, ignoring it");
+        let _ = auth;
+    }
+
+    /// When the items last listed under the given path came from a cache,
+    /// because the backend couldn't be reached, when that cache was written
+    fn cached_since(&self, path: &[String]) -> Option<SystemTime> {
+        let _ = path;
+        None
+    }
+
+    /// Creates a new entity called `name` under the given hierarchy path.
+    /// The name has already been checked with [`validate_name`].
+    fn create(&self, path: &[String], name: &str) -> color_eyre::Result<Item> {
+        let _ = (path, name);
+        Err(eyre::eyre!("this backend doesn't support creating items"))
+    }
+
+    /// Where the entity at the given hierarchy path lives on disk, if anywhere
+    fn location(&self, path: &[String]) -> Option<PathBuf> {
+        let _ = path;
+        None
+    }
+
+    /// What else is known about the entity at the given hierarchy path, as key/value
+    /// pairs in the order to show them. Only asked for when the user wants to see them,
+    /// so it can be slower than listing items.
+    fn details(&self, path: &[String]) -> color_eyre::Result<Vec<(String, String)>> {
+        let _ = path;
+        Ok(Vec::new())
+    }
+}
+
+/// Checks that `name` is usable for a new entity: made of ASCII letters,
+/// digits, `_`, `-` and `.`, not starting with a `.` or `-`.
+pub fn validate_name(name: &str) -> Result<(), String> {
+    if name.is_empty() {
+        return Err("name is empty".to_string());
+    }
+    if name.len() > MAX_NAME_LEN {
+        return Err(format!("name is longer than {MAX_NAME_LEN} characters"));
+    }
+    if name.starts_with(['.', '-']) {
+        return Err("name can't start with '.' or '-'".to_string());
+    }
+    if let Some(c) = name
+        .chars()
+        .find(|c| !(c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.')))
+    {
+        return Err(format!("'{c}' is not allowed in names"));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_icon_ascii_fallback() {
+        assert_eq!(ItemKind::Shot.icon(false), "SHT");
+        assert!(!ItemKind::Shot.icon(true).is_ascii());
+    }
+
+    #[test]
+    fn test_children_label() {
+        assert_eq!(ItemKind::Project.children_label(12), "12 seqs");
+        assert_eq!(ItemKind::Sequence.children_label(1), "1 shot");
+        assert_eq!(ItemKind::Shot.children_label(0), "0 items");
+    }
+
+    #[test]
+    fn test_validate_name() {
+        assert!(validate_name("sh010").is_ok());
+        assert!(validate_name("asset_v2.1-final").is_ok());
+        assert!(validate_name("").is_err());
+        assert!(validate_name(".hidden").is_err());
+        assert!(validate_name("with space").is_err());
+        assert!(validate_name("éclair").is_err());
+        assert!(validate_name(&"a".repeat(MAX_NAME_LEN + 1)).is_err());
+    }
+}
@@ -0,0 +1,85 @@
+use std::{
+    sync::mpsc::{self, Receiver, TryRecvError},
+    thread,
+};
+
+use super::Item;
+
+/// Progress is sent after this many entries, not to flood the channel
+const PROGRESS_STEP: usize = 100;
+
+/// What the background thread of a [`Scan`] reports back
+#[derive(Debug)]
+enum Update {
+    Progress(usize),
+    Done(Result<Vec<Item>, String>),
+}
+
+/// A listing running in a background thread, for levels too big to list
+/// without the UI looking frozen. See [`Backend::scan`](super::Backend::scan).
+#[derive(Debug)]
+pub struct Scan {
+    /// How many entries there are to look at
+    pub total: usize,
+    /// How many were looked at so far
+    pub scanned: usize,
+    receiver: Receiver<Update>,
+}
+
+impl Scan {
+    /// Runs `list` in a background thread. It's given a function to call
+    /// with the number of entries it looked at so far, out of `total`.
+    pub fn start<F>(total: usize, list: F) -> Self
+    where
+        F: FnOnce(&mut dyn FnMut(usize)) -> Result<Vec<Item>, String> + Send + 'static,
+    {
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            let mut progress = |scanned: usize| {
+                if scanned.is_multiple_of(PROGRESS_STEP) {
+                    let _ = sender.send(Update::Progress(scanned));
+                }
+            };
+            let result = list(&mut progress);
+            let _ = sender.send(Update::Done(result));
+        });
+
+        Self {
+            total,
+            scanned: 0,
+            receiver,
+        }
+    }
+
+    /// Collects the progress made so far. Returns the items once the scan is done.
+    pub fn poll(&mut self) -> Option<Result<Vec<Item>, String>> {
+        loop {
+            match self.receiver.try_recv() {
+                Ok(Update::Progress(scanned)) => self.scanned = scanned,
+                Ok(Update::Done(result)) => return Some(result),
+                Err(TryRecvError::Empty) => return None,
+                Err(TryRecvError::Disconnected) => {
+                    return Some(Err("the scan stopped unexpectedly".to_string()))
+                }
+            }
+        }
+    }
+
+    /// Blocks until the scan is done
+    pub fn wait(self) -> Result<Vec<Item>, String> {
+        for update in self.receiver {
+            if let Update::Done(result) = update {
+                return result;
+            }
+        }
+        Err("the scan stopped unexpectedly".to_string())
+    }
+
+    /// How far along the scan is, from 0 to 1
+    pub fn ratio(&self) -> f64 {
+        if self.total == 0 {
+            return 1.0;
+        }
+        (self.scanned as f64 / self.total as f64).min(1.0)
+    }
+}
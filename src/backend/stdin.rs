@@ -0,0 +1,449 @@
+use std::{
+    collections::HashSet,
+    io::{self, BufRead, BufReader},
+    process::{Command, Stdio},
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    thread,
+};
+
+use color_eyre::eyre::WrapErr;
+
+use super::{Backend, Item, ItemKind};
+use crate::ansi;
+
+/// How lines are turned into items, and how many of them are kept
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LineOptions {
+    /// Turn color escape codes into styles instead of keeping them as text
+    pub ansi: bool,
+    /// Stop reading after this many items
+    pub max_items: Option<usize>,
+    /// Cut lines longer than this many characters, e.g. from a binary file
+    pub max_line_len: Option<usize>,
+    /// Drop lines seen before, keeping the first one
+    pub unique: bool,
+}
+
+/// A flat list of lines, usually piped in through stdin. They can be read
+/// all at once, or in a background thread while the app is already shown.
+#[derive(Debug)]
+pub struct StdinBackend {
+    lines: Arc<Lines>,
+}
+
+/// No lines, and none coming
+impl Default for StdinBackend {
+    fn default() -> Self {
+        let lines = Lines::default();
+        lines.done.store(true, Ordering::Release);
+        Self {
+            lines: Arc::new(lines),
+        }
+    }
+}
+
+/// The lines read so far, shared with the thread reading them
+#[derive(Debug, Default)]
+struct Lines {
+    items: Mutex<Vec<Item>>,
+    /// Whether the reading thread is done, or there never was one
+    done: AtomicBool,
+    /// Whether reading stopped at [`LineOptions::max_items`]
+    capped: AtomicBool,
+    /// How many lines were cut at [`LineOptions::max_line_len`]
+    cut: AtomicUsize,
+    /// The lines read so far, with [`LineOptions::unique`]
+    seen: Mutex<HashSet<String>>,
+    /// How many lines were dropped for being seen before
+    duplicates: AtomicUsize,
+    /// Why the command printing the lines failed, if it did
+    failure: Mutex<Option<String>>,
+}
+
+impl Lines {
+    fn items(&self) -> std::sync::MutexGuard<'_, Vec<Item>> {
+        // A panic while pushing a line leaves the others usable
+        self.items.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Adds a line of input, unless it's empty. Bytes that aren't UTF-8 are
+    /// replaced. `cut_short` tells whether the end of the line was left unread.
+    /// Returns whether there's room for more.
+    fn push(&self, line: &[u8], cut_short: bool, options: &LineOptions) -> bool {
+        let line = line.strip_suffix(b"\r").unwrap_or(line);
+        let mut line = String::from_utf8_lossy(line).into_owned();
+        let mut cut = cut_short;
+        if let Some(max) = options.max_line_len {
+            if let Some((end, _)) = line.char_indices().nth(max) {
+                line.truncate(end);
+                cut = true;
+            }
+        }
+        if cut {
+            self.cut.fetch_add(1, Ordering::Relaxed);
+        }
+
+        if options.unique && !line.is_empty() {
+            let mut seen = self.seen.lock().unwrap_or_else(|e| e.into_inner());
+            if !seen.insert(line.clone()) {
+                self.duplicates.fetch_add(1, Ordering::Relaxed);
+                return true;
+            }
+        }
+
+        let mut items = self.items();
+        items.extend(line_item(line, options.ansi));
+        let full = options.max_items.is_some_and(|max| items.len() >= max);
+        self.capped.store(full, Ordering::Relaxed);
+        !full
+    }
+}
+
+impl StdinBackend {
+    /// Reads every line until EOF. Empty lines are skipped.
+    pub fn from_reader(mut reader: impl BufRead, options: LineOptions) -> color_eyre::Result<Self> {
+        let lines = Lines::default();
+        let mut line = Vec::new();
+        while let Some(cut) =
+            read_line(&mut reader, &mut line, &options).wrap_err("failed to read an input line")?
+        {
+            if !lines.push(&line, cut, &options) {
+                break;
+            }
+        }
+
+        lines.done.store(true, Ordering::Release);
+        Ok(Self {
+            lines: Arc::new(lines),
+        })
+    }
+
+    /// Reads lines in a background thread, so they can be matched while more
+    /// are coming, see [`Backend::streaming`]
+    pub fn streaming(reader: impl BufRead + Send + 'static, options: LineOptions) -> Self {
+        Self::streaming_then(reader, options, |_| {})
+    }
+
+    /// Like [`StdinBackend::streaming`], calling `finish` once the reader is
+    /// exhausted, or dropped once there's no room for more lines
+    fn streaming_then(
+        mut reader: impl BufRead + Send + 'static,
+        options: LineOptions,
+        finish: impl FnOnce(&Lines) + Send + 'static,
+    ) -> Self {
+        let lines = Arc::new(Lines::default());
+        let shared = Arc::clone(&lines);
+        thread::spawn(move || {
+            let mut line = Vec::new();
+            loop {
+                match read_line(&mut reader, &mut line, &options) {
+                    Ok(Some(cut)) if shared.push(&line, cut, &options) => {}
+                    Ok(_) => break,
+                    Err(e) => {
+                        tracing::warn!("Stopped reading the input: {e}");
+                        break;
+                    }
+                }
+            }
+            drop(reader);
+            finish(&shared);
+            shared.done.store(true, Ordering::Release);
+        });
+
+        Self { lines }
+    }
+
+    /// Runs `command` with `sh` and reads the lines it prints as they come.
+    /// When it fails, the last line of its errors is told as a notice.
+    pub fn from_command(command: &str, options: LineOptions) -> color_eyre::Result<Self> {
+        let mut child = Command::new("sh")
+            .arg("-c")
+            .arg(command)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            // Kept off the terminal, as it would be drawn over the app
+            .stderr(Stdio::piped())
+            .spawn()
+            .wrap_err_with(|| format!("failed to run {command:?}"))?;
+        let stdout = child.stdout.take().expect("the output is piped");
+        let stderr = child.stderr.take().expect("the errors are piped");
+        let errors = thread::spawn(move || last_error(BufReader::new(stderr)));
+
+        let command = command.to_string();
+        let finish = move |lines: &Lines| {
+            let last_error = errors.join().ok().flatten();
+            let failure = match child.wait() {
+                // Not reading the rest is what made it stop
+                Ok(_) if lines.capped.load(Ordering::Relaxed) => None,
+                Ok(status) if !status.success() => Some(match last_error {
+                    Some(error) => format!("{command} failed: {error}"),
+                    None => format!("{command} failed with {status}"),
+                }),
+                Ok(_) => None,
+                Err(e) => Some(format!("couldn't wait for {command}: {e}")),
+            };
+            if let Some(failure) = &failure {
+                tracing::warn!("{failure}");
+            }
+            *lines.failure.lock().unwrap_or_else(|e| e.into_inner()) = failure;
+        };
+        Ok(Self::streaming_then(
+            BufReader::new(stdout),
+            options,
+            finish,
+        ))
+    }
+}
+
+/// The last line that isn't blank of what a command printed as errors
+fn last_error(mut reader: impl BufRead) -> Option<String> {
+    let options = LineOptions {
+        max_line_len: Some(200),
+        ..LineOptions::default()
+    };
+    let mut line = Vec::new();
+    let mut last = None;
+    while let Ok(Some(_)) = read_line(&mut reader, &mut line, &options) {
+        let text = String::from_utf8_lossy(&line);
+        if !text.trim().is_empty() {
+            last = Some(text.trim().to_string());
+        }
+    }
+    last
+}
+
+/// Reads the next line of `reader` into `line`, without its `\n`. Past
+/// [`LineOptions::max_line_len`], bytes are skipped rather than kept, so a huge
+/// line (e.g. from a binary file) never has to fit in memory. Returns whether
+/// the line was cut short, or `None` at the end of the input.
+fn read_line(
+    reader: &mut impl BufRead,
+    line: &mut Vec<u8>,
+    options: &LineOptions,
+) -> io::Result<Option<bool>> {
+    // A character takes at most 4 bytes
+    let max = options
+        .max_line_len
+        .map_or(usize::MAX, |max| max.saturating_mul(4));
+    line.clear();
+    let mut cut = false;
+    let mut read_any = false;
+    loop {
+        let buf = match reader.fill_buf() {
+            Ok(buf) => buf,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        };
+        if buf.is_empty() {
+            return Ok(read_any.then_some(cut));
+        }
+        read_any = true;
+
+        let (chunk, used, ended) = match buf.iter().position(|b| *b == b'\n') {
+            Some(end) => (&buf[..end], end + 1, true),
+            None => (buf, buf.len(), false),
+        };
+        let room = max.saturating_sub(line.len());
+        cut |= chunk.len() > room;
+        line.extend_from_slice(&chunk[..chunk.len().min(room)]);
+        reader.consume(used);
+        if ended {
+            return Ok(Some(cut));
+        }
+    }
+}
+
+/// The item for a line of input, if it's not empty
+fn line_item(line: String, ansi: bool) -> Option<Item> {
+    if line.is_empty() {
+        return None;
+    }
+
+    Some(if ansi {
+        let parsed = ansi::parse(&line);
+        Item::new(parsed.text, ItemKind::Line).with_name_styles(parsed.styles)
+    } else {
+        Item::new(line, ItemKind::Line)
+    })
+}
+
+impl Backend for StdinBackend {
+    fn children(&self, path: &[String]) -> color_eyre::Result<Vec<Item>> {
+        if !path.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        Ok(self.lines.items().clone())
+    }
+
+    fn fetch(&self, path: &[String], offset: usize, limit: usize) -> color_eyre::Result<Vec<Item>> {
+        if !path.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let items = self.lines.items();
+        let start = offset.min(items.len());
+        let end = offset.saturating_add(limit).min(items.len());
+        Ok(items[start..end].to_vec())
+    }
+
+    fn streaming(&self, path: &[String]) -> Option<usize> {
+        if !path.is_empty() || self.lines.done.load(Ordering::Acquire) {
+            return None;
+        }
+        Some(self.lines.items().len())
+    }
+
+    fn notice(&self, path: &[String]) -> Option<String> {
+        if !path.is_empty() {
+            return None;
+        }
+        let mut notices = Vec::new();
+        if let Some(failure) = &*self.lines.failure.lock().unwrap_or_else(|e| e.into_inner()) {
+            notices.push(failure.clone());
+        }
+        if self.lines.capped.load(Ordering::Relaxed) {
+            let count = self.lines.items().len();
+            notices.push(format!("stopped reading at {count} items"));
+        }
+        match self.lines.cut.load(Ordering::Relaxed) {
+            0 => {}
+            1 => notices.push("1 long line cut".to_string()),
+            cut => notices.push(format!("{cut} long lines cut")),
+        }
+        match self.lines.duplicates.load(Ordering::Relaxed) {
+            0 => {}
+            1 => notices.push("1 duplicate dropped".to_string()),
+            duplicates => notices.push(format!("{duplicates} duplicates dropped")),
+        }
+        (!notices.is_empty()).then(|| notices.join(", "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_lines_are_leaves() -> color_eyre::Result<()> {
+        let backend = StdinBackend::from_reader(
+            "sh010\tanna\n\nsh020\tbob\n".as_bytes(),
+            LineOptions::default(),
+        )?;
+        let items = backend.children(&[])?;
+        assert_eq!(items.len(), 2);
+        assert!(items.iter().all(|i| i.kind.is_leaf()));
+        assert!(backend.children(&["sh010".into()])?.is_empty());
+
+        let page = backend.fetch(&[], 1, 10)?;
+        assert_eq!(page.len(), 1);
+        assert!(backend.fetch(&[], 5, 10)?.is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_ansi_colors_are_parsed() -> color_eyre::Result<()> {
+        let input = "\x1b[35msrc/app.rs\x1b[0m:12\n";
+        let ansi = LineOptions {
+            ansi: true,
+            ..LineOptions::default()
+        };
+
+        let items = StdinBackend::from_reader(input.as_bytes(), ansi)?.children(&[])?;
+        assert_eq!(items[0].name, "src/app.rs:12");
+        assert_eq!(items[0].name_styles.len(), 1);
+
+        let items =
+            StdinBackend::from_reader(input.as_bytes(), LineOptions::default())?.children(&[])?;
+        assert!(items[0].name.starts_with('\x1b'));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_streaming() {
+        let input = std::io::Cursor::new(b"sh010\n\nsh020\n".to_vec());
+        let backend = StdinBackend::streaming(input, LineOptions::default());
+        while backend.streaming(&[]).is_some() {
+            thread::sleep(std::time::Duration::from_millis(5));
+        }
+        assert_eq!(backend.fetch(&[], 1, 10).unwrap()[0].name, "sh020");
+        assert!(StdinBackend::default().streaming(&[]).is_none());
+    }
+
+    #[test]
+    fn test_command_output_is_streamed() -> color_eyre::Result<()> {
+        let backend =
+            StdinBackend::from_command("printf 'sh010\\nsh020\\n'", LineOptions::default())?;
+        while backend.streaming(&[]).is_some() {
+            thread::sleep(std::time::Duration::from_millis(5));
+        }
+        let names: Vec<String> = backend.children(&[])?.into_iter().map(|i| i.name).collect();
+        assert_eq!(names, ["sh010", "sh020"]);
+        assert_eq!(backend.notice(&[]), None);
+
+        let backend = StdinBackend::from_command(
+            "echo sh010; echo 'no such show' >&2; exit 2",
+            LineOptions::default(),
+        )?;
+        while backend.streaming(&[]).is_some() {
+            thread::sleep(std::time::Duration::from_millis(5));
+        }
+        assert_eq!(backend.children(&[])?.len(), 1);
+        assert_eq!(
+            backend.notice(&[]).as_deref(),
+            Some("echo sh010; echo 'no such show' >&2; exit 2 failed: no such show")
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_limits() -> color_eyre::Result<()> {
+        let options = LineOptions {
+            max_items: Some(2),
+            max_line_len: Some(4),
+            ..LineOptions::default()
+        };
+        let input = b"sh010\nsh\xff\r\nsh030\nsh040\n";
+        let backend = StdinBackend::from_reader(&input[..], options)?;
+        let names: Vec<String> = backend.children(&[])?.into_iter().map(|i| i.name).collect();
+        assert_eq!(names, ["sh01", "sh\u{fffd}"]);
+        assert_eq!(
+            backend.notice(&[]).as_deref(),
+            Some("stopped reading at 2 items, 1 long line cut")
+        );
+
+        // Only the start of a huge line is kept while reading it
+        let input = format!("{}\nsh020", "😀".repeat(100_000));
+        let mut reader = std::io::BufReader::with_capacity(16, input.as_bytes());
+        let mut line = Vec::new();
+        assert_eq!(read_line(&mut reader, &mut line, &options)?, Some(true));
+        assert_eq!(line, "😀😀😀😀".as_bytes());
+        assert_eq!(read_line(&mut reader, &mut line, &options)?, Some(false));
+        assert_eq!(line, b"sh020");
+        assert_eq!(read_line(&mut reader, &mut line, &options)?, None);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_unique() -> color_eyre::Result<()> {
+        let options = LineOptions {
+            unique: true,
+            ..LineOptions::default()
+        };
+        let input = "sh020\nsh010\nsh020\n\nsh010\nsh020\n";
+        let backend = StdinBackend::from_reader(input.as_bytes(), options)?;
+        let names: Vec<String> = backend.children(&[])?.into_iter().map(|i| i.name).collect();
+        assert_eq!(names, ["sh020", "sh010"]);
+        assert_eq!(backend.notice(&[]).as_deref(), Some("3 duplicates dropped"));
+
+        Ok(())
+    }
+}
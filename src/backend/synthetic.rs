@@ -0,0 +1,121 @@
+use std::time::{Duration, SystemTime};
+
+use super::{Backend, Item, ItemKind, Status};
+
+const WORDS: [&str; 16] = [
+    "amber", "bee", "comet", "dune", "ember", "fjord", "glacier", "harbor", "iris", "jade", "kelp",
+    "lunar", "meadow", "nova", "orchid", "pixel",
+];
+
+const ARTISTS: [&str; 5] = ["anna", "bob", "carla", "dev", "eli"];
+
+/// The latest modification time handed out, so the data doesn't depend on the clock
+const SYNTHETIC_NOW: u64 = 1_700_000_000;
+
+/// Generates a hierarchy with `size` items at every level, the same for a given seed.
+/// Items are made up when asked for, so huge sizes don't cost anything up front.
+#[derive(Debug)]
+pub struct SyntheticBackend {
+    size: usize,
+    seed: u64,
+}
+
+impl SyntheticBackend {
+    pub fn new(size: usize, seed: u64) -> Self {
+        Self { size, seed }
+    }
+
+    /// The `index`-th child of the level at `path`
+    fn item(&self, path: &[String], index: usize) -> Item {
+        let hash = splitmix64(self.seed ^ path_hash(path) ^ index as u64);
+        let word = |shift: u32| WORDS[(hash >> shift) as usize % WORDS.len()];
+
+        let kind = ItemKind::for_depth(path.len());
+        let name = match kind {
+            ItemKind::Project => format!("{}_{}_{index:06}", word(0), word(8)),
+            ItemKind::Sequence => format!("seq_{}_{index:04}", word(16)),
+            _ => format!("sh{:05}", index * 10),
+        };
+        let modified = SystemTime::UNIX_EPOCH
+            + Duration::from_secs(SYNTHETIC_NOW - (hash >> 40) % (3600 * 24 * 365));
+
+        let mut item = Item::new(name, kind).with_modified(modified);
+        if !kind.is_leaf() {
+            item = item.with_child_count(self.size);
+        }
+        if kind.is_leaf() {
+            item = item
+                .with_status(Status::ALL[(hash >> 24) as usize % Status::ALL.len()])
+                .with_metadata("artist", ARTISTS[(hash >> 32) as usize % ARTISTS.len()]);
+        }
+        item
+    }
+}
+
+impl Backend for SyntheticBackend {
+    fn children(&self, path: &[String]) -> color_eyre::Result<Vec<Item>> {
+        self.fetch(path, 0, self.size)
+    }
+
+    fn fetch(&self, path: &[String], offset: usize, limit: usize) -> color_eyre::Result<Vec<Item>> {
+        if path.len() > 2 {
+            return Ok(Vec::new());
+        }
+
+        let end = offset.saturating_add(limit).min(self.size);
+        Ok((offset.min(end)..end)
+            .map(|index| self.item(path, index))
+            .collect())
+    }
+}
+
+/// A well spread 64 bits hash of `x`, see <https://prng.di.unimi.it/splitmix64.c>
+fn splitmix64(x: u64) -> u64 {
+    let mut z = x.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+/// FNV-1a over the names of the path, so every level gets its own items
+fn path_hash(path: &[String]) -> u64 {
+    path.iter()
+        .flat_map(|name| name.bytes().chain([b'/']))
+        .fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+            (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3)
+        })
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_same_seed_same_items() -> color_eyre::Result<()> {
+        let backend = SyntheticBackend::new(100_000, 7);
+        let projects = backend.fetch(&[], 500, 10)?;
+        assert_eq!(projects.len(), 10);
+        assert!(projects[0].name.ends_with("_000500"));
+
+        let again = SyntheticBackend::new(100_000, 7).fetch(&[], 500, 10)?;
+        let names = |items: &[Item]| items.iter().map(|i| i.name.clone()).collect::<Vec<_>>();
+        assert_eq!(names(&projects), names(&again));
+
+        let other_seed = SyntheticBackend::new(100_000, 8).fetch(&[], 500, 10)?;
+        assert_ne!(names(&projects), names(&other_seed));
+
+        assert_eq!(backend.fetch(&[], 99_995, 10)?.len(), 5);
+
+        let path = vec![projects[0].name.clone(), "seq_x".to_string()];
+        let shots = backend.fetch(&path, 0, 3)?;
+        assert!(shots
+            .iter()
+            .all(|i| i.kind == ItemKind::Shot && i.status.is_some()));
+        assert!(backend
+            .fetch(&[path, vec!["sh00010".to_string()]].concat(), 0, 3)?
+            .is_empty());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,104 @@
+use std::{
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc, Mutex, MutexGuard,
+    },
+    thread,
+};
+
+use super::{
+    fs::{join, list_dir, ListOptions},
+    Item, ItemKind,
+};
+
+/// The directories some levels under a root, found by reading directories in
+/// parallel in the background and shared with the app while it's still going.
+/// See [`FsBackend::with_walk_depth`](super::FsBackend::with_walk_depth).
+#[derive(Debug, Default)]
+pub struct Walk {
+    /// What was found so far, named by their path under the root like
+    /// `project/sequence/shot`. The entries of a directory come sorted, together.
+    items: Mutex<Vec<Item>>,
+    done: AtomicBool,
+    /// Set once nobody waits for the rest anymore
+    cancelled: AtomicBool,
+    /// How many directories couldn't be read, e.g. for lack of permissions
+    unreadable: AtomicUsize,
+    /// What's listed of each directory
+    options: Arc<ListOptions>,
+}
+
+impl Walk {
+    /// Starts listing the directories `depth` levels under `root`, so 1 lists
+    /// what's right in it. Every directory is read in a task of its own.
+    pub fn start(root: PathBuf, depth: usize, options: Arc<ListOptions>) -> Arc<Self> {
+        let walk = Arc::new(Self {
+            options,
+            ..Self::default()
+        });
+        let shared = Arc::clone(&walk);
+        thread::spawn(move || {
+            let shared = &*shared;
+            rayon::scope(|scope| shared.walk_dir(scope, root, String::new(), 0, depth.max(1)));
+            shared.done.store(true, Ordering::Release);
+        });
+        walk
+    }
+
+    pub fn items(&self) -> MutexGuard<'_, Vec<Item>> {
+        // A panic while adding entries leaves the others usable
+        self.items.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.done.load(Ordering::Acquire)
+    }
+
+    /// Stops reading directories not read yet
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    pub fn unreadable(&self) -> usize {
+        self.unreadable.load(Ordering::Relaxed)
+    }
+
+    /// Lists `dir`, `level` levels under the root and named `prefix` under it,
+    /// then its subdirectories in other tasks until `depth` is reached
+    fn walk_dir<'s>(
+        &'s self,
+        scope: &rayon::Scope<'s>,
+        dir: PathBuf,
+        prefix: String,
+        level: usize,
+        depth: usize,
+    ) {
+        if self.cancelled.load(Ordering::Relaxed) {
+            return;
+        }
+        let kind = ItemKind::for_depth(level);
+        let children = match list_dir(&dir, &prefix, kind, &self.options, &mut |_| {}) {
+            Ok(children) => children,
+            Err(e) => {
+                tracing::warn!("Could not read {dir:?}: {e}");
+                self.unreadable.fetch_add(1, Ordering::Relaxed);
+                return;
+            }
+        };
+
+        if level + 1 >= depth {
+            let found = children.into_iter().map(|item| Item {
+                name: join(&prefix, &item.name),
+                ..item
+            });
+            self.items().extend(found);
+            return;
+        }
+        for child in children {
+            let dir = dir.join(&child.name);
+            let prefix = join(&prefix, &child.name);
+            scope.spawn(move |scope| self.walk_dir(scope, dir, prefix, level + 1, depth));
+        }
+    }
+}
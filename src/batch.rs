@@ -0,0 +1,84 @@
+use std::io::{BufRead, Write};
+
+use color_eyre::eyre::WrapErr;
+
+use crate::{backend::Backend, finder::Finder, matcher::FuzzyMatcher};
+
+/// Matches every line of `queries` against the items at `path`, without a terminal.
+/// Prints the best `top` matches of each query as `query<TAB>project/sequence/shot`,
+/// or just `query<TAB>` when nothing matches or the query is blank, so the rows
+/// line up with the queries.
+pub fn run(
+    backend: &dyn Backend,
+    normalize: bool,
+    path: Vec<String>,
+    queries: impl BufRead,
+    top: usize,
+    mut writer: impl Write,
+) -> color_eyre::Result<()> {
+    let mut finder = Finder::new(backend, FuzzyMatcher::new(normalize))?;
+    finder.set_path(path)?;
+
+    for query in queries.lines() {
+        let query = query.wrap_err("failed to read a query")?;
+        let query = query.trim();
+        if query.is_empty() {
+            writeln!(writer, "\t")?;
+            continue;
+        }
+
+        finder.set_query(query);
+        let mut matched = false;
+        for item in finder.results().take(top) {
+            let path: Vec<&str> = finder
+                .path()
+                .iter()
+                .chain([&item.name])
+                .map(String::as_str)
+                .collect();
+            writeln!(writer, "{query}\t{}", path.join("/"))?;
+            matched = true;
+        }
+        if !matched {
+            writeln!(writer, "{query}\t")?;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::backend::MockBackend;
+
+    #[test]
+    fn test_batch() -> color_eyre::Result<()> {
+        let path = vec!["man_vs_bee".to_string(), "sequence_001".to_string()];
+        let queries = "sh020\n\nzzz\nsh0\n";
+        let mut output = Vec::new();
+        run(
+            &MockBackend,
+            false,
+            path,
+            queries.as_bytes(),
+            2,
+            &mut output,
+        )?;
+
+        let lines: Vec<&str> = std::str::from_utf8(&output)?.lines().collect();
+        assert_eq!(lines[0], "sh020\tman_vs_bee/sequence_001/sh020");
+        assert_eq!(lines[1], "\t");
+        assert_eq!(lines[2], "zzz\t");
+        assert_eq!(
+            lines
+                .iter()
+                .filter(|line| line.starts_with("sh0\t"))
+                .count(),
+            2
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,591 @@
+use std::{
+    ffi::OsString,
+    io::{IsTerminal, Write},
+    path::PathBuf,
+};
+
+use clap::{CommandFactory, Parser, Subcommand, ValueEnum};
+
+use crate::{
+    auth::TokenSource,
+    backend::LineOptions,
+    shell::Shell,
+    sort::{SortMode, StartAt},
+    theme::{ColorChoice, ColorDepth, ThemeName},
+};
+
+/// Flags read before the ones on the command line, like `FZF_DEFAULT_OPTS`
+pub const DEFAULT_OPTS_VAR: &str = "RATATUI_FZF_DEFAULT_OPTS";
+
+/// A command whose output lines are browsed when nothing else is given, like
+/// `FZF_DEFAULT_COMMAND`
+pub const DEFAULT_COMMAND_VAR: &str = "RATATUI_FZF_DEFAULT_COMMAND";
+
+/// Fuzzy-find your way through projects, sequences and shots.
+///
+/// Defaults for most flags can be set in ~/.config/ratatui-fzf/config.toml,
+/// RATATUI_FZF_<SECTION>_<KEY> environment variables or RATATUI_FZF_DEFAULT_OPTS;
+/// flags win over all of them. Switches turned on there can be turned off again
+/// with --no-<switch>, like --no-watch (and --unicode undoes --no-unicode).
+#[derive(Debug, Clone, Default, Parser)]
+#[command(version, about, args_override_self = true)]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
+    /// Use the settings of this profile of the config file, from its `[profile.NAME]` tables
+    #[arg(long, value_name = "NAME")]
+    pub profile: Option<String>,
+
+    /// Browse this directory (laid out as project/sequence/shot) instead of the mock data.
+    /// When neither this nor --demo is given and stdin is not a terminal, lines are read from stdin
+    /// (except with --rpc, which reads requests from it).
+    #[arg(long)]
+    pub root: Option<PathBuf>,
+
+    /// Browse the lines printed by this shell command, shown as they come,
+    /// e.g. `--command 'find /shows -maxdepth 2 -type d'`
+    #[arg(long = "command", value_name = "COMMAND", conflicts_with_all = ["root", "demo"])]
+    pub items_command: Option<String>,
+
+    /// Stop reading lines from stdin or --command after this many
+    #[arg(long, value_name = "N")]
+    pub max_items: Option<usize>,
+
+    /// Cut lines from stdin or --command longer than this many characters [default: 4096]
+    #[arg(long, value_name = "N")]
+    pub max_line_len: Option<usize>,
+
+    /// Drop lines from stdin or --command seen before, keeping the first one
+    #[arg(long, overrides_with = "no_unique")]
+    pub unique: bool,
+
+    #[arg(long, overrides_with = "unique", hide = true)]
+    pub no_unique: bool,
+
+    /// Browse a made-up hierarchy with this many items at every level, for demos and profiling
+    #[arg(long, value_name = "SIZE", conflicts_with = "root")]
+    pub demo: Option<usize>,
+
+    /// Seed of the --demo data: the same seed always gives the same items [default: 0]
+    #[arg(long)]
+    pub seed: Option<u64>,
+
+    /// List every directory this many levels under --root at the top level, e.g.
+    /// every shot with 3, found by reading directories in parallel and shown as they come
+    #[arg(long, value_name = "DEPTH")]
+    pub walk_depth: Option<usize>,
+
+    /// Never list directories under --root matching this glob, like `renders` or
+    /// `*/tmp*`, on top of the ones in its .cgsignore file. Can be given several times.
+    #[arg(long, value_name = "GLOB")]
+    pub exclude: Vec<String>,
+
+    /// List symbolic links to directories under --root too, and walk through them
+    #[arg(long, overrides_with = "no_follow_symlinks")]
+    pub follow_symlinks: bool,
+
+    #[arg(long, overrides_with = "follow_symlinks", hide = true)]
+    pub no_follow_symlinks: bool,
+
+    /// With --follow-symlinks, also list links back to a directory they're in,
+    /// which are left out not to go round in circles
+    #[arg(long, overrides_with = "no_keep_symlink_cycles")]
+    pub keep_symlink_cycles: bool,
+
+    #[arg(long, overrides_with = "keep_symlink_cycles", hide = true)]
+    pub no_keep_symlink_cycles: bool,
+
+    /// Remember the items listed under --root, to list them anyway when it can't be
+    /// reached (e.g. a network share being down)
+    #[arg(long, requires = "root")]
+    pub offline_cache: bool,
+
+    /// Fetch more items from the backend at most this many times per second while scrolling,
+    /// not to hammer a busy server. Whether they're still needed is checked once allowed.
+    #[arg(long, value_name = "REQUESTS_PER_SECOND", value_parser = clap::value_parser!(u32).range(1..))]
+    pub rate_limit: Option<u32>,
+
+    /// Send the token in this environment variable to backends talking to a server
+    #[arg(long, value_name = "VAR", conflicts_with_all = ["token_file", "token_command"])]
+    pub token_env: Option<String>,
+
+    /// Send the token in this file to backends talking to a server, e.g. written by a login
+    /// tool. It's read again when the server refuses it.
+    #[arg(long, value_name = "FILE", conflicts_with = "token_command")]
+    pub token_file: Option<PathBuf>,
+
+    /// Send the token this shell command prints to backends talking to a server,
+    /// e.g. `vault read -field=token secret/tracker`. It's run again when the server refuses it.
+    #[arg(long, value_name = "COMMAND")]
+    pub token_command: Option<String>,
+
+    /// Refresh the results when directories are created or removed under --root
+    #[arg(long, overrides_with = "no_watch")]
+    pub watch: bool,
+
+    #[arg(long, overrides_with = "watch", hide = true)]
+    pub no_watch: bool,
+
+    /// Don't show the app: answer JSON requests (set-query, get-results, accept)
+    /// from other tools on a Unix socket at this path instead
+    #[arg(long, value_name = "SOCKET")]
+    pub serve: Option<PathBuf>,
+
+    /// Don't show the app: answer newline-delimited JSON-RPC 2.0 requests
+    /// (listChildren, match, select) read from stdin, on stdout
+    #[arg(long, conflicts_with = "serve")]
+    pub rpc: bool,
+
+    /// Draw the app below the prompt instead of taking over the whole screen
+    #[arg(long)]
+    pub no_alt_screen: bool,
+
+    /// Scroll the results with the mouse wheel. The terminal can't select text
+    /// meanwhile, except after Alt+S until the next key press.
+    #[arg(long)]
+    pub mouse: bool,
+
+    /// Split the results into Shots, Assets and Renders tabs (switched with Ctrl+Left/Right)
+    #[arg(long, overrides_with = "no_tabs")]
+    pub tabs: bool,
+
+    #[arg(long, overrides_with = "tabs", hide = true)]
+    pub no_tabs: bool,
+
+    /// Show results of different kinds in groups, under a header that sticks while scrolling.
+    /// Left and Right collapse and expand the group of the highlighted result
+    #[arg(long, overrides_with = "no_group")]
+    pub group: bool,
+
+    #[arg(long, overrides_with = "group", hide = true)]
+    pub no_group: bool,
+
+    /// Keep this many results visible above and below the highlighted one while scrolling
+    #[arg(long, value_name = "N")]
+    pub scroll_off: Option<usize>,
+
+    /// How many moves Up or Down go one row at a time while held, before speeding up [default: 8]
+    #[arg(long, value_name = "N")]
+    pub accelerate_after: Option<u32>,
+
+    /// How many rows each move goes once sped up, 1 to never speed up [default: 5]
+    #[arg(long, value_name = "N")]
+    pub accelerate_step: Option<usize>,
+
+    /// When to draw colors: `auto` looks at the terminal and `NO_COLOR` [default: auto]
+    #[arg(long, value_enum, value_name = "WHEN")]
+    pub color: Option<ColorChoice>,
+
+    /// The built-in theme to start from, before the styles of the config [default: default]
+    #[arg(long, value_enum, value_name = "NAME")]
+    pub theme: Option<ThemeName>,
+
+    /// Draw only ASCII: badges instead of nerd-font icons, `+-|` borders, etc.
+    #[arg(long, overrides_with = "unicode")]
+    pub no_unicode: bool,
+
+    #[arg(long, overrides_with = "no_unicode", hide = true)]
+    pub unicode: bool,
+
+    /// Marks where long names are cut (defaults to `…`, or `...` with --no-unicode)
+    #[arg(long)]
+    pub ellipsis: Option<String>,
+
+    /// Points at the highlighted result [default: >]
+    #[arg(long)]
+    pub pointer: Option<String>,
+
+    /// Marks the marked results, with --multi (defaults to `▌`, or `|` with --no-unicode)
+    #[arg(long)]
+    pub marker: Option<String>,
+
+    /// Ignore accents when matching, so `eclair` finds `éclair`
+    #[arg(long, overrides_with = "no_normalize")]
+    pub normalize: bool,
+
+    #[arg(long, overrides_with = "normalize", hide = true)]
+    pub no_normalize: bool,
+
+    /// Also list names one typo away from the query (like `porject` for `project`),
+    /// after the other matches. Matching takes longer.
+    #[arg(long, overrides_with = "no_typos")]
+    pub typos: bool,
+
+    #[arg(long, overrides_with = "typos", hide = true)]
+    pub no_typos: bool,
+
+    /// Filter with a regex instead of fuzzy matching (can be toggled at runtime with Ctrl+/)
+    #[arg(long, overrides_with = "no_regex")]
+    pub regex: bool,
+
+    #[arg(long, overrides_with = "regex", hide = true)]
+    pub no_regex: bool,
+
+    /// Interpret ANSI color codes in lines read from stdin, instead of showing them as text
+    #[arg(long)]
+    pub ansi: bool,
+
+    /// Show the output of this command for the highlighted item, e.g. `cat {path}/README`.
+    /// `{path}` is replaced with where the item lives and `{name}` with its name.
+    #[arg(long)]
+    pub preview: Option<String>,
+
+    /// Show the thumbnail of the highlighted shot, when the terminal can draw images
+    #[arg(long, overrides_with = "no_thumbnails")]
+    pub thumbnails: bool,
+
+    #[arg(long, overrides_with = "thumbnails", hide = true)]
+    pub no_thumbnails: bool,
+
+    /// Show where the highlighted item links to in the preview pane, when it's a link
+    #[arg(long, overrides_with = "no_show_link_targets")]
+    pub show_link_targets: bool,
+
+    #[arg(long, overrides_with = "show_link_targets", hide = true)]
+    pub no_show_link_targets: bool,
+
+    /// Show how long matching and fetching items took in the status bar
+    #[arg(long, overrides_with = "no_show_timings")]
+    pub show_timings: bool,
+
+    #[arg(long, overrides_with = "show_timings", hide = true)]
+    pub no_show_timings: bool,
+
+    /// Split items into fields with this string, instead of whitespace (used with --nth)
+    #[arg(long)]
+    pub delimiter: Option<String>,
+
+    /// Only match against these fields of each item, e.g. `1,3`, `2..` or `-1`.
+    /// The whole item is still shown and printed.
+    #[arg(long)]
+    pub nth: Option<String>,
+
+    /// Only keep this many of the best fuzzy matches, which is faster with huge inputs
+    #[arg(long, value_name = "N")]
+    pub max_results: Option<usize>,
+
+    /// How to order the results (can be cycled at runtime with Ctrl+S) [default: score]
+    #[arg(long, value_enum)]
+    pub sort: Option<SortMode>,
+
+    /// Where the highlight goes when the results are ranked again [default: first]
+    #[arg(long, value_enum)]
+    pub start_at: Option<StartAt>,
+
+    /// Also list archived items (can be toggled at runtime with Ctrl+X)
+    #[arg(long, overrides_with = "no_show_archived")]
+    pub show_archived: bool,
+
+    #[arg(long, overrides_with = "show_archived", hide = true)]
+    pub no_show_archived: bool,
+
+    /// Let several leaf items be marked (with Tab, or Ctrl+A/D/T for all the matches)
+    /// and print all of them when accepting
+    #[arg(short, long)]
+    pub multi: bool,
+
+    /// Keep the app open after accepting a leaf item, with the query cleared,
+    /// to pick several in a row. They're all printed when quitting.
+    #[arg(long = "loop")]
+    pub keep_open: bool,
+
+    /// Run this command for every item accepted with --loop, as soon as it is,
+    /// instead of printing them. `{path}` and `{name}` are replaced like in --preview.
+    #[arg(long, value_name = "COMMAND", requires = "keep_open")]
+    pub exec: Option<String>,
+
+    /// When only one item matches, select it without showing the app
+    /// (descending into it if it's not a leaf, and so on)
+    #[arg(long = "select-1", conflicts_with = "keep_open")]
+    pub select_1: bool,
+
+    /// When nothing matches, exit with an error code without showing the app
+    #[arg(long = "exit-0")]
+    pub exit_0: bool,
+
+    /// Write what the app does and how long it takes (handling events, matching, fetching
+    /// items...) to this file as JSON lines, to find out why searches are slow
+    #[arg(long, value_name = "FILE")]
+    pub trace_file: Option<PathBuf>,
+
+    /// Write every input and what the app looked like after it to this file,
+    /// as JSON lines, to reproduce bugs with --replay
+    #[arg(long, value_name = "FILE")]
+    pub record: Option<PathBuf>,
+
+    /// Play back the inputs recorded with --record, with the same pauses between them
+    #[arg(long, value_name = "FILE")]
+    pub replay: Option<PathBuf>,
+
+    /// How many times faster than recorded --replay goes, 0 for no pauses
+    #[arg(
+        long,
+        value_name = "SPEED",
+        default_value_t = 1.0,
+        requires = "replay",
+        value_parser = parse_speed
+    )]
+    pub replay_speed: f64,
+
+    /// How the selection is written: `text` is one path per line, `json` one object per line
+    /// with the path, match score and metadata of each item [default: text]
+    #[arg(long, value_enum, value_name = "FORMAT")]
+    pub output: Option<OutputFormat>,
+
+    /// Write the selection to this file instead of printing it, since the app is drawn on stdout
+    #[arg(long, value_name = "FILE")]
+    pub output_file: Option<PathBuf>,
+
+    /// Write the selection to this file descriptor inherited from the caller, like
+    /// `cgs-go --output-fd 3 3>&1 >/dev/tty`, instead of printing it
+    #[arg(long, value_name = "FD", conflicts_with = "output_file")]
+    pub output_fd: Option<u32>,
+
+    /// Where Alt+E writes the results matching the query, as CSV [default: results.csv]
+    #[arg(long, value_name = "FILE")]
+    pub export_csv: Option<PathBuf>,
+
+    /// Print where the selected item lives on disk instead of its path in the hierarchy
+    /// (needs --root)
+    #[arg(long)]
+    pub print_location: bool,
+
+    /// Start in the project/sequence the last selection was made from
+    #[arg(long, conflicts_with = "project")]
+    pub resume: bool,
+
+    /// Match every line of this file against the items of --project/--sequence without
+    /// showing the app, and print the best match of each as `query<TAB>path`
+    #[arg(long, value_name = "FILE")]
+    pub batch: Option<PathBuf>,
+
+    /// How many matches --batch prints per query
+    #[arg(long, value_name = "N", default_value_t = 1, requires = "batch")]
+    pub batch_top: usize,
+
+    /// Start with this query typed in
+    #[arg(long, short)]
+    pub query: Option<String>,
+
+    /// Start inside this project
+    #[arg(long)]
+    pub project: Option<String>,
+
+    /// Start inside this sequence of --project
+    #[arg(long, requires = "project")]
+    pub sequence: Option<String>,
+
+    /// Whether stdin is piped rather than a terminal, checked when parsing the flags
+    #[arg(skip)]
+    pub stdin_piped: bool,
+}
+
+/// How the selection is handed over, see `--output`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    #[default]
+    Text,
+    Json,
+}
+
+/// Things to do instead of running the finder
+#[derive(Debug, Clone, Subcommand)]
+pub enum Command {
+    /// Print the completion script of a shell, e.g. `cgs-go completions zsh > _cgs-go`
+    Completions { shell: clap_complete::Shell },
+    /// Print shell functions binding the finder to Alt+G and adding a `cgs-cd` command,
+    /// e.g. `eval "$(cgs-go init bash)"` in ~/.bashrc
+    Init { shell: Shell },
+    /// Check the config, the backend and what the terminal supports, and say
+    /// what to fix, without starting the finder. Exits with 1 when something's wrong.
+    Doctor,
+}
+
+impl Cli {
+    /// Parses the flags of `RATATUI_FZF_DEFAULT_OPTS` followed by the command line's,
+    /// so the command line wins when a flag is given in both
+    pub fn parse_with_default_opts() -> color_eyre::Result<Self> {
+        // Arguments that aren't UTF-8, like some paths, are passed on as they are
+        let mut args: Vec<OsString> = std::env::args_os().collect();
+        if let Ok(opts) = std::env::var(DEFAULT_OPTS_VAR) {
+            let defaults = split_words(&opts)
+                .map_err(|e| color_eyre::eyre::eyre!("invalid {DEFAULT_OPTS_VAR}: {e}"))?;
+            let program = args.len().min(1);
+            args.splice(program..program, defaults.into_iter().map(OsString::from));
+        }
+        let mut cli = Cli::parse_from(args);
+        cli.stdin_piped = !std::io::stdin().is_terminal();
+        Ok(cli)
+    }
+
+    /// Whether the command line says where the items come from, lines piped to
+    /// stdin included (but not with --rpc, which reads requests from it)
+    pub fn picks_items(&self) -> bool {
+        self.root.is_some()
+            || self.demo.is_some()
+            || self.items_command.is_some()
+            || (self.stdin_piped && !self.rpc)
+    }
+
+    /// How many colors to draw with, given --color and the terminal
+    pub fn color_depth(&self) -> ColorDepth {
+        self.color
+            .unwrap_or_default()
+            .depth(|name| std::env::var(name).ok())
+    }
+
+    /// Where the token sent to backends talking to a server comes from, if anywhere
+    pub fn token_source(&self) -> Option<TokenSource> {
+        let env = self.token_env.clone().map(TokenSource::Env);
+        let file = self.token_file.clone().map(TokenSource::File);
+        let command = self.token_command.clone().map(TokenSource::Command);
+        env.or(file).or(command)
+    }
+
+    /// How lines from stdin or --command are read
+    pub fn line_options(&self) -> LineOptions {
+        LineOptions {
+            ansi: self.ansi,
+            max_items: self.max_items,
+            max_line_len: Some(self.max_line_len.unwrap_or(4096)),
+            unique: self.unique,
+        }
+    }
+
+    /// What marks cut names, defaulting to one fitting --no-unicode
+    pub fn ellipsis(&self) -> String {
+        self.ellipsis
+            .clone()
+            .unwrap_or_else(|| if self.no_unicode { "..." } else { "…" }.to_string())
+    }
+
+    pub fn pointer(&self) -> String {
+        self.pointer.clone().unwrap_or_else(|| ">".to_string())
+    }
+
+    /// What marks marked results, defaulting to one fitting --no-unicode
+    pub fn marker(&self) -> String {
+        self.marker
+            .clone()
+            .unwrap_or_else(|| if self.no_unicode { "|" } else { "▌" }.to_string())
+    }
+}
+
+/// A speed factor, which can't go backwards
+fn parse_speed(text: &str) -> Result<f64, String> {
+    match text.parse::<f64>() {
+        Ok(speed) if speed.is_finite() && speed >= 0.0 => Ok(speed),
+        Ok(_) => Err("expected a number of 0 or more".to_string()),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+/// Splits `text` into words at whitespace like a shell does, keeping quoted
+/// strings together: `--query 'sh 010'` is `--query` and `sh 010`
+fn split_words(text: &str) -> Result<Vec<String>, String> {
+    let mut words = Vec::new();
+    let mut word: Option<String> = None;
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\'' | '"' => {
+                let word = word.get_or_insert_with(String::new);
+                loop {
+                    match chars.next() {
+                        Some(end) if end == c => break,
+                        Some('\\') if c == '"' => word.extend(chars.next()),
+                        Some(other) => word.push(other),
+                        None => return Err(format!("unclosed {c} quote")),
+                    }
+                }
+            }
+            '\\' => word.get_or_insert_with(String::new).extend(chars.next()),
+            c if c.is_whitespace() => words.extend(word.take()),
+            c => word.get_or_insert_with(String::new).push(c),
+        }
+    }
+    words.extend(word);
+
+    Ok(words)
+}
+
+/// Writes the script completing the flags of the binary in `shell`
+pub fn write_completions(shell: clap_complete::Shell, out: &mut impl Write) {
+    let mut command = Cli::command();
+    let name = command.get_name().to_string();
+    clap_complete::generate(shell, &mut command, name, out);
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_completions() {
+        let mut script = Vec::new();
+        write_completions(clap_complete::Shell::Bash, &mut script);
+        let script = String::from_utf8(script).unwrap();
+        assert!(script.contains("--max-results"));
+        assert!(script.contains("completions"));
+    }
+
+    #[test]
+    fn test_split_words() {
+        let words = split_words(r#"--theme light  --query 'sh 010' -p "it's" a\ b ''"#);
+        assert_eq!(
+            words.unwrap(),
+            ["--theme", "light", "--query", "sh 010", "-p", "it's", "a b", ""]
+        );
+        assert!(split_words("--query 'sh").is_err());
+    }
+
+    #[test]
+    fn test_later_flags_win() {
+        let cli = Cli::parse_from(["cgs-go", "--theme", "light", "--theme", "high-contrast"]);
+        assert_eq!(cli.theme, Some(ThemeName::HighContrast));
+    }
+
+    #[test]
+    fn test_one_source_of_items() {
+        assert!(Cli::try_parse_from(["cgs-go", "--command", "ls", "--root", "/shows"]).is_err());
+        assert!(Cli::try_parse_from(["cgs-go", "--command", "ls", "--demo", "10"]).is_err());
+        assert!(Cli::try_parse_from(["cgs-go", "--demo", "10", "--root", "/shows"]).is_err());
+        assert!(Cli::try_parse_from(["cgs-go", "--command", "ls"]).is_ok());
+    }
+
+    #[test]
+    fn test_token_source() {
+        let cli = Cli::parse_from(["cgs-go", "--token-command", "vault read token"]);
+        assert_eq!(
+            cli.token_source(),
+            Some(TokenSource::Command("vault read token".into()))
+        );
+        assert_eq!(Cli::parse_from(["cgs-go"]).token_source(), None);
+        assert!(Cli::try_parse_from(["cgs-go", "--token-env", "A", "--token-file", "b"]).is_err());
+    }
+
+    #[test]
+    fn test_replay_speed() {
+        let replay =
+            |speed| Cli::try_parse_from(["cgs-go", "--replay", "x", "--replay-speed", speed]);
+        assert_eq!(replay("2.5").unwrap().replay_speed, 2.5);
+        assert!(replay("-1").is_err());
+        assert!(replay("inf").is_err());
+    }
+
+    #[test]
+    fn test_rate_limit_is_positive() {
+        assert!(Cli::try_parse_from(["cgs-go", "--rate-limit", "0"]).is_err());
+        let cli = Cli::parse_from(["cgs-go", "--rate-limit", "5"]);
+        assert_eq!(cli.rate_limit, Some(5));
+    }
+
+    #[test]
+    fn test_output_json() {
+        let cli = Cli::parse_from(["cgs-go", "--output", "json"]);
+        assert_eq!(cli.output, Some(OutputFormat::Json));
+        assert!(Cli::try_parse_from(["cgs-go", "--output", "/tmp/selection"]).is_err());
+    }
+}
@@ -0,0 +1,567 @@
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use color_eyre::eyre::{eyre, WrapErr};
+use serde::Deserialize;
+
+use crate::{
+    action::Action,
+    cli::{Cli, DEFAULT_COMMAND_VAR, DEFAULT_OPTS_VAR},
+    keymap::Keymap,
+    matcher::Bonuses,
+    sort::{SortMode, StartAt},
+    theme::{ColorChoice, Theme, ThemeName},
+};
+
+/// Prefix of the environment variables overriding the config file,
+/// e.g. `RATATUI_FZF_MATCHER_NORMALIZE=true`
+const ENV_PREFIX: &str = "RATATUI_FZF_";
+
+/// The sections that can be set from environment variables. Theme styles
+/// and key bindings have names that don't fit in variable names.
+const ENV_SECTIONS: [&str; 3] = ["backend", "matcher", "layout"];
+
+/// Where the config file lives, e.g. `~/.config/ratatui-fzf/config.toml`
+pub fn config_file() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join("ratatui-fzf").join("config.toml"))
+}
+
+/// Where the settings came from, to load them again when the config file changes
+#[derive(Debug, Clone)]
+pub struct ConfigSource {
+    pub file: Option<PathBuf>,
+    /// The flags as given on the command line, before the config was applied
+    pub flags: Cli,
+}
+
+impl ConfigSource {
+    /// Loads the config again, returning it with the flags it gives
+    pub fn load(&self) -> color_eyre::Result<(Config, Cli)> {
+        let profile = self.flags.profile.as_deref();
+        let config = Config::load_from(self.file.as_deref(), profile, std::env::vars())?;
+        let mut cli = self.flags.clone();
+        config.apply_to(&mut cli);
+        Ok((config, cli))
+    }
+}
+
+/// Settings that can be given before the command line flags, from the config file
+/// and then from environment variables. Anything left unset keeps its default.
+///
+/// The file can also have named profiles, like `[profile.film_a.backend]`, with the
+/// same sections. The one picked with `--profile` is laid over the rest of the file.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub backend: BackendConfig,
+    pub matcher: MatcherConfig,
+    pub layout: LayoutConfig,
+    /// Styles by name, like `highlight = "bold magenta"`
+    pub theme: BTreeMap<String, String>,
+    /// Actions by key, like `"ctrl-o" = "toggle-preview"`
+    pub keymap: BTreeMap<String, String>,
+    /// Commands added to the item menu by label, like `"Queue render" = "queue {path}"`
+    pub actions: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct BackendConfig {
+    pub root: Option<PathBuf>,
+    pub demo: Option<usize>,
+    pub command: Option<String>,
+    pub max_items: Option<usize>,
+    pub max_line_len: Option<usize>,
+    pub unique: Option<bool>,
+    pub seed: Option<u64>,
+    pub preview: Option<String>,
+    pub watch: Option<bool>,
+    pub rate_limit: Option<u32>,
+    pub token_env: Option<String>,
+    pub token_file: Option<PathBuf>,
+    pub token_command: Option<String>,
+    pub walk_depth: Option<usize>,
+    pub exclude: Option<Vec<String>>,
+    pub follow_symlinks: Option<bool>,
+    pub keep_symlink_cycles: Option<bool>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MatcherConfig {
+    pub normalize: Option<bool>,
+    pub typos: Option<bool>,
+    pub boundary_bonus: Option<u32>,
+    pub camel_bonus: Option<u32>,
+    pub leaf_bonus: Option<u32>,
+    pub regex: Option<bool>,
+    pub sort: Option<SortMode>,
+    pub start_at: Option<StartAt>,
+    pub max_results: Option<usize>,
+    pub nth: Option<String>,
+    pub delimiter: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LayoutConfig {
+    pub unicode: Option<bool>,
+    pub ellipsis: Option<String>,
+    pub pointer: Option<String>,
+    pub marker: Option<String>,
+    pub tabs: Option<bool>,
+    pub group: Option<bool>,
+    pub scroll_off: Option<usize>,
+    pub accelerate_after: Option<u32>,
+    pub accelerate_step: Option<usize>,
+    pub show_archived: Option<bool>,
+    pub thumbnails: Option<bool>,
+    pub show_link_targets: Option<bool>,
+    pub show_timings: Option<bool>,
+    pub color: Option<ColorChoice>,
+    pub theme: Option<ThemeName>,
+}
+
+impl Config {
+    /// Loads the config file, if there is one, with `profile` laid over it and
+    /// overridden by the environment variables
+    pub fn load(profile: Option<&str>) -> color_eyre::Result<Self> {
+        Self::load_from(config_file().as_deref(), profile, std::env::vars())
+    }
+
+    /// Loads the config from `file` (which may not exist), the `profile` in it and
+    /// the `RATATUI_FZF_*` variables in `vars`. Each layer is checked on its own, so
+    /// errors say where they come from.
+    pub fn load_from(
+        file: Option<&Path>,
+        profile: Option<&str>,
+        vars: impl IntoIterator<Item = (String, String)>,
+    ) -> color_eyre::Result<Self> {
+        let (mut table, mut profiles) = match file {
+            Some(file) if file.exists() => {
+                let text = fs::read_to_string(file)
+                    .wrap_err_with(|| format!("failed to read the config file {file:?}"))?;
+                let mut table: toml::Table = toml::from_str(&text)
+                    .wrap_err_with(|| format!("invalid config file {file:?}"))?;
+                let profiles = match table.remove("profile") {
+                    Some(toml::Value::Table(profiles)) => profiles,
+                    Some(_) => {
+                        let error = eyre!("[profile] should have a table per profile");
+                        return Err(error.wrap_err(format!("invalid config file {file:?}")));
+                    }
+                    None => toml::Table::new(),
+                };
+                Config::deserialize(table.clone())
+                    .wrap_err_with(|| format!("invalid config file {file:?}"))?;
+                (table, profiles)
+            }
+            _ => (toml::Table::new(), toml::Table::new()),
+        };
+
+        if let Some(name) = profile {
+            let overlay = match profiles.remove(name) {
+                Some(toml::Value::Table(overlay)) => overlay,
+                _ => return Err(eyre!("there's no [profile.{name}] in the config file")),
+            };
+            Config::deserialize(overlay.clone())
+                .wrap_err_with(|| format!("invalid [profile.{name}] in the config file"))?;
+            merge(&mut table, overlay);
+        }
+
+        let env = env_table(vars)?;
+        Config::deserialize(env.clone())
+            .wrap_err_with(|| format!("invalid {ENV_PREFIX}* environment variable"))?;
+        merge(&mut table, env);
+
+        Ok(Config::deserialize(table)?)
+    }
+
+    /// Fills in the flags that weren't given on the command line with the config's.
+    /// Switches given either way on the command line, like --watch or --no-watch,
+    /// win over the config's.
+    pub fn apply_to(&self, cli: &mut Cli) {
+        let backend = &self.backend;
+        // Where the items come from is picked as a whole: a root in the config
+        // mustn't win over --demo, or over lines piped in
+        if !cli.picks_items() {
+            cli.root = backend.root.clone();
+            cli.demo = backend.demo;
+            cli.items_command = backend.command.clone();
+        }
+        cli.max_items = cli.max_items.or(backend.max_items);
+        cli.max_line_len = cli.max_line_len.or(backend.max_line_len);
+        cli.unique = switch(cli.unique, cli.no_unique, backend.unique);
+        cli.seed = cli.seed.or(backend.seed);
+        cli.preview = cli.preview.take().or_else(|| backend.preview.clone());
+        cli.watch = switch(cli.watch, cli.no_watch, backend.watch);
+        cli.rate_limit = cli.rate_limit.or(backend.rate_limit);
+        // Like the source of items, the token's is picked as a whole
+        if cli.token_source().is_none() {
+            cli.token_env = backend.token_env.clone();
+            cli.token_file = backend.token_file.clone();
+            cli.token_command = backend.token_command.clone();
+        }
+        cli.walk_depth = cli.walk_depth.or(backend.walk_depth);
+        // Excluded on top of the flags' ones
+        cli.exclude
+            .extend(backend.exclude.iter().flatten().cloned());
+        cli.follow_symlinks = switch(
+            cli.follow_symlinks,
+            cli.no_follow_symlinks,
+            backend.follow_symlinks,
+        );
+        cli.keep_symlink_cycles = switch(
+            cli.keep_symlink_cycles,
+            cli.no_keep_symlink_cycles,
+            backend.keep_symlink_cycles,
+        );
+
+        let matcher = &self.matcher;
+        cli.normalize = switch(cli.normalize, cli.no_normalize, matcher.normalize);
+        cli.typos = switch(cli.typos, cli.no_typos, matcher.typos);
+        cli.regex = switch(cli.regex, cli.no_regex, matcher.regex);
+        cli.sort = cli.sort.or(matcher.sort);
+        cli.start_at = cli.start_at.or(matcher.start_at);
+        cli.max_results = cli.max_results.or(matcher.max_results);
+        cli.nth = cli.nth.take().or_else(|| matcher.nth.clone());
+        cli.delimiter = cli.delimiter.take().or_else(|| matcher.delimiter.clone());
+
+        let layout = &self.layout;
+        let ascii = layout.unicode.map(|unicode| !unicode);
+        cli.no_unicode = switch(cli.no_unicode, cli.unicode, ascii);
+        cli.ellipsis = cli.ellipsis.take().or_else(|| layout.ellipsis.clone());
+        cli.pointer = cli.pointer.take().or_else(|| layout.pointer.clone());
+        cli.marker = cli.marker.take().or_else(|| layout.marker.clone());
+        cli.tabs = switch(cli.tabs, cli.no_tabs, layout.tabs);
+        cli.group = switch(cli.group, cli.no_group, layout.group);
+        cli.scroll_off = cli.scroll_off.or(layout.scroll_off);
+        cli.accelerate_after = cli.accelerate_after.or(layout.accelerate_after);
+        cli.accelerate_step = cli.accelerate_step.or(layout.accelerate_step);
+        cli.show_archived = switch(
+            cli.show_archived,
+            cli.no_show_archived,
+            layout.show_archived,
+        );
+        cli.thumbnails = switch(cli.thumbnails, cli.no_thumbnails, layout.thumbnails);
+        cli.show_link_targets = switch(
+            cli.show_link_targets,
+            cli.no_show_link_targets,
+            layout.show_link_targets,
+        );
+        cli.show_timings = switch(cli.show_timings, cli.no_show_timings, layout.show_timings);
+        cli.color = cli.color.or(layout.color);
+        cli.theme = cli.theme.or(layout.theme);
+    }
+
+    /// The built-in theme called `name` with the config's styles
+    pub fn theme(&self, name: ThemeName) -> color_eyre::Result<Theme> {
+        let mut theme = Theme::from(name);
+        for (name, style) in &self.theme {
+            theme
+                .set(name, style)
+                .map_err(|e| eyre!("invalid [theme] entry {name:?}: {e}"))?;
+        }
+        Ok(theme)
+    }
+
+    /// How much matching at the start of words adds to scores, see [`Bonuses`]
+    pub fn bonuses(&self) -> Bonuses {
+        let defaults = Bonuses::default();
+        Bonuses {
+            boundary: self.matcher.boundary_bonus.unwrap_or(defaults.boundary),
+            camel: self.matcher.camel_bonus.unwrap_or(defaults.camel),
+            leaf: self.matcher.leaf_bonus.unwrap_or(defaults.leaf),
+        }
+    }
+
+    /// The commands of the item menu, as `(label, command)`
+    pub fn item_commands(&self) -> Vec<(String, String)> {
+        self.actions
+            .iter()
+            .map(|(label, command)| (label.clone(), command.clone()))
+            .collect()
+    }
+
+    /// The config's key bindings, on top of the default ones
+    pub fn keymap(&self) -> color_eyre::Result<Keymap> {
+        let mut keymap = Keymap::default();
+        for (key, action) in &self.keymap {
+            let action: Action = action
+                .parse()
+                .map_err(|e| eyre!("invalid [keymap] entry {key:?}: {e}"))?;
+            keymap
+                .bind(key, action)
+                .map_err(|e| eyre!("invalid [keymap] entry {key:?}: {e}"))?;
+        }
+        Ok(keymap)
+    }
+}
+
+/// Turns variables like `RATATUI_FZF_LAYOUT_TABS=true` into `[layout] tabs = true`.
+/// Values are read as TOML when the setting takes what they parse to, and as plain
+/// strings otherwise: `RATATUI_FZF_MATCHER_NTH=1` is the string `"1"`.
+fn env_table(vars: impl IntoIterator<Item = (String, String)>) -> color_eyre::Result<toml::Table> {
+    let mut table = toml::Table::new();
+    for (name, value) in vars {
+        let Some(setting) = name.strip_prefix(ENV_PREFIX) else {
+            continue;
+        };
+        if name == DEFAULT_OPTS_VAR || name == DEFAULT_COMMAND_VAR {
+            continue;
+        }
+        let setting = setting.to_ascii_lowercase();
+        let section = ENV_SECTIONS
+            .into_iter()
+            .find(|section| setting.starts_with(&format!("{section}_")))
+            .ok_or_else(|| {
+                eyre!(
+                    "{name} doesn't start with {ENV_PREFIX} followed by one of {}",
+                    ENV_SECTIONS.map(str::to_ascii_uppercase).join(", ")
+                )
+            })?;
+        let key = setting[section.len() + 1..].to_string();
+
+        let parsed = toml::from_str::<toml::Table>(&format!("value = {value}"))
+            .ok()
+            .and_then(|mut parsed| parsed.remove("value"))
+            .filter(|parsed| {
+                let setting = toml::Table::from_iter([(key.clone(), parsed.clone())]);
+                let config = toml::Table::from_iter([(section.to_string(), setting.into())]);
+                Config::deserialize(config).is_ok()
+            });
+        let value = parsed.unwrap_or(toml::Value::String(value));
+
+        let section = table
+            .entry(section)
+            .or_insert_with(|| toml::Value::Table(toml::Table::new()));
+        if let toml::Value::Table(section) = section {
+            section.insert(key, value);
+        }
+    }
+
+    Ok(table)
+}
+
+/// A switch turned `on` or `off` by flags, or else by the config
+fn switch(on: bool, off: bool, config: Option<bool>) -> bool {
+    on || (!off && config.unwrap_or(false))
+}
+
+/// Adds the settings of `overlay` to `base`, replacing the ones found in both
+fn merge(base: &mut toml::Table, overlay: toml::Table) {
+    for (key, value) in overlay {
+        match (base.get_mut(&key), value) {
+            (Some(toml::Value::Table(base)), toml::Value::Table(overlay)) => merge(base, overlay),
+            (_, value) => {
+                base.insert(key, value);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use clap::Parser;
+
+    use super::*;
+    use crate::auth::TokenSource;
+
+    fn vars(vars: &[(&str, &str)]) -> Vec<(String, String)> {
+        vars.iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_layers() -> color_eyre::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let file = dir.path().join("config.toml");
+        fs::write(
+            &file,
+            r#"
+[backend]
+root = "/shows"
+
+[matcher]
+sort = "recency"
+start_at = "query-best"
+max_results = 50
+camel_bonus = 0
+
+[theme]
+highlight = "reversed"
+
+[keymap]
+"ctrl-o" = "toggle-preview"
+
+[actions]
+"Queue render" = "queue-render {path}"
+"#,
+        )?;
+
+        let config = Config::load_from(
+            Some(&file),
+            None,
+            vars(&[
+                ("RATATUI_FZF_MATCHER_MAX_RESULTS", "10"),
+                ("RATATUI_FZF_LAYOUT_ELLIPSIS", "~"),
+                ("HOME", "/home/me"),
+                ("RATATUI_FZF_DEFAULT_OPTS", "--tabs"),
+            ]),
+        )?;
+        assert_eq!(config.backend.root, Some(PathBuf::from("/shows")));
+        assert_eq!(config.matcher.sort, Some(SortMode::Recency));
+        assert_eq!(config.matcher.max_results, Some(10));
+        assert_eq!(
+            config.bonuses(),
+            Bonuses {
+                camel: 0,
+                ..Bonuses::default()
+            }
+        );
+        assert_eq!(config.layout.ellipsis.as_deref(), Some("~"));
+        config.theme(ThemeName::Light)?;
+        config.keymap()?;
+        assert_eq!(
+            config.item_commands(),
+            [("Queue render".into(), "queue-render {path}".into())]
+        );
+
+        let mut cli = Cli {
+            max_results: Some(3),
+            ..Cli::default()
+        };
+        config.apply_to(&mut cli);
+        assert_eq!(cli.max_results, Some(3));
+        assert_eq!(cli.sort, Some(SortMode::Recency));
+        assert_eq!(cli.start_at, Some(StartAt::QueryBest));
+        assert_eq!(cli.root, Some(PathBuf::from("/shows")));
+
+        assert_eq!(Config::load_from(None, None, vars(&[]))?, Config::default());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_flags_win() -> color_eyre::Result<()> {
+        let config = Config::load_from(
+            None,
+            None,
+            vars(&[
+                ("RATATUI_FZF_BACKEND_ROOT", "/shows"),
+                ("RATATUI_FZF_BACKEND_WATCH", "true"),
+                ("RATATUI_FZF_MATCHER_NTH", "1"),
+                ("RATATUI_FZF_LAYOUT_UNICODE", "false"),
+                ("RATATUI_FZF_BACKEND_TOKEN_FILE", "/run/token"),
+            ]),
+        )?;
+        assert_eq!(config.matcher.nth.as_deref(), Some("1"));
+
+        let mut cli = Cli::parse_from(["cgs-go", "--demo", "5", "--no-watch", "--unicode"]);
+        config.apply_to(&mut cli);
+        assert_eq!(
+            cli.token_source(),
+            Some(TokenSource::File("/run/token".into()))
+        );
+        assert_eq!(cli.root, None);
+        assert_eq!(cli.demo, Some(5));
+        assert!(!cli.watch);
+        assert!(!cli.no_unicode);
+
+        // Lines piped in are a source too
+        let mut cli = Cli {
+            stdin_piped: true,
+            ..Cli::default()
+        };
+        config.apply_to(&mut cli);
+        assert_eq!(cli.root, None);
+        assert!(cli.watch);
+        assert!(cli.no_unicode);
+
+        let mut cli = Cli::default();
+        config.apply_to(&mut cli);
+        assert_eq!(cli.root, Some(PathBuf::from("/shows")));
+
+        // A token source on the command line replaces the config's
+        let mut cli = Cli::parse_from(["cgs-go", "--token-env", "TRACKER_TOKEN"]);
+        config.apply_to(&mut cli);
+        assert_eq!(
+            cli.token_source(),
+            Some(TokenSource::Env("TRACKER_TOKEN".into()))
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_errors_say_where() -> color_eyre::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let file = dir.path().join("config.toml");
+
+        fs::write(&file, "[matcher]\nnormalise = true\n")?;
+        let error = format!(
+            "{:#}",
+            Config::load_from(Some(&file), None, vars(&[])).unwrap_err()
+        );
+        assert!(error.contains("config.toml"), "{error}");
+        assert!(error.contains("unknown field `normalise`"), "{error}");
+
+        let error = format!(
+            "{:#}",
+            Config::load_from(None, None, vars(&[("RATATUI_FZF_MATCHER_SORT", "random")]))
+                .unwrap_err()
+        );
+        assert!(error.contains("environment variable"), "{error}");
+
+        fs::write(&file, "[keymap]\n\"ctrl-o\" = \"explode\"\n")?;
+        let config = Config::load_from(Some(&file), None, vars(&[]))?;
+        let error = format!("{:#}", config.keymap().unwrap_err());
+        assert!(error.contains("unknown action \"explode\""), "{error}");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_profiles() -> color_eyre::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let file = dir.path().join("config.toml");
+        fs::write(
+            &file,
+            r#"
+[backend]
+root = "/shows"
+
+[layout]
+tabs = true
+
+[profile.film_a.backend]
+root = "/mnt/film_a"
+
+[profile.film_a.layout]
+theme = "light"
+"#,
+        )?;
+
+        let config = Config::load_from(Some(&file), None, vars(&[]))?;
+        assert_eq!(config.backend.root, Some(PathBuf::from("/shows")));
+        assert_eq!(config.layout.theme, None);
+
+        let config = Config::load_from(
+            Some(&file),
+            Some("film_a"),
+            vars(&[("RATATUI_FZF_LAYOUT_THEME", "high-contrast")]),
+        )?;
+        assert_eq!(config.backend.root, Some(PathBuf::from("/mnt/film_a")));
+        assert_eq!(config.layout.tabs, Some(true));
+        assert_eq!(config.layout.theme, Some(ThemeName::HighContrast));
+
+        let error = Config::load_from(Some(&file), Some("film_b"), vars(&[])).unwrap_err();
+        assert!(error.to_string().contains("[profile.film_b]"), "{error}");
+
+        Ok(())
+    }
+}
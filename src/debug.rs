@@ -0,0 +1,143 @@
+use std::{
+    collections::VecDeque,
+    time::{Duration, Instant},
+};
+
+use ratatui::{
+    buffer::Buffer,
+    layout::Rect,
+    symbols::border,
+    text::Line,
+    widgets::{Block, Borders, Clear, Paragraph, Widget},
+};
+
+/// Timings of the app's hot paths, shown in the debug overlay (F10)
+#[derive(Debug, Default)]
+pub struct DebugStats {
+    /// When the frames of the last second were drawn, oldest first
+    frames: VecDeque<Instant>,
+    /// How long drawing the last frame took
+    pub draw_time: Duration,
+    /// How long filtering and sorting the results took, the last time
+    pub match_time: Duration,
+    /// How long fetching the last page of items from the backend took
+    pub fetch_time: Duration,
+    /// From reading the last event to the frame showing its effect being drawn
+    pub event_latency: Duration,
+    /// When the event being handled was read, until the next frame is drawn
+    event_received: Option<Instant>,
+}
+
+impl DebugStats {
+    pub fn event_received(&mut self) {
+        self.event_received = Some(Instant::now());
+    }
+
+    /// Records a frame drawn from `start` until now
+    pub fn frame_drawn(&mut self, start: Instant) {
+        let now = Instant::now();
+        self.draw_time = now - start;
+        if let Some(received) = self.event_received.take() {
+            self.event_latency = now - received;
+        }
+
+        self.frames.push_back(now);
+        while self
+            .frames
+            .front()
+            .is_some_and(|frame| now - *frame > Duration::from_secs(1))
+        {
+            self.frames.pop_front();
+        }
+    }
+
+    /// Frames drawn during the last second
+    pub fn fps(&self) -> usize {
+        self.frames.len()
+    }
+
+    /// The last match and fetch times with the number of items, like `3ms + 12ms fetch / 340 items`
+    pub fn summary(&self, items: usize) -> String {
+        let millis = |duration: Duration| duration.as_millis();
+        let fetch = if self.fetch_time.is_zero() {
+            String::new()
+        } else {
+            format!(" + {}ms fetch", millis(self.fetch_time))
+        };
+        format!("{}ms{fetch} / {items} items", millis(self.match_time))
+    }
+}
+
+/// The debug overlay: the stats plus item counts, in the top right corner
+pub struct DebugOverlay<'a> {
+    pub stats: &'a DebugStats,
+    pub items: usize,
+    pub matches: usize,
+    /// What the border is drawn with
+    pub borders: border::Set,
+}
+
+impl Widget for DebugOverlay<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let millis = |duration: Duration| format!("{:.2} ms", duration.as_secs_f64() * 1000.0);
+        let lines = vec![
+            Line::from(format!("fps      {}", self.stats.fps())),
+            Line::from(format!("draw     {}", millis(self.stats.draw_time))),
+            Line::from(format!("match    {}", millis(self.stats.match_time))),
+            Line::from(format!("fetch    {}", millis(self.stats.fetch_time))),
+            Line::from(format!("latency  {}", millis(self.stats.event_latency))),
+            Line::from(format!("items    {}", self.items)),
+            Line::from(format!("matches  {}", self.matches)),
+        ];
+
+        let width = 26.min(area.width);
+        let height = (lines.len() as u16 + 2).min(area.height);
+        let area = Rect {
+            x: area.right() - width,
+            y: area.y,
+            width,
+            height,
+        };
+
+        Clear.render(area, buf);
+        Paragraph::new(lines)
+            .block(
+                Block::default()
+                    .title(" Debug ")
+                    .borders(Borders::ALL)
+                    .border_set(self.borders),
+            )
+            .render(area, buf);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_frame_stats() {
+        let mut stats = DebugStats::default();
+        let start = Instant::now();
+        stats.frame_drawn(start);
+        assert_eq!(stats.fps(), 1);
+        assert_eq!(stats.event_latency, Duration::ZERO);
+
+        stats.event_received();
+        stats.frame_drawn(Instant::now());
+        assert_eq!(stats.fps(), 2);
+        assert!(stats.event_latency >= stats.draw_time);
+    }
+
+    #[test]
+    fn test_summary() {
+        let mut stats = DebugStats {
+            match_time: Duration::from_micros(3_400),
+            ..DebugStats::default()
+        };
+        assert_eq!(stats.summary(340), "3ms / 340 items");
+        stats.fetch_time = Duration::from_millis(12);
+        assert_eq!(stats.summary(340), "3ms + 12ms fetch / 340 items");
+    }
+}
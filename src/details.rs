@@ -0,0 +1,176 @@
+use std::time::{Duration, SystemTime};
+
+use ratatui::{
+    buffer::Buffer,
+    layout::{Alignment, Constraint, Rect},
+    style::{Modifier, Style},
+    symbols::border,
+    text::Line,
+    widgets::{Block, Borders, Clear, Paragraph, Row, Table, Widget, Wrap},
+};
+
+use crate::{action::Action, dialog::centered_rect, text};
+
+/// A modal popup listing everything known about an item, as a key/value table.
+/// While it's open, it should receive every action.
+#[derive(Debug, Clone)]
+pub struct DetailsPopup {
+    /// Name of the item the details are about
+    name: String,
+    rows: Vec<(String, String)>,
+    /// Why the backend couldn't tell more, if it failed to
+    error: Option<String>,
+    /// Style of the keys
+    key_style: Style,
+    /// What the border is drawn with
+    borders: border::Set,
+}
+
+impl DetailsPopup {
+    pub fn new(name: impl Into<String>, rows: Vec<(String, String)>) -> Self {
+        Self {
+            name: name.into(),
+            rows,
+            error: None,
+            key_style: Style::new().add_modifier(Modifier::BOLD),
+            borders: border::PLAIN,
+        }
+    }
+
+    pub fn with_error(mut self, error: impl Into<String>) -> Self {
+        self.error = Some(error.into());
+        self
+    }
+
+    pub fn with_key_style(mut self, key_style: Style) -> Self {
+        self.key_style = key_style;
+        self
+    }
+
+    pub fn with_borders(mut self, borders: border::Set) -> Self {
+        self.borders = borders;
+        self
+    }
+
+    /// Handles an action. Returns whether the popup should close.
+    pub fn handle_action(&mut self, action: Action) -> bool {
+        matches!(
+            action,
+            Action::Back | Action::Accept | Action::ShowDetails | Action::Quit
+        )
+    }
+}
+
+impl Widget for &DetailsPopup {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let key_width = self
+            .rows
+            .iter()
+            .map(|(key, _)| text::width(key))
+            .max()
+            .unwrap_or_default();
+        let value_width = self
+            .rows
+            .iter()
+            .map(|(_, value)| text::width(value))
+            .max()
+            .unwrap_or_default();
+        let width = (key_width + value_width + 5).max(text::width(&self.name) + 4);
+        let height = self.rows.len() + 2 + if self.error.is_some() { 2 } else { 0 };
+        let area = centered_rect(width as u16, height as u16, area);
+
+        let block = Block::default()
+            .title(format!(" {} ", self.name))
+            .title_alignment(Alignment::Center)
+            .borders(Borders::ALL)
+            .border_set(self.borders);
+        let inner = block.inner(area);
+        Clear.render(area, buf);
+        block.render(area, buf);
+
+        let rows = self.rows.iter().map(|(key, value)| {
+            Row::new([
+                Line::styled(key.as_str(), self.key_style),
+                Line::raw(value.as_str()),
+            ])
+        });
+        let widths = [Constraint::Length(key_width as u16), Constraint::Min(0)];
+        Widget::render(Table::new(rows, widths), inner, buf);
+
+        if let Some(error) = &self.error {
+            let error_area = Rect {
+                y: inner.y + self.rows.len() as u16 + 1,
+                height: inner.height.saturating_sub(self.rows.len() as u16 + 1),
+                ..inner
+            };
+            Paragraph::new(error.as_str())
+                .wrap(Wrap { trim: true })
+                .render(error_area, buf);
+        }
+    }
+}
+
+/// Writes a time like `2023-11-14 22:13 UTC`
+pub fn format_time(time: SystemTime) -> String {
+    let seconds = match time.duration_since(SystemTime::UNIX_EPOCH) {
+        Ok(since_epoch) => since_epoch.as_secs(),
+        Err(_) => return "before 1970".to_string(),
+    };
+    let (days, seconds_today) = (seconds / 86_400, seconds % 86_400);
+
+    // Days since the epoch to a civil date, see http://howardhinnant.github.io/date_algorithms.html
+    let z = days as i64 + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{year}-{month:02}-{day:02} {:02}:{:02} UTC",
+        seconds_today / 3600,
+        seconds_today % 3600 / 60
+    )
+}
+
+/// Writes how long ago something happened, roughly, like `2h ago`
+pub fn format_age(age: Duration) -> String {
+    match age.as_secs() {
+        0..60 => "just now".to_string(),
+        seconds @ 60..3600 => format!("{}m ago", seconds / 60),
+        seconds @ 3600..86_400 => format!("{}h ago", seconds / 3600),
+        seconds => format!("{}d ago", seconds / 86_400),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_format_time() {
+        let at = |seconds| SystemTime::UNIX_EPOCH + Duration::from_secs(seconds);
+        assert_eq!(format_time(at(0)), "1970-01-01 00:00 UTC");
+        assert_eq!(format_time(at(1_700_000_000)), "2023-11-14 22:13 UTC");
+        assert_eq!(format_time(at(951_782_400)), "2000-02-29 00:00 UTC");
+    }
+
+    #[test]
+    fn test_format_age() {
+        let ages = [5, 90, 2 * 3600 + 59 * 60, 3 * 86_400];
+        let formatted: Vec<String> = ages
+            .into_iter()
+            .map(|seconds| format_age(Duration::from_secs(seconds)))
+            .collect();
+        assert_eq!(formatted, ["just now", "1m ago", "2h ago", "3d ago"]);
+    }
+}
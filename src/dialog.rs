@@ -0,0 +1,148 @@
+use ratatui::{
+    buffer::Buffer,
+    layout::{Alignment, Constraint, Layout, Rect},
+    style::{Modifier, Style},
+    symbols::border,
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Paragraph, Widget, Wrap},
+};
+
+use crate::action::Action;
+
+/// Returns a `width` x `height` rect centered in `area`, clamped to fit in it
+pub fn centered_rect(width: u16, height: u16, area: Rect) -> Rect {
+    let width = width.min(area.width);
+    let height = height.min(area.height);
+
+    Rect {
+        x: area.x + (area.width - width) / 2,
+        y: area.y + (area.height - height) / 2,
+        width,
+        height,
+    }
+}
+
+/// A modal Yes/No popup guarding an action of type `A`.
+/// While it's open, it should receive every key press.
+#[derive(Debug, Clone)]
+pub struct ConfirmDialog<A> {
+    title: String,
+    message: String,
+    action: A,
+    yes_selected: bool,
+    /// Style of the selected button
+    highlight: Style,
+    /// What the border is drawn with
+    borders: border::Set,
+}
+
+impl<A> ConfirmDialog<A> {
+    pub fn new(title: impl Into<String>, message: impl Into<String>, action: A) -> Self {
+        Self {
+            title: title.into(),
+            message: message.into(),
+            action,
+            yes_selected: true,
+            highlight: Style::new().add_modifier(Modifier::REVERSED),
+            borders: border::PLAIN,
+        }
+    }
+
+    pub fn with_highlight(mut self, highlight: Style) -> Self {
+        self.highlight = highlight;
+        self
+    }
+
+    pub fn with_borders(mut self, borders: border::Set) -> Self {
+        self.borders = borders;
+        self
+    }
+
+    /// Handles an action. Returns `Some(true)` if the user confirmed,
+    /// `Some(false)` if they cancelled and `None` if the dialog stays open.
+    pub fn handle_action(&mut self, action: Action) -> Option<bool> {
+        match action {
+            Action::Accept => Some(self.yes_selected),
+            Action::Insert('y') | Action::Insert('Y') => Some(true),
+            Action::Back | Action::Insert('n') | Action::Insert('N') => Some(false),
+            Action::ScrollLeft
+            | Action::ScrollRight
+            | Action::FocusNext
+            | Action::FocusPrevious => {
+                self.yes_selected = !self.yes_selected;
+                None
+            }
+            _ => None,
+        }
+    }
+
+    pub fn into_action(self) -> A {
+        self.action
+    }
+}
+
+impl<A> Widget for &ConfirmDialog<A> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let width = (self.message.len() as u16 + 4).clamp(30, 60);
+        let area = centered_rect(width, 6, area);
+
+        let block = Block::default()
+            .title(format!(" {} ", self.title))
+            .title_alignment(Alignment::Center)
+            .borders(Borders::ALL)
+            .border_set(self.borders);
+        let inner = block.inner(area);
+
+        Clear.render(area, buf);
+        block.render(area, buf);
+
+        let [message_area, buttons_area] =
+            Layout::vertical([Constraint::Min(1), Constraint::Length(1)]).areas(inner);
+
+        Paragraph::new(self.message.as_str())
+            .alignment(Alignment::Center)
+            .wrap(Wrap { trim: true })
+            .render(message_area, buf);
+
+        let button = |label: &'static str, selected: bool| {
+            if selected {
+                Span::styled(label, self.highlight)
+            } else {
+                Span::raw(label)
+            }
+        };
+        Line::from(vec![
+            button(" Yes ", self.yes_selected),
+            "   ".into(),
+            button(" No ", !self.yes_selected),
+        ])
+        .alignment(Alignment::Center)
+        .render(buttons_area, buf);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_confirm_dialog_keys() {
+        let mut dialog = ConfirmDialog::new("Create", "Create 'sh010'?", 42);
+        assert_eq!(dialog.handle_action(Action::Insert('x')), None);
+        assert_eq!(dialog.handle_action(Action::ScrollRight), None);
+        assert_eq!(dialog.handle_action(Action::Accept), Some(false));
+        assert_eq!(dialog.handle_action(Action::FocusNext), None);
+        assert_eq!(dialog.handle_action(Action::Accept), Some(true));
+        assert_eq!(dialog.handle_action(Action::Back), Some(false));
+        assert_eq!(dialog.handle_action(Action::Quit), None);
+        assert_eq!(dialog.into_action(), 42);
+    }
+
+    #[test]
+    fn test_centered_rect_is_clamped() {
+        let area = Rect::new(0, 0, 20, 10);
+        assert_eq!(centered_rect(10, 4, area), Rect::new(5, 3, 10, 4));
+        assert_eq!(centered_rect(50, 40, area), area);
+    }
+}
@@ -0,0 +1,186 @@
+use std::{
+    fmt, fs,
+    io::{IsTerminal, Write},
+    path::Path,
+};
+
+use crate::{
+    backend::{Backend, FsBackend},
+    cli::Cli,
+    config::{self, Config},
+    graphics::GraphicsProtocol,
+    state,
+    theme::ColorDepth,
+};
+
+/// The result of one check, with what to do about it when it failed
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Check {
+    name: &'static str,
+    result: Result<String, String>,
+}
+
+impl Check {
+    fn new(name: &'static str, result: Result<String, String>) -> Self {
+        Self { name, result }
+    }
+}
+
+impl fmt::Display for Check {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.result {
+            Ok(found) => write!(f, "ok    {:<10} {found}", self.name),
+            Err(problem) => write!(f, "FAIL  {:<10} {problem}", self.name),
+        }
+    }
+}
+
+/// Checks the config, the backend, where state is kept and what the terminal
+/// can do, without starting the app. Returns whether everything is fine.
+pub fn run(cli: &Cli, mut out: impl Write) -> color_eyre::Result<bool> {
+    let mut cli = cli.clone();
+    let checks = [
+        check_config(&mut cli),
+        check_backend(&cli),
+        check_state_dir(),
+        check_terminal(),
+        check_colors(&cli),
+        check_keyboard(),
+        check_images(),
+    ];
+
+    for check in &checks {
+        writeln!(out, "{check}")?;
+    }
+    Ok(checks.iter().all(|check| check.result.is_ok()))
+}
+
+/// Loads the config like the app does, flags included, down to the theme and keymap
+fn check_config(cli: &mut Cli) -> Check {
+    let loaded = Config::load(cli.profile.as_deref()).and_then(|config| {
+        config.apply_to(cli);
+        config.theme(cli.theme.unwrap_or_default())?;
+        config.keymap()?;
+        Ok(())
+    });
+    let result = match (loaded, config::config_file()) {
+        (Err(e), _) => Err(format!(
+            "{e:#}. Fix it, or move it away to start from the defaults"
+        )),
+        (Ok(()), Some(file)) if file.exists() => Ok(format!("{} is valid", file.display())),
+        (Ok(()), Some(file)) => Ok(format!("no config file at {}", file.display())),
+        (Ok(()), None) => Ok("no config directory, using the defaults".to_string()),
+    };
+    Check::new("config", result)
+}
+
+fn check_backend(cli: &Cli) -> Check {
+    let result = match (&cli.root, cli.demo, &cli.items_command) {
+        (Some(root), _, _) => check_root(root),
+        (None, Some(size), _) => Ok(format!("generated demo data, {size} items")),
+        (None, None, Some(command)) => Ok(format!("lines printed by `{command}`")),
+        (None, None, None) => Ok("no --root given, showing the example data".to_string()),
+    };
+    Check::new("backend", result)
+}
+
+/// Whether the projects under `root` can be listed
+fn check_root(root: &Path) -> Result<String, String> {
+    if !root.exists() {
+        return Err(format!(
+            "{} doesn't exist. Check --root, or mount the share it's on",
+            root.display()
+        ));
+    }
+    if !root.is_dir() {
+        return Err(format!(
+            "{} isn't a directory. --root takes the directory with the projects in it",
+            root.display()
+        ));
+    }
+    match FsBackend::new(root).fetch(&[], 0, 1) {
+        Ok(_) => Ok(format!("{} can be listed", root.display())),
+        Err(e) => Err(format!(
+            "can't list {}: {e:#}. Check its permissions",
+            root.display()
+        )),
+    }
+}
+
+/// Pins, the history and the last context are kept there
+fn check_state_dir() -> Check {
+    let result = match state::state_dir() {
+        Some(dir) => match fs::create_dir_all(&dir) {
+            Ok(()) => Ok(dir.display().to_string()),
+            Err(e) => Err(format!(
+                "can't create {}: {e}. Pins, history and --resume won't be saved",
+                dir.display()
+            )),
+        },
+        None => Err("no home directory, pins, history and --resume won't be saved".to_string()),
+    };
+    Check::new("state", result)
+}
+
+fn check_terminal() -> Check {
+    let term = std::env::var("TERM").unwrap_or_else(|_| "unknown".to_string());
+    let result = if std::io::stdout().is_terminal() {
+        Ok(format!("TERM={term}"))
+    } else {
+        Ok(format!("TERM={term}, but the output isn't a terminal"))
+    };
+    Check::new("terminal", result)
+}
+
+fn check_colors(cli: &Cli) -> Check {
+    let colors = match cli.color_depth() {
+        ColorDepth::None => "none (see NO_COLOR and --color)",
+        ColorDepth::Ansi16 => "16",
+        ColorDepth::Ansi256 => "256",
+        ColorDepth::TrueColor => "true color",
+    };
+    Check::new("colors", Ok(colors.to_string()))
+}
+
+/// Asks the terminal, so only when there's one to ask
+fn check_keyboard() -> Check {
+    let supported = std::io::stdout().is_terminal()
+        && crossterm::terminal::supports_keyboard_enhancement().unwrap_or(false);
+    let keyboard = if supported {
+        "kitty keyboard protocol, chords like ctrl-enter can be bound"
+    } else {
+        "no kitty keyboard protocol, chords like ctrl-enter can't be bound"
+    };
+    Check::new("keyboard", Ok(keyboard.to_string()))
+}
+
+fn check_images() -> Check {
+    let images = match GraphicsProtocol::detect() {
+        GraphicsProtocol::Kitty => "kitty graphics protocol",
+        GraphicsProtocol::Sixel => "sixel",
+        GraphicsProtocol::None => "none detected, thumbnails are shown as paths",
+    };
+    Check::new("images", Ok(images.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_check_root() -> color_eyre::Result<()> {
+        let dir = tempfile::tempdir()?;
+        assert!(check_root(dir.path()).is_ok());
+
+        let missing = dir.path().join("missing");
+        let problem = check_root(&missing).unwrap_err();
+        assert!(problem.contains("doesn't exist"));
+
+        let file = dir.path().join("file.txt");
+        fs::write(&file, "")?;
+        assert!(check_root(&file).unwrap_err().contains("isn't a directory"));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,86 @@
+use std::collections::BTreeSet;
+
+use crate::{backend::Item, details};
+
+/// Writes items as CSV, one row per item with its path in the hierarchy,
+/// followed by a column for every metadata key any of them has
+pub fn to_csv<'a>(rows: impl IntoIterator<Item = (Vec<String>, &'a Item)>) -> String {
+    let rows: Vec<(Vec<String>, &Item)> = rows.into_iter().collect();
+    let keys: BTreeSet<&str> = rows
+        .iter()
+        .flat_map(|(_, item)| item.metadata.keys().map(String::as_str))
+        .collect();
+
+    let mut csv = String::new();
+    let header = ["path", "name", "kind", "status", "modified", "archived"];
+    push_row(&mut csv, header.into_iter().chain(keys.iter().copied()));
+    for (path, item) in &rows {
+        let fixed = [
+            path.join("/"),
+            item.name.clone(),
+            item.kind.label().to_string(),
+            item.status
+                .map(|status| status.label().to_string())
+                .unwrap_or_default(),
+            item.modified.map(details::format_time).unwrap_or_default(),
+            item.archived.to_string(),
+        ];
+        let metadata = keys
+            .iter()
+            .map(|key| item.metadata.get(*key).map_or("", String::as_str));
+        push_row(&mut csv, fixed.iter().map(String::as_str).chain(metadata));
+    }
+
+    csv
+}
+
+fn push_row<'a>(csv: &mut String, fields: impl Iterator<Item = &'a str>) {
+    for (i, field) in fields.enumerate() {
+        if i > 0 {
+            csv.push(',');
+        }
+        csv.push_str(&escape(field));
+    }
+    csv.push_str("\r\n");
+}
+
+/// Quotes a field when it has a comma, a quote or a line break in it
+fn escape(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::backend::{ItemKind, Status};
+
+    #[test]
+    fn test_to_csv() {
+        let mut shot = Item::new("sh010", ItemKind::Shot);
+        shot.status = Some(Status::Review);
+        shot.metadata.insert("artist".into(), "Ada, L.".into());
+        let mut other = Item::new("sh020 \"final\"", ItemKind::Shot);
+        other.metadata.insert("frames".into(), "1001-1100".into());
+        let path = |name: &str| vec!["man_vs_bee".to_string(), name.to_string()];
+
+        let csv = to_csv([(path("sh010"), &shot), (path("sh020"), &other)]);
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(
+            lines[0],
+            "path,name,kind,status,modified,archived,artist,frames"
+        );
+        assert_eq!(
+            lines[1],
+            "man_vs_bee/sh010,sh010,shot,review,,false,\"Ada, L.\","
+        );
+        assert_eq!(
+            lines[2],
+            "man_vs_bee/sh020,\"sh020 \"\"final\"\"\",shot,,,false,,1001-1100"
+        );
+    }
+}
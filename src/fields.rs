@@ -0,0 +1,132 @@
+/// A 1-based, inclusive range of fields, as in `fzf --nth`.
+/// Negative indices count from the last field (`-1` is the last one).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FieldRange {
+    start: Option<isize>,
+    end: Option<isize>,
+}
+
+impl FieldRange {
+    /// Resolves the range to 0-based indices into a record of `count` fields
+    fn indices(&self, count: usize) -> std::ops::Range<usize> {
+        let resolve = |index: isize| {
+            if index < 0 {
+                count as isize + index
+            } else {
+                index - 1
+            }
+        };
+
+        let start = self.start.map_or(0, resolve).max(0);
+        let end = self
+            .end
+            .map_or(count as isize - 1, resolve)
+            .min(count as isize - 1);
+        if end < start {
+            return 0..0;
+        }
+
+        start as usize..end as usize + 1
+    }
+}
+
+/// Picks some fields out of delimited records (e.g. `shot010<TAB>anna<TAB>approved`),
+/// so matching can be restricted to them
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldSelector {
+    /// Splits records into fields. `None` means runs of whitespace, like awk.
+    delimiter: Option<String>,
+    ranges: Vec<FieldRange>,
+}
+
+impl FieldSelector {
+    /// Parses a comma separated list of fields like `1,3`, `2..`, `..-2` or `-1`
+    pub fn parse(nth: &str, delimiter: Option<&str>) -> Result<Self, String> {
+        let ranges = nth
+            .split(',')
+            .map(|spec| parse_range(spec.trim()))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        // Make `--delimiter '\t'` work without shell quoting tricks
+        let delimiter = delimiter.map(|d| d.replace("\\t", "\t"));
+        if delimiter.as_deref() == Some("") {
+            return Err("the delimiter can't be empty".to_string());
+        }
+
+        Ok(Self { delimiter, ranges })
+    }
+
+    /// Returns the selected fields of `record`, joined back with the delimiter
+    pub fn select(&self, record: &str) -> String {
+        let fields: Vec<&str> = match &self.delimiter {
+            Some(delimiter) => record.split(delimiter.as_str()).collect(),
+            None => record.split_whitespace().collect(),
+        };
+
+        let selected: Vec<&str> = self
+            .ranges
+            .iter()
+            .flat_map(|range| fields[range.indices(fields.len())].iter().copied())
+            .collect();
+
+        selected.join(self.delimiter.as_deref().unwrap_or(" "))
+    }
+}
+
+fn parse_range(spec: &str) -> Result<FieldRange, String> {
+    let parse_index = |index: &str| -> Result<Option<isize>, String> {
+        if index.is_empty() {
+            return Ok(None);
+        }
+        match index.parse::<isize>() {
+            Ok(0) | Err(_) => Err(format!("invalid field index '{index}' in '{spec}'")),
+            Ok(index) => Ok(Some(index)),
+        }
+    };
+
+    let (start, end) = match spec.split_once("..") {
+        Some((start, end)) => (parse_index(start)?, parse_index(end)?),
+        None => {
+            let index = parse_index(spec)?;
+            if index.is_none() {
+                return Err("empty field index".to_string());
+            }
+            (index, index)
+        }
+    };
+
+    Ok(FieldRange { start, end })
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_select_fields() -> Result<(), String> {
+        let record = "shot010\tanna\tapproved\t1001-1100";
+
+        let selector = FieldSelector::parse("1,3", Some("\\t"))?;
+        assert_eq!(selector.select(record), "shot010\tapproved");
+
+        let selector = FieldSelector::parse("-1", Some("\t"))?;
+        assert_eq!(selector.select(record), "1001-1100");
+
+        let selector = FieldSelector::parse("2..", None)?;
+        assert_eq!(selector.select("sh010  anna approved"), "anna approved");
+
+        let selector = FieldSelector::parse("..-2,9", None)?;
+        assert_eq!(selector.select("a b c"), "a b");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(FieldSelector::parse("0", None).is_err());
+        assert!(FieldSelector::parse("1,x", None).is_err());
+        assert!(FieldSelector::parse("", None).is_err());
+        assert!(FieldSelector::parse("1", Some("")).is_err());
+    }
+}
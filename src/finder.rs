@@ -0,0 +1,179 @@
+use color_eyre::eyre::{eyre, WrapErr};
+use serde::Serialize;
+
+use crate::{
+    backend::{Backend, Item},
+    matcher::FuzzyMatcher,
+    query::Query,
+};
+
+/// What accepting a result did, see [`Finder::accept`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Accepted {
+    /// Descended into the item, whose children are now the results
+    Entered(Vec<String>),
+    /// Selected a leaf item, given with its full path like `project/sequence/shot`
+    Selected(String),
+}
+
+/// How an item is described to other tools, see [`crate::server`] and [`crate::rpc`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ResultEntry {
+    pub name: String,
+    pub kind: &'static str,
+    /// Whether accepting it selects it instead of descending into it
+    pub leaf: bool,
+}
+
+impl From<&Item> for ResultEntry {
+    fn from(item: &Item) -> Self {
+        Self {
+            name: item.name.clone(),
+            kind: item.kind.label(),
+            leaf: item.kind.is_leaf(),
+        }
+    }
+}
+
+/// The app's browsing and matching logic without a terminal: a current level of
+/// the hierarchy, a query, and the items matching it, best first.
+pub struct Finder<'a> {
+    backend: &'a dyn Backend,
+    matcher: FuzzyMatcher,
+    path: Vec<String>,
+    items: Vec<Item>,
+    query: String,
+    /// Indices into `items` of the entries matching the query, best first
+    matches: Vec<usize>,
+}
+
+impl<'a> Finder<'a> {
+    /// Starts at the top level of `backend`
+    pub fn new(backend: &'a dyn Backend, matcher: FuzzyMatcher) -> color_eyre::Result<Self> {
+        let mut finder = Self {
+            backend,
+            matcher,
+            path: Vec::new(),
+            items: Vec::new(),
+            query: String::new(),
+            matches: Vec::new(),
+        };
+        finder.load_items()?;
+        Ok(finder)
+    }
+
+    pub fn path(&self) -> &[String] {
+        &self.path
+    }
+
+    /// Moves to another level of the hierarchy, keeping the query.
+    /// Stays where it was if the new level can't be listed.
+    pub fn set_path(&mut self, path: Vec<String>) -> color_eyre::Result<()> {
+        if path == self.path {
+            return Ok(());
+        }
+        let previous = std::mem::replace(&mut self.path, path);
+        if let Err(e) = self.load_items() {
+            self.path = previous;
+            return Err(e);
+        }
+        Ok(())
+    }
+
+    pub fn query(&self) -> &str {
+        &self.query
+    }
+
+    pub fn set_query(&mut self, query: impl Into<String>) {
+        self.query = query.into();
+        self.update_matches();
+    }
+
+    /// The items matching the query, best first
+    pub fn results(&self) -> impl Iterator<Item = &Item> {
+        self.matches.iter().map(|i| &self.items[*i])
+    }
+
+    pub fn match_count(&self) -> usize {
+        self.matches.len()
+    }
+
+    /// Accepts the result at `index`: leaf items are selected,
+    /// others are descended into, clearing the query
+    pub fn accept(&mut self, index: usize) -> color_eyre::Result<Accepted> {
+        let item = self
+            .results()
+            .nth(index)
+            .ok_or_else(|| eyre!("there is no result {index}"))?;
+
+        let mut path = self.path.clone();
+        path.push(item.name.clone());
+        if item.kind.is_leaf() {
+            return Ok(Accepted::Selected(path.join("/")));
+        }
+
+        self.path = path;
+        self.query.clear();
+        self.load_items()?;
+        Ok(Accepted::Entered(self.path.clone()))
+    }
+
+    fn load_items(&mut self) -> color_eyre::Result<()> {
+        self.items = self
+            .backend
+            .children(&self.path)
+            .wrap_err_with(|| format!("failed to list items under {:?}", self.path))?;
+        self.update_matches();
+        Ok(())
+    }
+
+    fn update_matches(&mut self) {
+        let query = Query::parse(&self.query);
+        let candidates: Vec<usize> = (0..self.items.len())
+            .filter(|i| {
+                let item = &self.items[*i];
+                !item.archived
+                    && query.matches_facets(item)
+                    && query.matches_scopes(&self.path, item)
+            })
+            .collect();
+
+        let names = candidates.iter().map(|i| self.items[*i].match_text());
+        self.matches = self
+            .matcher
+            .match_names(&query.text, names)
+            .into_iter()
+            .map(|(index, _score)| candidates[index])
+            .collect();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::backend::MockBackend;
+
+    #[test]
+    fn test_finder_navigates_and_selects() -> color_eyre::Result<()> {
+        let mut finder = Finder::new(&MockBackend, FuzzyMatcher::default())?;
+        finder.set_query("man_vs");
+        assert_eq!(finder.results().next().unwrap().name, "man_vs_bee");
+
+        assert_eq!(
+            finder.accept(0)?,
+            Accepted::Entered(vec!["man_vs_bee".to_string()])
+        );
+        assert_eq!(finder.query(), "");
+        assert!(finder.accept(finder.match_count()).is_err());
+
+        finder.accept(0)?;
+        let shot = finder.results().next().unwrap().name.clone();
+        assert_eq!(
+            finder.accept(0)?,
+            Accepted::Selected(format!("{}/{shot}", finder.path().join("/")))
+        );
+
+        Ok(())
+    }
+}
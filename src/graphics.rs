@@ -0,0 +1,132 @@
+use std::{
+    fs,
+    io::{self, Write},
+    path::Path,
+};
+
+use crossterm::{cursor, queue};
+use ratatui::layout::Rect;
+
+/// How the terminal can draw images, if at all
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum GraphicsProtocol {
+    /// The kitty graphics protocol, which can load PNG files by itself
+    Kitty,
+    /// Sixel. Images must already be encoded as sixel, e.g. `thumbnail.six`.
+    Sixel,
+    #[default]
+    None,
+}
+
+impl GraphicsProtocol {
+    /// Guesses what the terminal supports from its environment variables
+    pub fn detect() -> Self {
+        let var = |name| std::env::var(name).unwrap_or_default();
+        let term = var("TERM");
+        let term_program = var("TERM_PROGRAM");
+
+        if std::env::var_os("KITTY_WINDOW_ID").is_some()
+            || term.contains("kitty")
+            || matches!(term_program.as_str(), "WezTerm" | "ghostty")
+        {
+            GraphicsProtocol::Kitty
+        } else if term.contains("sixel") || matches!(term.as_str(), "foot" | "mlterm") {
+            GraphicsProtocol::Sixel
+        } else {
+            GraphicsProtocol::None
+        }
+    }
+
+    /// Whether an image stored at `path` can be drawn with this protocol
+    pub fn can_draw(&self, path: &Path) -> bool {
+        let extension = path
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .unwrap_or_default()
+            .to_ascii_lowercase();
+        match self {
+            GraphicsProtocol::Kitty => extension == "png",
+            GraphicsProtocol::Sixel => matches!(extension.as_str(), "six" | "sixel"),
+            GraphicsProtocol::None => false,
+        }
+    }
+
+    /// Draws the image at `path` so it fills `area`, bypassing the ratatui buffer.
+    /// The cursor is put back where it was.
+    pub fn draw(&self, out: &mut impl Write, path: &Path, area: Rect) -> io::Result<()> {
+        queue!(out, cursor::SavePosition, cursor::MoveTo(area.x, area.y))?;
+        match self {
+            GraphicsProtocol::Kitty => {
+                let path = path.to_string_lossy();
+                write!(
+                    out,
+                    "\x1b_Ga=T,t=f,f=100,q=2,c={},r={};{}\x1b\\",
+                    area.width,
+                    area.height,
+                    base64(path.as_bytes())
+                )?;
+            }
+            GraphicsProtocol::Sixel => out.write_all(&fs::read(path)?)?,
+            GraphicsProtocol::None => {}
+        }
+        queue!(out, cursor::RestorePosition)?;
+        out.flush()
+    }
+
+    /// Removes the images drawn so far. Sixel images are just text cells,
+    /// so they go away when ratatui redraws over them.
+    pub fn clear(&self, out: &mut impl Write) -> io::Result<()> {
+        if *self == GraphicsProtocol::Kitty {
+            write!(out, "\x1b_Ga=d,q=2\x1b\\")?;
+            out.flush()?;
+        }
+        Ok(())
+    }
+}
+
+/// Standard base64 with padding, which is how kitty wants file paths
+pub(crate) fn base64(input: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+    let mut encoded = String::with_capacity(input.len().div_ceil(3) * 4);
+    for chunk in input.chunks(3) {
+        let bytes = [
+            chunk[0],
+            *chunk.get(1).unwrap_or(&0),
+            *chunk.get(2).unwrap_or(&0),
+        ];
+        let bits = u32::from_be_bytes([0, bytes[0], bytes[1], bytes[2]]);
+        for i in 0..4 {
+            if i <= chunk.len() {
+                encoded.push(ALPHABET[(bits >> (18 - 6 * i) & 0x3f) as usize] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+
+    encoded
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_base64() {
+        assert_eq!(base64(b""), "");
+        assert_eq!(base64(b"f"), "Zg==");
+        assert_eq!(base64(b"fo"), "Zm8=");
+        assert_eq!(base64(b"foo"), "Zm9v");
+        assert_eq!(base64(b"/shows/sh010.png"), "L3Nob3dzL3NoMDEwLnBuZw==");
+    }
+
+    #[test]
+    fn test_can_draw() {
+        assert!(GraphicsProtocol::Kitty.can_draw(Path::new("sh010/thumbnail.PNG")));
+        assert!(!GraphicsProtocol::Kitty.can_draw(Path::new("sh010/thumbnail.jpg")));
+        assert!(GraphicsProtocol::Sixel.can_draw(Path::new("sh010/thumbnail.six")));
+        assert!(!GraphicsProtocol::None.can_draw(Path::new("sh010/thumbnail.png")));
+    }
+}
@@ -0,0 +1,399 @@
+use std::{
+    collections::HashMap,
+    fs::{self, OpenOptions},
+    io::{self, Write},
+    path::PathBuf,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use color_eyre::eyre::WrapErr;
+use ratatui::{
+    buffer::Buffer,
+    layout::{Alignment, Rect},
+    style::Style,
+    symbols::border,
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, List, ListItem, ListState, StatefulWidget, Widget},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{action::Action, details, dialog::centered_rect, matcher::FuzzyMatcher, text};
+
+const HOUR: u64 = 60 * 60;
+const DAY: u64 = 24 * HOUR;
+const WEEK: u64 = 7 * DAY;
+
+/// A leaf item accepted at some point
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    /// Seconds since the Unix epoch
+    pub at: u64,
+    /// The full hierarchy path, e.g. `["man_vs_bee", "sequence_001", "sh020"]`
+    pub path: Vec<String>,
+}
+
+/// Every selection accepted, oldest first. Stored on disk as one JSON object per
+/// selection and line, like `{"at":1700000000,"path":["project","sequence","shot"]}`,
+/// so names can have a `/` in them.
+#[derive(Debug, Default)]
+pub struct History {
+    /// Where to append selections. When `None`, they only live in memory.
+    file: Option<PathBuf>,
+    entries: Vec<HistoryEntry>,
+}
+
+impl History {
+    /// Reads the history from the given file. A missing file just means nothing
+    /// was accepted yet, and lines that can't be parsed are skipped.
+    pub fn load(file: Option<PathBuf>) -> color_eyre::Result<Self> {
+        let mut entries = Vec::new();
+
+        if let Some(file) = &file {
+            match fs::read_to_string(file) {
+                Ok(contents) => entries = contents.lines().filter_map(parse_entry).collect(),
+                Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+                Err(e) => {
+                    return Err(e).wrap_err_with(|| format!("failed to read history from {file:?}"))
+                }
+            }
+        }
+
+        Ok(Self { file, entries })
+    }
+
+    pub fn entries(&self) -> &[HistoryEntry] {
+        &self.entries
+    }
+
+    /// Takes note that the item at `path` was accepted just now
+    pub fn record(&mut self, path: &[String]) -> color_eyre::Result<()> {
+        let entry = HistoryEntry {
+            at: now(),
+            path: path.to_vec(),
+        };
+        let line = format!("{}\n", serde_json::to_string(&entry)?);
+        self.entries.push(entry);
+
+        let Some(file) = &self.file else {
+            return Ok(());
+        };
+        if let Some(parent) = file.parent() {
+            fs::create_dir_all(parent)
+                .wrap_err_with(|| format!("failed to create directory {parent:?}"))?;
+        }
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(file)
+            .and_then(|mut f| f.write_all(line.as_bytes()))
+            .wrap_err_with(|| format!("failed to write history to {file:?}"))
+    }
+
+    /// The `limit` paths accepted the most, and the most lately, best first.
+    /// Each selection counts for less as it gets older.
+    pub fn frecent(&self, limit: usize, now: u64) -> Vec<Vec<String>> {
+        let mut scores: HashMap<&[String], (u64, u64)> = HashMap::new();
+        for entry in &self.entries {
+            let (score, last) = scores.entry(&entry.path).or_default();
+            *score += match now.saturating_sub(entry.at) {
+                age if age < HOUR => 16,
+                age if age < DAY => 8,
+                age if age < WEEK => 4,
+                _ => 1,
+            };
+            *last = (*last).max(entry.at);
+        }
+
+        let mut paths: Vec<_> = scores.into_iter().collect();
+        paths.sort_by(|(_, a), (_, b)| b.cmp(a));
+        paths
+            .into_iter()
+            .take(limit)
+            .map(|(path, _)| path.to_vec())
+            .collect()
+    }
+}
+
+/// What the user did with the history browser, see [`HistoryBrowser::handle_action`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BrowserOutcome {
+    /// The browser stays open
+    Pending,
+    Cancelled,
+    /// Close the browser and accept the item at this path again
+    Accept(Vec<String>),
+}
+
+/// A modal popup listing every selection accepted, newest first, fuzzy-searching
+/// through when and what they were. While it's open, it should receive every action.
+#[derive(Debug, Clone)]
+pub struct HistoryBrowser {
+    /// Every entry of the history, newest first
+    entries: Vec<HistoryEntry>,
+    /// The row shown for each entry, which is also what the query matches
+    labels: Vec<String>,
+    query: String,
+    /// Indices into `entries` of the ones matching the query, best first
+    matches: Vec<usize>,
+    list_state: ListState,
+    /// Style of the highlighted entry
+    highlight: Style,
+    /// What the border is drawn with
+    borders: border::Set,
+}
+
+impl HistoryBrowser {
+    pub fn new(history: &History, matcher: &mut FuzzyMatcher, highlight: Style) -> Self {
+        let entries: Vec<HistoryEntry> = history.entries().iter().rev().cloned().collect();
+        let labels = entries
+            .iter()
+            .map(|entry| {
+                let at = UNIX_EPOCH + Duration::from_secs(entry.at);
+                format!("{}  {}", details::format_time(at), entry.path.join("/"))
+            })
+            .collect();
+        let mut browser = Self {
+            entries,
+            labels,
+            query: String::new(),
+            matches: Vec::new(),
+            list_state: ListState::default(),
+            highlight,
+            borders: border::PLAIN,
+        };
+        browser.update_matches(matcher);
+        browser
+    }
+
+    pub fn with_borders(mut self, borders: border::Set) -> Self {
+        self.borders = borders;
+        self
+    }
+
+    /// The path of the highlighted entry, if any matches
+    pub fn highlighted(&self) -> Option<&[String]> {
+        let index = self.list_state.selected()?;
+        self.matches
+            .get(index)
+            .map(|i| self.entries[*i].path.as_slice())
+    }
+
+    pub fn handle_action(&mut self, action: Action, matcher: &mut FuzzyMatcher) -> BrowserOutcome {
+        match action {
+            Action::Back | Action::ShowHistory => return BrowserOutcome::Cancelled,
+            Action::Accept => {
+                return match self.highlighted() {
+                    Some(path) => BrowserOutcome::Accept(path.to_vec()),
+                    None => BrowserOutcome::Pending,
+                }
+            }
+            Action::MoveUp => self.move_highlight(-1),
+            Action::MoveDown => self.move_highlight(1),
+            Action::Insert(c) => {
+                self.query.push(c);
+                self.update_matches(matcher);
+            }
+            Action::DeleteChar => {
+                text::pop_grapheme(&mut self.query);
+                self.update_matches(matcher);
+            }
+            Action::ClearQuery => {
+                self.query.clear();
+                self.update_matches(matcher);
+            }
+            _ => {}
+        }
+
+        BrowserOutcome::Pending
+    }
+
+    /// Without a query, entries stay newest first
+    fn update_matches(&mut self, matcher: &mut FuzzyMatcher) {
+        self.matches = if self.query.is_empty() {
+            (0..self.entries.len()).collect()
+        } else {
+            matcher
+                .match_names(&self.query, self.labels.iter().map(String::as_str))
+                .into_iter()
+                .map(|(index, _score)| index)
+                .collect()
+        };
+
+        let selected = if self.matches.is_empty() {
+            None
+        } else {
+            Some(0)
+        };
+        self.list_state = ListState::default().with_selected(selected);
+    }
+
+    fn move_highlight(&mut self, delta: isize) {
+        let Some(selected) = self.list_state.selected() else {
+            return;
+        };
+        let last = self.matches.len().saturating_sub(1);
+        self.list_state
+            .select(Some(selected.saturating_add_signed(delta).min(last)));
+    }
+}
+
+impl Widget for &HistoryBrowser {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let height = area.height.saturating_sub(4);
+        let area = centered_rect(area.width.saturating_sub(8).min(80), height, area);
+
+        let title = format!(" History ({}/{}) ", self.matches.len(), self.entries.len());
+        let block = Block::default()
+            .title(title)
+            .title_alignment(Alignment::Center)
+            .borders(Borders::ALL)
+            .border_set(self.borders);
+        let inner = block.inner(area);
+
+        Clear.render(area, buf);
+        block.render(area, buf);
+
+        if inner.height == 0 {
+            return;
+        }
+        let query_area = Rect { height: 1, ..inner };
+        let list_area = Rect {
+            y: inner.y + 1,
+            height: inner.height - 1,
+            ..inner
+        };
+
+        Line::from(vec!["> ".into(), Span::raw(self.query.as_str())]).render(query_area, buf);
+
+        let items: Vec<ListItem> = self
+            .matches
+            .iter()
+            .map(|i| ListItem::new(self.labels[*i].as_str()))
+            .collect();
+        let mut list_state = self.list_state.clone();
+        StatefulWidget::render(
+            List::new(items).highlight_style(self.highlight),
+            list_area,
+            buf,
+            &mut list_state,
+        );
+    }
+}
+
+/// Seconds since the Unix epoch
+pub fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs())
+}
+
+fn parse_entry(line: &str) -> Option<HistoryEntry> {
+    serde_json::from_str(line)
+        .ok()
+        .filter(|entry: &HistoryEntry| !entry.path.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_history_roundtrip() -> color_eyre::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let file = dir.path().join("nested").join("history.jsonl");
+
+        let mut history = History::load(Some(file.clone()))?;
+        history.record(&["man_vs_bee".into(), "sequence_001".into(), "sh020".into()])?;
+        history.record(&["project_001".into(), "a/b".into()])?;
+
+        let history = History::load(Some(file))?;
+        let paths: Vec<&[String]> = history
+            .entries()
+            .iter()
+            .map(|e| e.path.as_slice())
+            .collect();
+        assert_eq!(
+            paths,
+            [
+                ["man_vs_bee", "sequence_001", "sh020"]
+                    .map(String::from)
+                    .as_slice(),
+                ["project_001", "a/b"].map(String::from).as_slice(),
+            ]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_frecent() -> color_eyre::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let file = dir.path().join("history.jsonl");
+        let now = 100 * WEEK;
+        let old = now - 2 * WEEK;
+        let line = |at: u64, path: &str| {
+            format!("{{\"at\":{at},\"path\":[\"project\",\"seq\",\"{path}\"]}}\n")
+        };
+        let contents = [
+            line(old, "often"),
+            line(old, "often"),
+            line(old, "often"),
+            "not a line\n".to_string(),
+            line(now - DAY - 1, "yesterday"),
+            line(now - 60, "today"),
+        ]
+        .concat();
+        fs::write(&file, contents)?;
+
+        let history = History::load(Some(file))?;
+        let names: Vec<String> = history
+            .frecent(2, now)
+            .into_iter()
+            .map(|path| path.join("/"))
+            .collect();
+        assert_eq!(names, ["project/seq/today", "project/seq/yesterday"]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_browse_history() -> color_eyre::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let file = dir.path().join("history.jsonl");
+        fs::write(
+            &file,
+            "{\"at\":1700000000,\"path\":[\"man_vs_bee\",\"sequence_001\",\"sh020\"]}\n\
+             {\"at\":1700086400,\"path\":[\"project_001\",\"seq\",\"sh010\"]}\n",
+        )?;
+        let history = History::load(Some(file))?;
+        let mut matcher = FuzzyMatcher::default();
+        let mut browser = HistoryBrowser::new(&history, &mut matcher, Style::new());
+
+        // Newest first
+        let newest = ["project_001", "seq", "sh010"].map(String::from);
+        assert_eq!(browser.highlighted(), Some(newest.as_slice()));
+
+        for c in "bee".chars() {
+            browser.handle_action(Action::Insert(c), &mut matcher);
+        }
+        let oldest = ["man_vs_bee", "sequence_001", "sh020"].map(String::from);
+        assert_eq!(
+            browser.handle_action(Action::Accept, &mut matcher),
+            BrowserOutcome::Accept(oldest.to_vec())
+        );
+
+        // When they were is matched too
+        browser.handle_action(Action::ClearQuery, &mut matcher);
+        for c in "11-15".chars() {
+            browser.handle_action(Action::Insert(c), &mut matcher);
+        }
+        assert_eq!(browser.highlighted(), Some(newest.as_slice()));
+        assert_eq!(
+            browser.handle_action(Action::Back, &mut matcher),
+            BrowserOutcome::Cancelled
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,176 @@
+use ratatui::{
+    buffer::Buffer,
+    layout::{Alignment, Rect},
+    style::Style,
+    symbols::border,
+    widgets::{Block, Borders, Clear, List, ListItem, ListState, StatefulWidget, Widget},
+};
+
+use crate::{action::Action, dialog::centered_rect, text};
+
+/// Something that can be done to a single item, from the [`ItemMenu`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MenuEntry {
+    CopyPath,
+    Open,
+    TogglePin,
+    ShowDetails,
+    /// A command from the config, run like `--exec`
+    Run {
+        label: String,
+        command: String,
+    },
+}
+
+impl MenuEntry {
+    pub fn label(&self) -> &str {
+        match self {
+            MenuEntry::CopyPath => "Copy path",
+            MenuEntry::Open => "Open",
+            MenuEntry::TogglePin => "Pin/unpin",
+            MenuEntry::ShowDetails => "Show details",
+            MenuEntry::Run { label, .. } => label,
+        }
+    }
+}
+
+/// What the user did with the menu, see [`ItemMenu::handle_action`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MenuOutcome {
+    /// The menu stays open
+    Pending,
+    Cancelled,
+    /// Close the menu and do this to the item
+    Run(MenuEntry),
+}
+
+/// A small modal popup of what can be done to the highlighted item.
+/// While it's open, it should receive every action.
+#[derive(Debug, Clone)]
+pub struct ItemMenu {
+    /// Name of the item the menu is for
+    name: String,
+    entries: Vec<MenuEntry>,
+    list_state: ListState,
+    /// Style of the highlighted entry
+    highlight: Style,
+    /// What the border is drawn with
+    borders: border::Set,
+}
+
+impl ItemMenu {
+    /// The built-in entries, followed by `commands` given as `(label, command)`
+    pub fn new(name: impl Into<String>, commands: &[(String, String)], highlight: Style) -> Self {
+        let mut entries = vec![
+            MenuEntry::CopyPath,
+            MenuEntry::Open,
+            MenuEntry::TogglePin,
+            MenuEntry::ShowDetails,
+        ];
+        entries.extend(commands.iter().map(|(label, command)| MenuEntry::Run {
+            label: label.clone(),
+            command: command.clone(),
+        }));
+
+        Self {
+            name: name.into(),
+            entries,
+            list_state: ListState::default().with_selected(Some(0)),
+            highlight,
+            borders: border::PLAIN,
+        }
+    }
+
+    pub fn with_borders(mut self, borders: border::Set) -> Self {
+        self.borders = borders;
+        self
+    }
+
+    pub fn handle_action(&mut self, action: Action) -> MenuOutcome {
+        match action {
+            Action::Back | Action::OpenItemMenu => return MenuOutcome::Cancelled,
+            Action::Accept => {
+                let index = self.list_state.selected().unwrap_or_default();
+                return MenuOutcome::Run(self.entries[index].clone());
+            }
+            Action::MoveUp | Action::FocusPrevious => self.move_highlight(-1),
+            Action::MoveDown | Action::FocusNext => self.move_highlight(1),
+            _ => {}
+        }
+
+        MenuOutcome::Pending
+    }
+
+    fn move_highlight(&mut self, delta: isize) {
+        let selected = self.list_state.selected().unwrap_or_default();
+        let last = self.entries.len() - 1;
+        self.list_state
+            .select(Some(selected.saturating_add_signed(delta).min(last)));
+    }
+}
+
+impl Widget for &ItemMenu {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let title = format!(" {} ", self.name);
+        let widest = self
+            .entries
+            .iter()
+            .map(|entry| text::width(entry.label()))
+            .chain([text::width(&title)])
+            .max()
+            .unwrap_or_default();
+        let width = widest as u16 + 4;
+        let height = self.entries.len() as u16 + 2;
+        let area = centered_rect(width, height, area);
+
+        let block = Block::default()
+            .title(title)
+            .title_alignment(Alignment::Center)
+            .borders(Borders::ALL)
+            .border_set(self.borders);
+        let items: Vec<ListItem> = self
+            .entries
+            .iter()
+            .map(|entry| ListItem::new(format!(" {}", entry.label())))
+            .collect();
+
+        Clear.render(area, buf);
+        let mut list_state = self.list_state.clone();
+        StatefulWidget::render(
+            List::new(items)
+                .block(block)
+                .highlight_style(self.highlight),
+            area,
+            buf,
+            &mut list_state,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_menu_lists_configured_commands() {
+        let commands = [("Queue render".to_string(), "queue {path}".to_string())];
+        let mut menu = ItemMenu::new("sh010", &commands, Style::new());
+        assert_eq!(
+            menu.handle_action(Action::Accept),
+            MenuOutcome::Run(MenuEntry::CopyPath)
+        );
+
+        for _ in 0..10 {
+            menu.handle_action(Action::MoveDown);
+        }
+        assert_eq!(
+            menu.handle_action(Action::Accept),
+            MenuOutcome::Run(MenuEntry::Run {
+                label: "Queue render".to_string(),
+                command: "queue {path}".to_string()
+            })
+        );
+        assert_eq!(menu.handle_action(Action::Back), MenuOutcome::Cancelled);
+    }
+}
@@ -0,0 +1,168 @@
+use crate::action::Action;
+
+/// The keys hints are made of, home row first
+const HINT_KEYS: &str = "asdfghjklqwertyuiopzxcvbnm";
+
+/// What the user did while picking a hint, see [`Jump::handle_action`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JumpOutcome {
+    /// Waiting for more of a hint to be typed
+    Pending,
+    Cancelled,
+    /// Move the highlight to the result at this index
+    Jump(usize),
+}
+
+/// Short hints laid over the visible results, typed to highlight one of them
+/// right away, like avy or easymotion. While it's on, it should receive every action.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Jump {
+    /// Each hint and the index of the result it jumps to
+    hints: Vec<(String, usize)>,
+    typed: String,
+}
+
+impl Jump {
+    /// Gives each of `targets` a hint, all of the same length: a single key
+    /// when they fit, two otherwise
+    pub fn new(targets: impl IntoIterator<Item = usize>) -> Self {
+        let targets: Vec<usize> = targets.into_iter().collect();
+        let keys: Vec<char> = HINT_KEYS.chars().collect();
+        let hints = if targets.len() <= keys.len() {
+            keys.iter().map(|key| key.to_string()).collect::<Vec<_>>()
+        } else {
+            keys.iter()
+                .flat_map(|first| keys.iter().map(move |second| format!("{first}{second}")))
+                .collect()
+        };
+
+        Self {
+            hints: hints.into_iter().zip(targets).collect(),
+            typed: String::new(),
+        }
+    }
+
+    /// The hint of the result at `index`, if it still matches what was typed.
+    /// Returns the part typed so far and the rest of the hint.
+    pub fn hint_for(&self, index: usize) -> Option<(&str, &str)> {
+        let (hint, _) = self.hints.iter().find(|(_, target)| *target == index)?;
+        let rest = hint.strip_prefix(self.typed.as_str())?;
+        Some((&self.typed, rest))
+    }
+
+    pub fn handle_action(&mut self, action: Action) -> JumpOutcome {
+        match action {
+            Action::Insert(c) => {
+                self.typed.push(c.to_ascii_lowercase());
+                if let Some((_, target)) = self.hints.iter().find(|(hint, _)| *hint == self.typed) {
+                    return JumpOutcome::Jump(*target);
+                }
+                if self
+                    .hints
+                    .iter()
+                    .any(|(hint, _)| hint.starts_with(&self.typed))
+                {
+                    JumpOutcome::Pending
+                } else {
+                    JumpOutcome::Cancelled
+                }
+            }
+            Action::DeleteChar if !self.typed.is_empty() => {
+                self.typed.pop();
+                JumpOutcome::Pending
+            }
+            _ => JumpOutcome::Cancelled,
+        }
+    }
+}
+
+/// A result number being typed after `:`, to highlight that result
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GoTo {
+    digits: String,
+}
+
+impl GoTo {
+    /// The number typed so far
+    pub fn digits(&self) -> &str {
+        &self.digits
+    }
+
+    /// Handles an action. Results are numbered from 1, so this jumps to the
+    /// index before the number typed.
+    pub fn handle_action(&mut self, action: Action) -> JumpOutcome {
+        match action {
+            Action::Insert(c) if c.is_ascii_digit() => {
+                self.digits.push(c);
+                JumpOutcome::Pending
+            }
+            Action::DeleteChar if !self.digits.is_empty() => {
+                self.digits.pop();
+                JumpOutcome::Pending
+            }
+            // A stray key while typing the number is ignored
+            Action::Insert(_) => JumpOutcome::Pending,
+            Action::Accept => match self.digits.parse::<usize>() {
+                Ok(number) if number > 0 => JumpOutcome::Jump(number - 1),
+                _ => JumpOutcome::Cancelled,
+            },
+            _ => JumpOutcome::Cancelled,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_hints() {
+        let mut jump = Jump::new(10..13);
+        assert_eq!(jump.hint_for(11), Some(("", "s")));
+        assert_eq!(
+            jump.handle_action(Action::Insert('D')),
+            JumpOutcome::Jump(12)
+        );
+
+        let mut jump = Jump::new(0..40);
+        assert_eq!(jump.hint_for(27), Some(("", "ss")));
+        assert_eq!(
+            jump.handle_action(Action::Insert('s')),
+            JumpOutcome::Pending
+        );
+        assert_eq!(jump.hint_for(0), None);
+        assert_eq!(jump.hint_for(26), Some(("s", "a")));
+        assert_eq!(jump.handle_action(Action::DeleteChar), JumpOutcome::Pending);
+        assert_eq!(
+            jump.handle_action(Action::Insert('a')),
+            JumpOutcome::Pending
+        );
+        assert_eq!(
+            jump.handle_action(Action::Insert('a')),
+            JumpOutcome::Jump(0)
+        );
+
+        let mut jump = Jump::new(0..3);
+        assert_eq!(
+            jump.handle_action(Action::Insert('z')),
+            JumpOutcome::Cancelled
+        );
+    }
+
+    #[test]
+    fn test_go_to() {
+        let mut go_to = GoTo::default();
+        for c in "120x".chars() {
+            assert_eq!(go_to.handle_action(Action::Insert(c)), JumpOutcome::Pending);
+        }
+        go_to.handle_action(Action::DeleteChar);
+        assert_eq!(go_to.digits(), "12");
+        assert_eq!(go_to.handle_action(Action::Accept), JumpOutcome::Jump(11));
+
+        let mut go_to = GoTo::default();
+        assert_eq!(go_to.handle_action(Action::Accept), JumpOutcome::Cancelled);
+        assert_eq!(go_to.handle_action(Action::Back), JumpOutcome::Cancelled);
+        assert_eq!(go_to.handle_action(Action::Quit), JumpOutcome::Cancelled);
+    }
+}
@@ -0,0 +1,354 @@
+use std::collections::HashMap;
+
+use crossterm::event::{
+    Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers, MouseEvent, MouseEventKind,
+};
+
+use crate::action::{Action, InputEvent};
+
+/// Key bindings taking precedence over the default ones, e.g. from the config file
+#[derive(Debug, Clone, Default)]
+pub struct Keymap {
+    bindings: HashMap<(KeyCode, KeyModifiers), Action>,
+}
+
+impl Keymap {
+    /// Binds a key written like `ctrl-r`, `alt-enter` or `f5` to `action`.
+    /// Chords like `ctrl-enter` or `shift-enter` only work in terminals speaking the
+    /// kitty keyboard protocol, the others send them as plain keys.
+    pub fn bind(&mut self, key: &str, action: Action) -> Result<(), String> {
+        let (code, modifiers) = parse_key(key)?;
+        self.bindings.insert(normalize(code, modifiers), action);
+        Ok(())
+    }
+
+    /// The action bound to a key press, if any
+    pub fn action_for(&self, key_event: KeyEvent) -> Option<Action> {
+        let key = normalize(key_event.code, key_event.modifiers);
+        match self.bindings.get(&key) {
+            Some(action) => Some(*action),
+            None => action_for(key_event),
+        }
+    }
+
+    /// Turns a terminal event into an input for the app, if it means anything to it
+    pub fn translate(&self, event: Event) -> Option<InputEvent> {
+        match event {
+            // It's important to check that the event is a key press event as
+            // crossterm also emits key release and repeat events on Windows.
+            Event::Key(key_event) if key_event.kind == KeyEventKind::Press => {
+                self.action_for(key_event).map(InputEvent::Action)
+            }
+            Event::Paste(text) => Some(InputEvent::Paste(text)),
+            // Only sent with --mouse
+            Event::Mouse(MouseEvent {
+                kind: MouseEventKind::ScrollUp,
+                ..
+            }) => Some(InputEvent::Action(Action::MoveUp)),
+            Event::Mouse(MouseEvent {
+                kind: MouseEventKind::ScrollDown,
+                ..
+            }) => Some(InputEvent::Action(Action::MoveDown)),
+            _ => None,
+        }
+    }
+}
+
+/// Turns a terminal event into an input for the app with the default bindings
+pub fn translate(event: Event) -> Option<InputEvent> {
+    Keymap::default().translate(event)
+}
+
+/// Parses keys written like `ctrl-r`, `alt-1`, `shift-up`, `f10` or `esc`
+pub fn parse_key(key: &str) -> Result<(KeyCode, KeyModifiers), String> {
+    let mut modifiers = KeyModifiers::NONE;
+    let mut rest = key;
+    // A trailing `-` is the key itself, as in `ctrl--`
+    while let Some((modifier, after)) = rest.split_once('-').filter(|(_, after)| !after.is_empty())
+    {
+        modifiers |= match modifier.to_ascii_lowercase().as_str() {
+            "ctrl" => KeyModifiers::CONTROL,
+            "alt" => KeyModifiers::ALT,
+            "shift" => KeyModifiers::SHIFT,
+            _ => return Err(format!("unknown modifier {modifier:?} in key {key:?}")),
+        };
+        rest = after;
+    }
+
+    let mut chars = rest.chars();
+    let code = match (chars.next(), chars.next()) {
+        (Some(c), None) => KeyCode::Char(c),
+        _ => match rest.to_ascii_lowercase().as_str() {
+            "enter" => KeyCode::Enter,
+            "esc" => KeyCode::Esc,
+            "tab" => KeyCode::Tab,
+            "backtab" => KeyCode::BackTab,
+            "backspace" => KeyCode::Backspace,
+            "delete" => KeyCode::Delete,
+            "space" => KeyCode::Char(' '),
+            "up" => KeyCode::Up,
+            "down" => KeyCode::Down,
+            "left" => KeyCode::Left,
+            "right" => KeyCode::Right,
+            "home" => KeyCode::Home,
+            "end" => KeyCode::End,
+            "pageup" => KeyCode::PageUp,
+            "pagedown" => KeyCode::PageDown,
+            name => match name.strip_prefix('f').and_then(|n| n.parse().ok()) {
+                Some(number @ 1..=12) => KeyCode::F(number),
+                _ => return Err(format!("unknown key {rest:?} in {key:?}")),
+            },
+        },
+    };
+
+    Ok((code, modifiers))
+}
+
+/// Terminals disagree on whether shifted characters come with the Shift modifier,
+/// so it's ignored for them: `A` is `A` either way
+fn normalize(code: KeyCode, modifiers: KeyModifiers) -> (KeyCode, KeyModifiers) {
+    match code {
+        KeyCode::Char(_) => (code, modifiers - KeyModifiers::SHIFT),
+        _ => (code, modifiers),
+    }
+}
+
+/// The character typed with AltGr, which Windows reports as Ctrl+Alt, e.g.
+/// AltGr+Q typing `@` on a German keyboard. Ctrl+Alt+letter is left alone.
+fn altgr_char(key_event: KeyEvent) -> Option<char> {
+    let altgr = KeyModifiers::CONTROL | KeyModifiers::ALT;
+    match key_event.code {
+        KeyCode::Char(c) if key_event.modifiers.contains(altgr) && !c.is_ascii_alphanumeric() => {
+            Some(c)
+        }
+        _ => None,
+    }
+}
+
+/// The action bound to a key press, if any
+pub fn action_for(key_event: KeyEvent) -> Option<Action> {
+    let shift = key_event.modifiers.contains(KeyModifiers::SHIFT);
+
+    if let Some(c) = altgr_char(key_event).filter(|_| cfg!(windows)) {
+        return Some(Action::Insert(c));
+    }
+
+    if key_event.modifiers.contains(KeyModifiers::CONTROL) {
+        let action = match key_event.code {
+            KeyCode::Char('z') if !shift => Action::Undo,
+            // Terminals report Ctrl+Shift+Z either way
+            KeyCode::Char('z') | KeyCode::Char('Z') | KeyCode::Char('y') => Action::Redo,
+            KeyCode::Char('c') | KeyCode::Char('q') => Action::Quit,
+            KeyCode::Char('u') => Action::ClearQuery,
+            KeyCode::Char('f') => Action::CycleStatusFilter,
+            KeyCode::Char('s') => Action::CycleSort,
+            KeyCode::Char('b') => Action::TogglePin,
+            KeyCode::Char('a') => Action::MarkAll,
+            KeyCode::Char('d') => Action::UnmarkAll,
+            KeyCode::Char('t') => Action::InvertMarks,
+            KeyCode::Char('l') => Action::ToggleMarksPanel,
+            KeyCode::Char('o') => Action::OpenExternally,
+            KeyCode::Char('e') => Action::ShowDetails,
+            KeyCode::Char('x') => Action::ToggleArchived,
+            KeyCode::Char('g') => Action::GoToTop,
+            KeyCode::Char('j') => Action::Jump,
+            KeyCode::Char('r') => Action::Reload,
+            KeyCode::Char('p') | KeyCode::Char('P') => Action::OpenPalette,
+            // What terminals sending ^H for Backspace look like
+            KeyCode::Char('h') => Action::DeleteChar,
+            KeyCode::Left => Action::PreviousTab,
+            KeyCode::Right => Action::NextTab,
+            // Only told apart from Enter with the kitty keyboard protocol
+            KeyCode::Enter => Action::Accept,
+            // Most terminals send Ctrl+/ as Ctrl+7
+            KeyCode::Char('/') | KeyCode::Char('7') => Action::ToggleMatchMode,
+            _ => return None,
+        };
+        return Some(action);
+    }
+
+    // Plain digits go to the query, so tabs are picked with Alt+1, Alt+2...
+    if key_event.modifiers.contains(KeyModifiers::ALT) {
+        return match key_event.code {
+            KeyCode::Char(c @ '1'..='9') => Some(Action::SelectTab(c as usize - '1' as usize)),
+            KeyCode::Enter => Some(Action::OpenItemMenu),
+            KeyCode::Char('e') => Some(Action::ExportCsv),
+            KeyCode::Char('s') => Some(Action::SelectText),
+            KeyCode::Char('h') => Some(Action::ShowHistory),
+            KeyCode::Char('q') => Some(Action::RecordMacro),
+            KeyCode::Char('@') => Some(Action::ReplayMacro),
+            _ => None,
+        };
+    }
+
+    let action = match key_event.code {
+        KeyCode::Up if shift => Action::ScrollPreviewUp,
+        KeyCode::Down if shift => Action::ScrollPreviewDown,
+        KeyCode::Esc => Action::Back,
+        KeyCode::Enter => Action::Accept,
+        KeyCode::Up => Action::MoveUp,
+        KeyCode::Down => Action::MoveDown,
+        KeyCode::Left => Action::ScrollLeft,
+        KeyCode::Right => Action::ScrollRight,
+        KeyCode::F(10) => Action::ToggleDebug,
+        KeyCode::Tab => Action::FocusNext,
+        KeyCode::BackTab => Action::FocusPrevious,
+        KeyCode::Backspace => Action::DeleteChar,
+        KeyCode::Char(c) => Action::Insert(c),
+        _ => return None,
+    };
+    Some(action)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_action_for_keys() {
+        let ctrl = |c| KeyEvent::new(KeyCode::Char(c), KeyModifiers::CONTROL);
+        assert_eq!(action_for(ctrl('z')), Some(Action::Undo));
+        assert_eq!(
+            action_for(KeyEvent::new(
+                KeyCode::Char('Z'),
+                KeyModifiers::CONTROL | KeyModifiers::SHIFT
+            )),
+            Some(Action::Redo)
+        );
+        assert_eq!(action_for(ctrl('7')), Some(Action::ToggleMatchMode));
+        assert_eq!(action_for(ctrl('c')), Some(Action::Quit));
+        assert_eq!(action_for(ctrl('q')), Some(Action::Quit));
+        assert_eq!(action_for(ctrl('w')), None);
+        assert_eq!(action_for(ctrl('r')), Some(Action::Reload));
+        assert_eq!(action_for(ctrl('t')), Some(Action::InvertMarks));
+        assert_eq!(action_for(ctrl('P')), Some(Action::OpenPalette));
+        assert_eq!(action_for(ctrl('h')), Some(Action::DeleteChar));
+        assert_eq!(
+            action_for(KeyEvent::new(KeyCode::Char('h'), KeyModifiers::ALT)),
+            Some(Action::ShowHistory)
+        );
+        assert_eq!(
+            action_for(KeyEvent::new(KeyCode::Right, KeyModifiers::CONTROL)),
+            Some(Action::NextTab)
+        );
+        assert_eq!(
+            action_for(KeyEvent::new(KeyCode::Char('2'), KeyModifiers::ALT)),
+            Some(Action::SelectTab(1))
+        );
+
+        assert_eq!(
+            action_for(KeyEvent::new(KeyCode::Down, KeyModifiers::SHIFT)),
+            Some(Action::ScrollPreviewDown)
+        );
+        assert_eq!(action_for(KeyCode::Down.into()), Some(Action::MoveDown));
+        assert_eq!(
+            action_for(KeyCode::Char('Q').into()),
+            Some(Action::Insert('Q'))
+        );
+        assert_eq!(
+            action_for(KeyCode::Char('q').into()),
+            Some(Action::Insert('q'))
+        );
+        assert_eq!(action_for(KeyCode::F(1).into()), None);
+        assert_eq!(action_for(KeyCode::F(10).into()), Some(Action::ToggleDebug));
+    }
+
+    #[test]
+    fn test_altgr_char() {
+        let altgr = KeyModifiers::CONTROL | KeyModifiers::ALT;
+        assert_eq!(
+            altgr_char(KeyEvent::new(KeyCode::Char('@'), altgr)),
+            Some('@')
+        );
+        assert_eq!(
+            altgr_char(KeyEvent::new(
+                KeyCode::Char('€'),
+                altgr | KeyModifiers::SHIFT
+            )),
+            Some('€')
+        );
+        assert_eq!(altgr_char(KeyEvent::new(KeyCode::Char('x'), altgr)), None);
+        assert_eq!(
+            altgr_char(KeyEvent::new(KeyCode::Char('/'), KeyModifiers::CONTROL)),
+            None
+        );
+    }
+
+    #[test]
+    fn test_custom_bindings() -> Result<(), String> {
+        assert_eq!(
+            parse_key("ctrl-alt-x")?,
+            (
+                KeyCode::Char('x'),
+                KeyModifiers::CONTROL | KeyModifiers::ALT
+            )
+        );
+        assert_eq!(
+            parse_key("ctrl--")?,
+            (KeyCode::Char('-'), KeyModifiers::CONTROL)
+        );
+        assert_eq!(parse_key("F5")?, (KeyCode::F(5), KeyModifiers::NONE));
+        assert!(parse_key("hyper-x").is_err());
+        assert!(parse_key("f13").is_err());
+
+        let mut keymap = Keymap::default();
+        keymap.bind("ctrl-o", Action::TogglePreview)?;
+        keymap.bind("ctrl-f", Action::Reload)?;
+        keymap.bind("Q", Action::Quit)?;
+        let ctrl = |c| KeyEvent::new(KeyCode::Char(c), KeyModifiers::CONTROL);
+        assert_eq!(keymap.action_for(ctrl('o')), Some(Action::TogglePreview));
+        assert_eq!(keymap.action_for(ctrl('f')), Some(Action::Reload));
+        assert_eq!(keymap.action_for(ctrl('s')), Some(Action::CycleSort));
+        assert_eq!(
+            keymap.action_for(KeyEvent::new(KeyCode::Char('Q'), KeyModifiers::SHIFT)),
+            Some(Action::Quit)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_kitty_chords() -> Result<(), String> {
+        let mut keymap = Keymap::default();
+        keymap.bind("shift-enter", Action::OpenItemMenu)?;
+        keymap.bind("ctrl-i", Action::ToggleMarksPanel)?;
+
+        let shift_enter = KeyEvent::new(KeyCode::Enter, KeyModifiers::SHIFT);
+        assert_eq!(keymap.action_for(shift_enter), Some(Action::OpenItemMenu));
+        let ctrl_i = KeyEvent::new(KeyCode::Char('i'), KeyModifiers::CONTROL);
+        assert_eq!(keymap.action_for(ctrl_i), Some(Action::ToggleMarksPanel));
+        assert_eq!(
+            keymap.action_for(KeyCode::Tab.into()),
+            Some(Action::FocusNext)
+        );
+        // Unbound chords do what the plain key does
+        let ctrl_enter = KeyEvent::new(KeyCode::Enter, KeyModifiers::CONTROL);
+        assert_eq!(keymap.action_for(ctrl_enter), Some(Action::Accept));
+        Ok(())
+    }
+
+    #[test]
+    fn test_translate_ignores_key_releases() {
+        let mut release = KeyEvent::from(KeyCode::Enter);
+        release.kind = KeyEventKind::Release;
+        assert_eq!(translate(Event::Key(release)), None);
+        assert_eq!(
+            translate(Event::Key(KeyCode::Enter.into())),
+            Some(InputEvent::Action(Action::Accept))
+        );
+        assert_eq!(
+            translate(Event::Paste("sh010".to_string())),
+            Some(InputEvent::Paste("sh010".to_string()))
+        );
+        let scroll = MouseEvent {
+            kind: MouseEventKind::ScrollDown,
+            column: 0,
+            row: 0,
+            modifiers: KeyModifiers::NONE,
+        };
+        assert_eq!(
+            translate(Event::Mouse(scroll)),
+            Some(InputEvent::Action(Action::MoveDown))
+        );
+    }
+}
@@ -0,0 +1,10 @@
+/// What a level of the hierarchy remembers while a deeper one is shown,
+/// to be put back as it was when going back up
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LevelState {
+    pub query: String,
+    /// Name of the highlighted item, which may have moved when coming back
+    pub highlighted: Option<String>,
+    /// How far the results list was scrolled
+    pub offset: usize,
+}
@@ -0,0 +1,53 @@
+//! Fuzzy-find your way through projects, sequences and shots.
+//!
+//! The binary is a thin wrapper around [`app::App`], which can also be driven
+//! headlessly with [`app::App::run_scripted`], e.g. from other tools' tests.
+
+pub mod action;
+pub mod ansi;
+pub mod app;
+pub mod auth;
+pub mod backend;
+pub mod batch;
+pub mod cli;
+pub mod config;
+pub mod debug;
+pub mod details;
+pub mod dialog;
+pub mod doctor;
+pub mod errors;
+pub mod export;
+pub mod fields;
+pub mod finder;
+pub mod graphics;
+pub mod history;
+pub mod item_menu;
+pub mod jump;
+pub mod keymap;
+pub mod levels;
+pub mod logging;
+pub mod macros;
+pub mod marks;
+pub mod matcher;
+pub mod opener;
+pub mod palette;
+pub mod pins;
+pub mod preview;
+pub mod query;
+pub mod rate_limit;
+pub mod repeat;
+pub mod retry;
+pub mod rpc;
+#[cfg(unix)]
+pub mod server;
+pub mod session;
+pub mod shell;
+pub mod sort;
+pub mod state;
+pub mod tabs;
+pub mod text;
+pub mod theme;
+pub mod transcript;
+pub mod tui;
+pub mod undo;
+pub mod watch;
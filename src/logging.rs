@@ -0,0 +1,82 @@
+use std::{fmt, fs::File, path::Path, sync::Mutex};
+
+use color_eyre::{eyre::WrapErr, owo_colors::OwoColorize};
+use tracing::{Event, Level, Subscriber};
+use tracing_subscriber::{
+    filter::{EnvFilter, LevelFilter, Targets},
+    fmt::{
+        format::{FmtSpan, Writer},
+        FmtContext, FormatEvent, FormatFields,
+    },
+    layer::SubscriberExt,
+    registry::LookupSpan,
+    util::SubscriberInitExt,
+    Layer,
+};
+
+/// Logs to stderr at the level set by `RUST_LOG` (info by default or when it's invalid)
+/// and, with a `trace_file`, every span and event as a line of JSON in it,
+/// with how long spans took when they close
+pub fn init(trace_file: Option<&Path>) -> color_eyre::Result<()> {
+    let stderr = tracing_subscriber::fmt::layer()
+        .with_writer(std::io::stderr)
+        .event_format(PipeFormat)
+        .with_filter(EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info")));
+
+    let trace = match trace_file {
+        Some(path) => {
+            let file = File::create(path)
+                .wrap_err_with(|| format!("failed to create the trace file {path:?}"))?;
+            let layer = tracing_subscriber::fmt::layer()
+                .json()
+                .with_span_events(FmtSpan::CLOSE)
+                .with_writer(Mutex::new(file))
+                // Our spans are at the debug level, not to bother anyone else
+                .with_filter(
+                    Targets::new()
+                        .with_default(LevelFilter::INFO)
+                        .with_target("cgs_go", LevelFilter::DEBUG),
+                );
+            Some(layer)
+        }
+        None => None,
+    };
+
+    tracing_subscriber::registry()
+        .with(stderr)
+        .with(trace)
+        .try_init()?;
+    Ok(())
+}
+
+/// Writes events as `| LEVEL | message`, the whole line colored by level
+struct PipeFormat;
+
+impl<S, N> FormatEvent<S, N> for PipeFormat
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+    N: for<'a> FormatFields<'a> + 'static,
+{
+    fn format_event(
+        &self,
+        ctx: &FmtContext<'_, S, N>,
+        mut writer: Writer<'_>,
+        event: &Event<'_>,
+    ) -> fmt::Result {
+        let style = match *event.metadata().level() {
+            Level::INFO => color_eyre::owo_colors::Style::new().blue(),
+            Level::WARN => color_eyre::owo_colors::Style::new().yellow(),
+            Level::ERROR => color_eyre::owo_colors::Style::new().red().bold(),
+            _ => color_eyre::owo_colors::Style::new().white(),
+        };
+
+        let mut message = String::new();
+        ctx.format_fields(Writer::new(&mut message), event)?;
+        writeln!(
+            writer,
+            "| {} | {}",
+            event.metadata().level().style(style),
+            message.style(style)
+        )
+    }
+}
@@ -0,0 +1,77 @@
+use crate::action::{Action, InputEvent};
+
+/// Records inputs to play them again later, like vim's `qq` and `@q`
+#[derive(Debug, Default)]
+pub struct MacroRecorder {
+    /// What was input since recording started, while recording
+    recording: Option<Vec<InputEvent>>,
+    /// The last macro recorded
+    recorded: Vec<InputEvent>,
+}
+
+impl MacroRecorder {
+    pub fn is_recording(&self) -> bool {
+        self.recording.is_some()
+    }
+
+    /// Starts recording, or stops and keeps what was recorded as the macro to replay.
+    /// Returns whether it's recording now.
+    pub fn toggle(&mut self) -> bool {
+        match self.recording.take() {
+            Some(inputs) => {
+                self.recorded = inputs;
+                false
+            }
+            None => {
+                self.recording = Some(Vec::new());
+                true
+            }
+        }
+    }
+
+    /// Adds `input` to the macro being recorded, if any. Starting, stopping and
+    /// replaying macros isn't recorded.
+    pub fn record(&mut self, input: &InputEvent) {
+        if let (Some(inputs), false) = (&mut self.recording, is_macro_action(input)) {
+            inputs.push(input.clone());
+        }
+    }
+
+    /// The inputs of the last macro recorded, to handle them again
+    pub fn recorded(&self) -> Vec<InputEvent> {
+        self.recorded.clone()
+    }
+}
+
+fn is_macro_action(input: &InputEvent) -> bool {
+    matches!(
+        input,
+        InputEvent::Action(Action::RecordMacro | Action::ReplayMacro)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_record_and_replay() {
+        let mut recorder = MacroRecorder::default();
+        recorder.record(&InputEvent::Action(Action::MoveDown));
+        assert!(recorder.toggle());
+        recorder.record(&InputEvent::Action(Action::Accept));
+        recorder.record(&InputEvent::Action(Action::ReplayMacro));
+        recorder.record(&InputEvent::Paste("lighting".into()));
+        assert!(recorder.is_recording());
+        assert!(!recorder.toggle());
+
+        assert_eq!(
+            recorder.recorded(),
+            vec![
+                InputEvent::Action(Action::Accept),
+                InputEvent::Paste("lighting".into())
+            ]
+        );
+    }
+}
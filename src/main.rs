@@ -1,57 +1,319 @@
-use std::io::Write;
+use std::path::Path;
 
-use color_eyre::owo_colors::OwoColorize;
-use crossterm::{
-    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
-    ExecutableCommand,
-};
+use color_eyre::eyre::WrapErr;
 
-mod app;
-mod errors;
-mod tui;
+use cgs_go::{
+    app, auth, backend, cli, config, errors, fields, graphics, logging, matcher, repeat, session,
+    state, tui,
+};
 
 fn main() -> color_eyre::Result<()> {
+    let mut cli = cli::Cli::parse_with_default_opts()?;
+    match cli.command {
+        Some(cli::Command::Completions { shell }) => {
+            cli::write_completions(shell, &mut std::io::stdout());
+            return Ok(());
+        }
+        Some(cli::Command::Init { shell }) => {
+            print!("{}", shell.init_script());
+            return Ok(());
+        }
+        Some(cli::Command::Doctor) => {
+            if !cgs_go::doctor::run(&cli, std::io::stdout().lock())? {
+                std::process::exit(1);
+            }
+            return Ok(());
+        }
+        None => {}
+    }
+    // Kept to apply the config again when it changes
+    let flags = cli.clone();
+
+    let tui_options = tui::Options {
+        alternate_screen: !cli.no_alt_screen,
+        mouse: cli.mouse,
+        ..tui::Options::default()
+    };
+
     // Install the error handlers by 'eyre'
-    errors::install_hooks()?;
-
-    // Enable LOG info by default if the caller didn't provide any overrides
-    // or if it set the RUST_LOG env var incorrectly
-    if std::env::var("RUST_LOG").is_err() {
-        std::env::set_var("RUST_LOG", "info");
-    }
-
-    // Initialize the logging library
-    env_logger::builder()
-        // Customize the formatting to offer full color per line
-        .format(|buf, record| {
-            let style = match record.level() {
-                log::Level::Info => color_eyre::owo_colors::Style::new().blue(),
-                log::Level::Warn => color_eyre::owo_colors::Style::new().yellow(),
-                log::Level::Error => color_eyre::owo_colors::Style::new().red().bold(),
-                _ => color_eyre::owo_colors::Style::new().white(),
-            };
-
-            writeln!(
-                buf,
-                "| {} | {}",
-                record.level().style(style),
-                record.args().style(style)
-            )
-        })
-        .init();
-
-    log::info!("Entering RAW mode..");
-
-    std::io::stdout().execute(EnterAlternateScreen)?;
-    enable_raw_mode()?;
-
-    let mut terminal = tui::init()?;
-    let app_result = app::App::default().run(&mut terminal)?;
-    log::info!("App result: {app_result:?}");
-    log::info!("Exiting cleanly...");
-
-    std::io::stdout().execute(LeaveAlternateScreen)?;
-    disable_raw_mode()?;
+    errors::install_hooks(tui_options)?;
+
+    // Flags win over the config file and environment variables
+    let config = config::Config::load(cli.profile.as_deref())?;
+    config.apply_to(&mut cli);
+
+    logging::init(cli.trace_file.as_deref())?;
+
+    // Each source of items has a history of its own, named after it
+    let (backend, source): (Box<dyn backend::Backend>, String) = match (&cli.root, cli.demo) {
+        (Some(root), _) => {
+            let excludes = backend::Excludes::load(root, &cli.exclude)?;
+            let mut backend = backend::FsBackend::new(root).with_excludes(excludes);
+            if cli.follow_symlinks {
+                backend = backend.with_follow_symlinks(cli.keep_symlink_cycles);
+            }
+            let source = root.canonicalize().unwrap_or_else(|_| root.clone());
+            let source = source.display().to_string();
+            match cli.walk_depth {
+                Some(depth) => (Box::new(backend.with_walk_depth(depth)), source),
+                None => (Box::new(backend), source),
+            }
+        }
+        (None, Some(size)) => {
+            let seed = cli.seed.unwrap_or_default();
+            let backend = backend::SyntheticBackend::new(size, seed);
+            (Box::new(backend), format!("demo {size} {seed}"))
+        }
+        (None, None) => match &cli.items_command {
+            Some(command) => (
+                Box::new(backend::StdinBackend::from_command(
+                    command,
+                    cli.line_options(),
+                )?),
+                format!("command {command}"),
+            ),
+            // Piped lines are matched while they're still coming, like fzf does
+            None if !cli.rpc && cli.stdin_piped => {
+                let stdin = std::io::BufReader::new(std::io::stdin());
+                let backend = backend::StdinBackend::streaming(stdin, cli.line_options());
+                (Box::new(backend), "stdin".to_string())
+            }
+            None => match std::env::var(cli::DEFAULT_COMMAND_VAR) {
+                Ok(command) if !cli.rpc => (
+                    Box::new(backend::StdinBackend::from_command(
+                        &command,
+                        cli.line_options(),
+                    )?),
+                    format!("command {command}"),
+                ),
+                _ => (Box::new(backend::MockBackend), "mock".to_string()),
+            },
+        },
+    };
+    let mut backend = match cli.root.as_deref().and_then(state::listings_cache_dir) {
+        Some(dir) if cli.offline_cache => Box::new(backend::CachedBackend::new(backend, dir)),
+        _ => backend,
+    };
+    if let Some(source) = cli.token_source() {
+        backend.authenticate(auth::Auth::new(source));
+    }
+
+    if cli.print_location && backend.location(&[]).is_none() {
+        color_eyre::eyre::bail!("--print-location needs a backend living on disk, see --root");
+    }
+
+    // Tools driving the finder without the app expect every item to be there
+    if cli.rpc || cli.batch.is_some() {
+        while backend.streaming(&[]).is_some() {
+            std::thread::sleep(std::time::Duration::from_millis(10));
+        }
+    }
+
+    if cli.rpc {
+        let stdin = std::io::stdin().lock();
+        return cgs_go::rpc::run(backend.as_ref(), cli.normalize, stdin, std::io::stdout());
+    }
+    #[cfg(unix)]
+    if let Some(socket_path) = &cli.serve {
+        return cgs_go::server::serve(socket_path, backend.as_ref(), cli.normalize);
+    }
+    #[cfg(not(unix))]
+    if cli.serve.is_some() {
+        color_eyre::eyre::bail!("--serve needs Unix domain sockets");
+    }
+
+    let fields = cli
+        .nth
+        .as_deref()
+        .map(|nth| fields::FieldSelector::parse(nth, cli.delimiter.as_deref()))
+        .transpose()
+        .map_err(color_eyre::eyre::Error::msg)?;
+
+    let last_context_file = state::last_context_file();
+    let start_path = match (&last_context_file, cli.resume) {
+        (Some(file), true) => session::load_last_context(file)?,
+        _ => cli.project.iter().chain(&cli.sequence).cloned().collect(),
+    };
+
+    if let Some(file) = &cli.batch {
+        let queries = std::fs::File::open(file)
+            .wrap_err_with(|| format!("failed to open the queries in {file:?}"))?;
+        return cgs_go::batch::run(
+            backend.as_ref(),
+            cli.normalize,
+            start_path,
+            std::io::BufReader::new(queries),
+            cli.batch_top,
+            std::io::stdout().lock(),
+        );
+    }
+
+    let options = app::Options {
+        unicode: !cli.no_unicode,
+        ellipsis: cli.ellipsis(),
+        pointer: cli.pointer(),
+        marker: cli.marker(),
+        normalize: cli.normalize,
+        typos: cli.typos,
+        bonuses: config.bonuses(),
+        fields,
+        match_mode: if cli.regex {
+            matcher::MatchMode::Regex
+        } else {
+            matcher::MatchMode::Fuzzy
+        },
+        sort: cli.sort.unwrap_or_default(),
+        start_at: cli.start_at.unwrap_or_default(),
+        show_archived: cli.show_archived,
+        pins_file: state::pins_file(),
+        history_file: state::history_file(&source),
+        export_file: cli
+            .export_csv
+            .clone()
+            .unwrap_or_else(|| "results.csv".into()),
+        start_path,
+        query: cli.query.clone().unwrap_or_default(),
+        preview: cli.preview.clone(),
+        thumbnails: cli.thumbnails,
+        link_targets: cli.show_link_targets,
+        show_timings: cli.show_timings,
+        tabs: cli.tabs,
+        group: cli.group,
+        scroll_off: cli.scroll_off.unwrap_or(0),
+        acceleration: Some(repeat::Acceleration::new(
+            cli.accelerate_after.unwrap_or(repeat::DEFAULT_AFTER),
+            cli.accelerate_step.unwrap_or(repeat::DEFAULT_STEP),
+        )),
+        max_results: cli.max_results,
+        watch: cli.watch,
+        rate_limit: cli.rate_limit,
+        multi: cli.multi,
+        keep_open: cli.keep_open,
+        record: cli.record.clone(),
+        replay: cli.replay.clone(),
+        replay_speed: cli.replay_speed,
+        exec: cli.exec.clone(),
+        tui: tui_options,
+        item_commands: config.item_commands(),
+        theme: config
+            .theme(cli.theme.unwrap_or_default())?
+            .with_depth(cli.color_depth()),
+        keymap: config.keymap()?,
+        config: Some(config::ConfigSource {
+            file: config::config_file(),
+            flags,
+        }),
+        graphics: graphics::GraphicsProtocol::detect(),
+    };
+    let mut app = app::App::new(backend, options)?;
+
+    // Some answers don't need the app to be shown
+    let outcome = if cli.exit_0 && app.count_all_matches()? == 0 {
+        tracing::info!("Nothing matches, exiting");
+        Some(app::Outcome::NoMatch)
+    } else if cli.select_1 {
+        app.select_only_match()?
+            .map(|selection| app::Outcome::Accepted(vec![selection]))
+    } else {
+        None
+    };
+
+    let outcome = match outcome {
+        Some(outcome) => outcome,
+        None => {
+            tracing::info!("Entering RAW mode..");
+
+            let mut terminal = tui::init(tui_options)?;
+            let outcome = app.run(&mut terminal);
+
+            // Without the alternate screen, the app would stay drawn above the output
+            if !tui_options.alternate_screen {
+                terminal.clear()?;
+            }
+            // Errors are reported on the restored terminal too
+            tui::restore(tui_options)?;
+            let outcome = outcome?;
+            tracing::info!("App outcome: {outcome:?}");
+            tracing::info!("Exiting cleanly...");
+            outcome
+        }
+    };
+
+    if let app::Outcome::Accepted(selections) = &outcome {
+        finish(&cli, &app, selections, last_context_file.as_deref())?;
+    }
+    match outcome.exit_code() {
+        0 => Ok(()),
+        code => std::process::exit(code),
+    }
+}
+
+/// Remembers where the selection was made and hands it over, one item per line
+fn finish(
+    cli: &cli::Cli,
+    app: &app::App,
+    selections: &[app::Selection],
+    last_context_file: Option<&Path>,
+) -> color_eyre::Result<()> {
+    let context: Vec<&str> = selections
+        .last()
+        .map_or(&[][..], app::Selection::context)
+        .iter()
+        .map(String::as_str)
+        .collect();
+    if let (Some(file), false) = (last_context_file, context.is_empty()) {
+        if let Err(e) = session::save_last_context(file, &context) {
+            tracing::warn!("Could not remember the last context: {e:#}");
+        }
+    }
+    // They were handed to --exec one by one already
+    if cli.exec.is_some() {
+        return Ok(());
+    }
+
+    let mut output = String::new();
+    for selection in selections {
+        let location = app.location(selection);
+        match cli.output.unwrap_or_default() {
+            cli::OutputFormat::Json => {
+                let mut json = serde_json::to_value(selection)?;
+                json["location"] = serde_json::to_value(location)?;
+                output.push_str(&json.to_string());
+            }
+            cli::OutputFormat::Text => match location {
+                Some(location) if cli.print_location => {
+                    output.push_str(&location.to_string_lossy());
+                }
+                _ => output.push_str(&selection.to_string()),
+            },
+        }
+        output.push('\n');
+    }
+    match (&cli.output_file, cli.output_fd) {
+        (Some(file), _) => std::fs::write(file, output)
+            .wrap_err_with(|| format!("failed to write the selection to {file:?}"))?,
+        (None, Some(fd)) => write_to_fd(fd, &output)?,
+        (None, None) => print!("{output}"),
+    }
 
     Ok(())
 }
+
+/// Writes to a file descriptor the caller left open for us, going through
+/// `/dev/fd` so it's opened like any other file
+#[cfg(unix)]
+fn write_to_fd(fd: u32, output: &str) -> color_eyre::Result<()> {
+    use std::io::Write;
+
+    let mut file = std::fs::OpenOptions::new()
+        .append(true)
+        .open(format!("/dev/fd/{fd}"))
+        .wrap_err_with(|| format!("file descriptor {fd} isn't open for writing"))?;
+    file.write_all(output.as_bytes())
+        .wrap_err_with(|| format!("failed to write the selection to file descriptor {fd}"))
+}
+
+#[cfg(not(unix))]
+fn write_to_fd(_fd: u32, _output: &str) -> color_eyre::Result<()> {
+    color_eyre::eyre::bail!("--output-fd needs a Unix system")
+}
@@ -0,0 +1,259 @@
+use std::collections::BTreeMap;
+
+use ratatui::{
+    buffer::Buffer,
+    layout::{Alignment, Rect},
+    style::{Color, Modifier, Style},
+    symbols::border,
+    text::{Line, Span},
+    widgets::{
+        block::{Position, Title},
+        Block, Borders, Clear, List, ListItem, ListState, StatefulWidget, Widget,
+    },
+};
+
+use crate::{action::Action, app::Selection};
+
+/// The leaf items marked in multi-select mode, by their full path in the hierarchy.
+/// They stay marked when the query or the level changes, so a batch can be
+/// picked from several places before accepting it. Each keeps the score and
+/// metadata it had when it was marked, to be accepted with them.
+#[derive(Debug, Default)]
+pub struct Marks {
+    selections: BTreeMap<Vec<String>, Selection>,
+    /// Bumped on every change, to tell when what's drawn of them is outdated
+    revision: u64,
+}
+
+impl Marks {
+    pub fn len(&self) -> usize {
+        self.selections.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.selections.is_empty()
+    }
+
+    pub fn contains(&self, path: &[String]) -> bool {
+        self.selections.contains_key(path)
+    }
+
+    pub fn revision(&self) -> u64 {
+        self.revision
+    }
+
+    pub fn mark(&mut self, selection: Selection) {
+        self.revision += 1;
+        self.selections.insert(selection.path.clone(), selection);
+    }
+
+    pub fn unmark(&mut self, path: &[String]) {
+        self.revision += 1;
+        self.selections.remove(path);
+    }
+
+    /// Marks the item if it wasn't, unmarks it otherwise.
+    /// Returns whether the item is now marked.
+    pub fn toggle(&mut self, selection: Selection) -> bool {
+        self.revision += 1;
+        if self.selections.remove(&selection.path).is_some() {
+            false
+        } else {
+            self.selections.insert(selection.path.clone(), selection);
+            true
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.revision += 1;
+        self.selections.clear();
+    }
+
+    /// The marked paths, grouped by the level they were marked at
+    pub fn iter(&self) -> impl Iterator<Item = &[String]> {
+        self.selections.keys().map(Vec::as_slice)
+    }
+
+    /// The marked items, in the same order as [`Marks::iter`]
+    pub fn selections(&self) -> impl Iterator<Item = &Selection> {
+        self.selections.values()
+    }
+}
+
+/// What the user did with the marks panel, see [`MarksPanel::handle_action`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PanelOutcome {
+    /// The panel stays open
+    Pending,
+    Closed,
+    /// Close the panel and accept everything marked
+    Accept,
+}
+
+/// A side panel listing everything marked, wherever it was marked,
+/// so entries can be unmarked before accepting the batch.
+/// While it's open, it should receive every action.
+#[derive(Debug, Clone)]
+pub struct MarksPanel {
+    list_state: ListState,
+    /// Style of the highlighted entry
+    highlight: Style,
+    /// What the border is drawn with
+    borders: border::Set,
+    /// Style of the keys in the hints
+    key_style: Style,
+}
+
+impl MarksPanel {
+    pub fn new(marks: &Marks, highlight: Style) -> Self {
+        let selected = (!marks.is_empty()).then_some(0);
+        Self {
+            list_state: ListState::default().with_selected(selected),
+            highlight,
+            borders: border::PLAIN,
+            key_style: Style::new().fg(Color::Blue).add_modifier(Modifier::BOLD),
+        }
+    }
+
+    pub fn with_key_style(mut self, key_style: Style) -> Self {
+        self.key_style = key_style;
+        self
+    }
+
+    pub fn with_borders(mut self, borders: border::Set) -> Self {
+        self.borders = borders;
+        self
+    }
+
+    pub fn handle_action(&mut self, action: Action, marks: &mut Marks) -> PanelOutcome {
+        match action {
+            Action::Back | Action::ToggleMarksPanel => return PanelOutcome::Closed,
+            Action::Accept => return PanelOutcome::Accept,
+            Action::MoveUp => self.move_highlight(-1, marks),
+            Action::MoveDown => self.move_highlight(1, marks),
+            Action::ToggleMark | Action::FocusNext | Action::DeleteChar => {
+                let highlighted = self.list_state.selected().and_then(|i| marks.iter().nth(i));
+                if let Some(path) = highlighted.map(<[String]>::to_vec) {
+                    marks.unmark(&path);
+                }
+                self.move_highlight(0, marks);
+            }
+            Action::UnmarkAll => {
+                marks.clear();
+                self.move_highlight(0, marks);
+            }
+            _ => {}
+        }
+
+        PanelOutcome::Pending
+    }
+
+    /// Moves the highlight, keeping it on an entry as they get unmarked
+    fn move_highlight(&mut self, delta: isize, marks: &Marks) {
+        let selected = match marks.len() {
+            0 => None,
+            len => {
+                let selected = self.list_state.selected().unwrap_or(0);
+                Some(selected.saturating_add_signed(delta).min(len - 1))
+            }
+        };
+        self.list_state.select(selected);
+    }
+
+    pub fn render(&mut self, marks: &Marks, area: Rect, buf: &mut Buffer) {
+        let instructions = Line::from(vec![
+            " Unmark ".into(),
+            Span::styled("<Tab>", self.key_style),
+            " Accept ".into(),
+            Span::styled("<Enter>", self.key_style),
+            " Close ".into(),
+            Span::styled("<Esc> ", self.key_style),
+        ]);
+        let block = Block::default()
+            .title(format!(" Marked ({}) ", marks.len()))
+            .title(
+                Title::from(instructions)
+                    .alignment(Alignment::Center)
+                    .position(Position::Bottom),
+            )
+            .borders(Borders::ALL)
+            .border_set(self.borders);
+
+        let items: Vec<ListItem> = marks
+            .iter()
+            .map(|path| ListItem::new(path.join(" / ")))
+            .collect();
+
+        Clear.render(area, buf);
+        StatefulWidget::render(
+            List::new(items)
+                .block(block)
+                .highlight_style(self.highlight),
+            area,
+            buf,
+            &mut self.list_state,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_toggle_marks() {
+        let shot = |name: &str| vec!["man_vs_bee".to_string(), name.to_string()];
+        let selection = |name: &str| Selection {
+            path: shot(name),
+            score: Some(10),
+            ..Selection::default()
+        };
+        let mut marks = Marks::default();
+        assert!(marks.toggle(selection("sh020")));
+        assert!(marks.toggle(selection("sh010")));
+        assert!(!marks.toggle(selection("sh020")));
+        marks.mark(selection("sh030"));
+
+        assert_eq!(marks.len(), 2);
+        assert!(marks.contains(&shot("sh010")));
+        assert_eq!(
+            marks.iter().collect::<Vec<_>>(),
+            vec![shot("sh010").as_slice(), shot("sh030").as_slice()]
+        );
+        assert_eq!(marks.selections().next(), Some(&selection("sh010")));
+    }
+
+    #[test]
+    fn test_unmark_from_panel() {
+        let mut marks = Marks::default();
+        for name in ["sh010", "sh020", "sh030"] {
+            marks.mark(Selection {
+                path: vec!["man_vs_bee".to_string(), name.to_string()],
+                ..Selection::default()
+            });
+        }
+
+        let mut panel = MarksPanel::new(&marks, Style::new());
+        panel.handle_action(Action::MoveDown, &mut marks);
+        panel.handle_action(Action::MoveDown, &mut marks);
+        panel.handle_action(Action::ToggleMark, &mut marks);
+        // The highlight stays on the new last entry
+        panel.handle_action(Action::ToggleMark, &mut marks);
+        let left: Vec<String> = marks.iter().map(|path| path.join("/")).collect();
+        assert_eq!(left, ["man_vs_bee/sh010"]);
+
+        assert_eq!(
+            panel.handle_action(Action::Insert('x'), &mut marks),
+            PanelOutcome::Pending
+        );
+        assert_eq!(
+            panel.handle_action(Action::Accept, &mut marks),
+            PanelOutcome::Accept
+        );
+        assert_eq!(
+            panel.handle_action(Action::Back, &mut marks),
+            PanelOutcome::Closed
+        );
+    }
+}
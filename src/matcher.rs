@@ -0,0 +1,540 @@
+use std::{cmp::Reverse, collections::BinaryHeap};
+
+use nucleo::{
+    pattern::{CaseMatching, Normalization, Pattern},
+    Config, Matcher, Utf32Str,
+};
+use rayon::prelude::*;
+use regex::RegexBuilder;
+
+/// From this many names on, they're scored in parallel
+const PARALLEL_THRESHOLD: usize = 20_000;
+
+/// How many names each parallel job scores
+const PARALLEL_CHUNK_SIZE: usize = 8_192;
+
+/// Queries shorter than this are too short to tell a typo from another word
+const MIN_TYPO_QUERY_LEN: usize = 4;
+
+/// How the query text is matched against names
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum MatchMode {
+    /// Scored fuzzy matching, best match first
+    #[default]
+    Fuzzy,
+    /// Plain regex filtering, keeping the original order
+    Regex,
+}
+
+/// Returns the `(index, score)` of every name the regex finds a match in.
+/// All scores are 0, since a regex either matches or it doesn't.
+/// Like the fuzzy matcher, it's case insensitive unless there's an uppercase letter.
+pub fn match_regex<'a>(
+    pattern: &str,
+    names: impl IntoIterator<Item = &'a str>,
+) -> Result<Vec<(usize, u32)>, regex::Error> {
+    let regex = RegexBuilder::new(pattern)
+        .case_insensitive(!pattern.chars().any(char::is_uppercase))
+        .build()?;
+
+    Ok(names
+        .into_iter()
+        .enumerate()
+        .filter(|(_, name)| regex.is_match(name))
+        .map(|(index, _)| (index, 0))
+        .collect())
+}
+
+/// Points added to nucleo's score for where in a name the query matched, so
+/// `alb24` prefers `asset_library_2024` to `albedo_2024`. With both at 0,
+/// scores are nucleo's own, which are close to fzf's v2 algorithm.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Bonuses {
+    /// For each matched character starting a word: first in the name, or after
+    /// anything that's not a letter or a digit (`_`, `-`, `.`, a space...)
+    pub boundary: u32,
+    /// For each matched character where the case changes, or letters turn to
+    /// digits or back, like the `L` of `assetLibrary` or the `0` of `sh010`
+    pub camel: u32,
+    /// For each matched character in the last segment of a path, when matching
+    /// paths like `project/sequence/shot` (e.g. piped in), so shots named like the
+    /// query come before the projects merely containing it, like fzf does with files
+    pub leaf: u32,
+}
+
+impl Default for Bonuses {
+    fn default() -> Self {
+        Self {
+            boundary: 32,
+            camel: 8,
+            leaf: 16,
+        }
+    }
+}
+
+impl Bonuses {
+    /// The bonus for matching the characters at `indices` of `haystack`
+    fn score(&self, haystack: Utf32Str, indices: &[u32]) -> u32 {
+        // Bonuses are set in the config file, so they can be anything
+        let mut bonus: u32 = 0;
+        if self.leaf > 0 {
+            let leaf_start = (0..haystack.len() as u32)
+                .rev()
+                .find(|index| haystack.get(*index) == '/')
+                .map(|slash| slash + 1);
+            if let Some(leaf_start) = leaf_start {
+                let in_leaf = indices.iter().filter(|index| **index >= leaf_start).count();
+                bonus = bonus.saturating_add(self.leaf.saturating_mul(in_leaf as u32));
+            }
+        }
+        for &index in indices {
+            let current = haystack.get(index);
+            let Some(previous) = index.checked_sub(1).map(|index| haystack.get(index)) else {
+                bonus = bonus.saturating_add(self.boundary);
+                continue;
+            };
+            if !previous.is_alphanumeric() {
+                bonus = bonus.saturating_add(self.boundary);
+            } else if (previous.is_lowercase() && current.is_uppercase())
+                || previous.is_alphabetic() != current.is_alphabetic()
+            {
+                bonus = bonus.saturating_add(self.camel);
+            }
+        }
+        bonus
+    }
+
+    fn is_zero(&self) -> bool {
+        self.boundary == 0 && self.camel == 0 && self.leaf == 0
+    }
+}
+
+/// Scores a list of names against a fuzzy query
+pub struct FuzzyMatcher {
+    matcher: Matcher,
+    /// Used to give each parallel job a matcher of its own
+    config: Config,
+    normalization: Normalization,
+    /// Whether names one typo away from matching are kept too, see [`Self::with_typos`]
+    typos: bool,
+    bonuses: Bonuses,
+}
+
+impl Default for FuzzyMatcher {
+    fn default() -> Self {
+        Self::new(false)
+    }
+}
+
+impl FuzzyMatcher {
+    /// When `normalize` is set, accented latin characters are folded to ASCII
+    /// before scoring, so `eclair` matches `éclair`. Typing the accent in the
+    /// query still only matches the accented character.
+    pub fn new(normalize: bool) -> Self {
+        let mut config = Config::DEFAULT;
+        config.normalize = normalize;
+
+        let normalization = if normalize {
+            Normalization::Smart
+        } else {
+            Normalization::Never
+        };
+
+        Self {
+            matcher: Matcher::new(config.clone()),
+            config,
+            normalization,
+            typos: false,
+            bonuses: Bonuses::default(),
+        }
+    }
+
+    pub fn with_bonuses(mut self, bonuses: Bonuses) -> Self {
+        self.bonuses = bonuses;
+        self
+    }
+
+    /// Also keeps the names that would match if the query had one typo less (two
+    /// letters swapped, or one too many or wrong), like `porject` for `project`.
+    /// They come after all the other matches. It scores every name again for
+    /// each way of fixing the query, so it's off by default.
+    pub fn with_typos(mut self, typos: bool) -> Self {
+        self.typos = typos;
+        self
+    }
+
+    /// Returns the `(index, score)` of every name matching the query,
+    /// best score first. Ties keep the original order of the names.
+    pub fn match_names<'a>(
+        &mut self,
+        query: &str,
+        names: impl IntoIterator<Item = &'a str>,
+    ) -> Vec<(usize, u32)> {
+        self.match_top(query, names, None)
+    }
+
+    /// Like [`FuzzyMatcher::match_names`], but only keeps the best `limit` matches.
+    /// Those are picked without sorting all the others.
+    pub fn match_top<'a>(
+        &mut self,
+        query: &str,
+        names: impl IntoIterator<Item = &'a str>,
+        limit: Option<usize>,
+    ) -> Vec<(usize, u32)> {
+        let pattern = Pattern::parse(query, CaseMatching::Smart, self.normalization);
+
+        let names: Vec<&str> = names.into_iter().collect();
+
+        let mut matches = if names.len() < PARALLEL_THRESHOLD {
+            let matches = score_names(&pattern, &mut self.matcher, self.bonuses, &names, 0);
+            best_matches(matches, limit)
+        } else {
+            self.match_parallel(&pattern, &names, limit)
+        };
+
+        if self.typos && limit.is_none_or(|limit| matches.len() < limit) {
+            let typos = self.match_typos(query, &names, &matches);
+            let room = limit.map(|limit| limit - matches.len());
+            matches.extend(best_matches(typos, room));
+        }
+        matches
+    }
+
+    fn match_parallel(
+        &self,
+        pattern: &Pattern,
+        names: &[&str],
+        limit: Option<usize>,
+    ) -> Vec<(usize, u32)> {
+        // Big lists are split in chunks scored and sorted in parallel, then merged
+        let chunks: Vec<Vec<(usize, u32)>> = names
+            .par_chunks(PARALLEL_CHUNK_SIZE)
+            .enumerate()
+            .map_init(
+                || Matcher::new(self.config.clone()),
+                |matcher, (chunk_index, chunk)| {
+                    let offset = chunk_index * PARALLEL_CHUNK_SIZE;
+                    let matches = score_names(pattern, matcher, self.bonuses, chunk, offset);
+                    best_matches(matches, limit)
+                },
+            )
+            .collect();
+
+        merge_sorted(chunks, limit)
+    }
+
+    /// Scores the names that didn't match against every fix of a single typo in
+    /// the query. Their scores are halved and kept below the worst of `matches`.
+    fn match_typos(
+        &self,
+        query: &str,
+        names: &[&str],
+        matches: &[(usize, u32)],
+    ) -> Vec<(usize, u32)> {
+        // Queries with several words or special syntax aren't one word with a typo
+        let plain = query
+            .chars()
+            .all(|c| c.is_alphanumeric() || matches!(c, '_' | '-' | '.'));
+        if !plain || query.chars().count() < MIN_TYPO_QUERY_LEN {
+            return Vec::new();
+        }
+
+        let patterns: Vec<Pattern> = typo_fixes(query)
+            .iter()
+            .map(|fix| Pattern::parse(fix, CaseMatching::Smart, self.normalization))
+            .collect();
+        let mut matched = vec![false; names.len()];
+        for (index, _) in matches {
+            matched[*index] = true;
+        }
+        let ceiling = matches
+            .last()
+            .map_or(u32::MAX, |(_, score)| score.saturating_sub(1));
+
+        names
+            .par_iter()
+            .enumerate()
+            .filter(|(index, _)| !matched[*index])
+            .map_init(
+                || (Matcher::new(self.config.clone()), Vec::new()),
+                |(matcher, buf), (index, name)| {
+                    let score = patterns
+                        .iter()
+                        .filter_map(|pattern| pattern.score(Utf32Str::new(name, buf), matcher))
+                        .max()?;
+                    Some((index, (score / 2).min(ceiling)))
+                },
+            )
+            .flatten()
+            .collect()
+    }
+}
+
+/// The queries `query` could have been meant as, with one typo fixed: each pair
+/// of neighbouring characters swapped, and each character removed. Missing
+/// characters need no fixing, fuzzy matching skips over them already.
+fn typo_fixes(query: &str) -> Vec<String> {
+    let chars: Vec<char> = query.chars().collect();
+    let swapped = (1..chars.len()).map(|i| {
+        let mut fixed = chars.clone();
+        fixed.swap(i - 1, i);
+        fixed
+    });
+    let removed = (0..chars.len()).map(|i| {
+        let mut fixed = chars.clone();
+        fixed.remove(i);
+        fixed
+    });
+
+    let mut fixes: Vec<String> = swapped
+        .chain(removed)
+        .map(|fixed| fixed.into_iter().collect())
+        .collect();
+    fixes.sort();
+    fixes.dedup();
+    fixes
+}
+
+/// Returns the `(offset + index, score)` of every name matching the pattern, in order
+fn score_names(
+    pattern: &Pattern,
+    matcher: &mut Matcher,
+    bonuses: Bonuses,
+    names: &[&str],
+    offset: usize,
+) -> Vec<(usize, u32)> {
+    let mut buf = Vec::new();
+    let mut indices = Vec::new();
+    names
+        .iter()
+        .enumerate()
+        .filter_map(|(index, name)| {
+            let haystack = Utf32Str::new(name, &mut buf);
+            // Finding where it matched is slower, so only when the bonuses need it
+            if bonuses.is_zero() {
+                let score = pattern.score(haystack, matcher)?;
+                return Some((offset + index, score));
+            }
+            indices.clear();
+            let score = pattern.indices(haystack, matcher, &mut indices)?;
+            // Each word of the query reports its own
+            indices.sort_unstable();
+            indices.dedup();
+            Some((
+                offset + index,
+                score.saturating_add(bonuses.score(haystack, &indices)),
+            ))
+        })
+        .collect()
+}
+
+/// Sorts matches best score first, lowest index first on ties, keeping at most `limit`.
+/// With a limit, the best ones are selected first so the rest never gets sorted.
+fn best_matches(mut matches: Vec<(usize, u32)>, limit: Option<usize>) -> Vec<(usize, u32)> {
+    let key = |m: &(usize, u32)| (Reverse(m.1), m.0);
+    if let Some(limit) = limit {
+        if limit == 0 {
+            return Vec::new();
+        }
+        if limit < matches.len() {
+            matches.select_nth_unstable_by_key(limit - 1, key);
+            matches.truncate(limit);
+        }
+    }
+    matches.sort_unstable_by_key(key);
+
+    matches
+}
+
+/// Merges lists of matches sorted by score into one, still best score first and
+/// lowest index first on ties, stopping after `limit` matches.
+/// The heap only ever holds the head of each list.
+fn merge_sorted(lists: Vec<Vec<(usize, u32)>>, limit: Option<usize>) -> Vec<(usize, u32)> {
+    let total: usize = lists.iter().map(Vec::len).sum();
+    let limit = limit.unwrap_or(total).min(total);
+    let mut merged = Vec::with_capacity(limit);
+    let mut heap: BinaryHeap<(u32, Reverse<usize>, usize, usize)> = lists
+        .iter()
+        .enumerate()
+        .filter_map(|(list, matches)| {
+            let (index, score) = *matches.first()?;
+            Some((score, Reverse(index), list, 0))
+        })
+        .collect();
+
+    while merged.len() < limit {
+        let Some((score, Reverse(index), list, position)) = heap.pop() else {
+            break;
+        };
+        merged.push((index, score));
+        if let Some((next_index, next_score)) = lists[list].get(position + 1) {
+            heap.push((*next_score, Reverse(*next_index), list, position + 1));
+        }
+    }
+
+    merged
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_empty_query_matches_everything_in_order() {
+        let mut matcher = FuzzyMatcher::default();
+        let matches = matcher.match_names("", ["b", "a", "c"]);
+        let indices: Vec<usize> = matches.iter().map(|m| m.0).collect();
+        assert_eq!(indices, vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn test_best_match_first() {
+        let mut matcher = FuzzyMatcher::default();
+        let matches = matcher.match_names("bee", ["project_001", "bee_movie_2", "man_vs_bee"]);
+        assert_eq!(matches.len(), 2);
+        assert!(matches.iter().all(|m| m.0 != 0));
+    }
+
+    #[test]
+    fn test_parallel_scoring_matches_serial_order() {
+        let names: Vec<String> = (0..PARALLEL_THRESHOLD * 2)
+            .map(|i| format!("sh{:05}_comp_v{:03}", i, i % 7))
+            .collect();
+        let names: Vec<&str> = names.iter().map(String::as_str).collect();
+
+        let mut matcher = FuzzyMatcher::default();
+        let parallel = matcher.match_names("sh1comp3", names.iter().copied());
+        let top = matcher.match_top("sh1comp3", names.iter().copied(), Some(50));
+
+        let pattern = Pattern::parse("sh1comp3", CaseMatching::Smart, Normalization::Never);
+        let mut serial = score_names(
+            &pattern,
+            &mut Matcher::new(Config::DEFAULT),
+            Bonuses::default(),
+            &names,
+            0,
+        );
+        serial.sort_by_key(|m| Reverse(m.1));
+
+        assert!(parallel.len() > 50);
+        assert_eq!(parallel, serial);
+        assert_eq!(top, serial[..50]);
+    }
+
+    #[test]
+    fn test_merge_sorted() {
+        let lists = vec![vec![(0, 9), (1, 5)], vec![], vec![(2, 9), (3, 7)]];
+        assert_eq!(
+            merge_sorted(lists.clone(), None),
+            vec![(0, 9), (2, 9), (3, 7), (1, 5)]
+        );
+        assert_eq!(merge_sorted(lists, Some(3)), vec![(0, 9), (2, 9), (3, 7)]);
+    }
+
+    #[test]
+    fn test_match_top_keeps_the_best() {
+        let names = ["bee", "bee_movie_2", "man_vs_bee", "b_e_e", "project_001"];
+        let mut matcher = FuzzyMatcher::default();
+        let all = matcher.match_names("bee", names);
+        assert_eq!(matcher.match_top("bee", names, Some(2)), all[..2]);
+        assert_eq!(matcher.match_top("bee", names, Some(100)), all);
+        assert!(matcher.match_top("bee", names, Some(0)).is_empty());
+
+        assert_eq!(
+            best_matches(vec![(3, 1), (0, 5), (2, 5), (1, 9)], Some(3)),
+            vec![(1, 9), (0, 5), (2, 5)]
+        );
+    }
+
+    #[test]
+    fn test_regex_matching() {
+        let names = ["sh010", "sh020", "SH030"];
+        let matches = match_regex("^sh0[13]0$", names).unwrap();
+        assert_eq!(matches, vec![(0, 0), (2, 0)]);
+
+        assert!(match_regex("^SH", names).unwrap().len() == 1);
+        assert!(match_regex("sh(", names).is_err());
+    }
+
+    #[test]
+    fn test_normalization_is_opt_in() {
+        let names = ["éclair", "sequence_001"];
+
+        let mut matcher = FuzzyMatcher::default();
+        assert!(matcher.match_names("eclair", names).is_empty());
+
+        let mut matcher = FuzzyMatcher::new(true);
+        assert_eq!(
+            matcher.match_names("eclair", names),
+            vec![(0, matcher.match_names("éclair", names)[0].1)]
+        );
+        assert!(matcher.match_names("é", ["e"]).is_empty());
+    }
+
+    #[test]
+    fn test_typos() {
+        let names = ["project_001", "project_002", "porject_notes", "props"];
+
+        let mut matcher = FuzzyMatcher::default();
+        let matches = matcher.match_names("porject", names);
+        assert_eq!(matches.len(), 1);
+
+        let mut matcher = FuzzyMatcher::default().with_typos(true);
+        let matches = matcher.match_names("porject", names);
+        let indices: Vec<usize> = matches.iter().map(|m| m.0).collect();
+        // The exact match first, however good the others are
+        assert_eq!(indices, vec![2, 0, 1]);
+        assert!(matches[1].1 < matches[0].1);
+
+        // A wrong letter, or one too many
+        assert_eq!(matcher.match_names("projwct_002", names)[0].0, 1);
+        assert_eq!(matcher.match_names("projjject_002", names).len(), 0);
+        assert_eq!(matcher.match_top("porject", names, Some(2)).len(), 2);
+        // Too short to be sure
+        assert!(matcher.match_names("rpo", names).is_empty());
+    }
+
+    #[test]
+    fn test_typo_fixes() {
+        assert_eq!(typo_fixes("abc"), vec!["ab", "ac", "acb", "bac", "bc"]);
+    }
+
+    #[test]
+    fn test_word_boundary_bonuses() {
+        let names = ["albedo_2024", "calibration_b24", "asset_library_2024"];
+        let mut matcher = FuzzyMatcher::default();
+        assert_eq!(matcher.match_names("alb24", names)[0].0, 2);
+
+        let mut matcher = FuzzyMatcher::default().with_bonuses(Bonuses {
+            boundary: 0,
+            camel: 0,
+            leaf: 0,
+        });
+        assert_eq!(matcher.match_names("alb24", names)[0].0, 0);
+    }
+
+    #[test]
+    fn test_leaf_bonus() {
+        let paths = [
+            "sh010_tests/sequence_001/sh020",
+            "man_vs_bee/sequence_001/sh010",
+        ];
+        let mut matcher = FuzzyMatcher::default();
+        assert_eq!(matcher.match_names("sh010", paths)[0].0, 1);
+
+        let mut matcher = FuzzyMatcher::default().with_bonuses(Bonuses {
+            leaf: 0,
+            ..Bonuses::default()
+        });
+        assert_eq!(matcher.match_names("sh010", paths)[0].0, 0);
+
+        // Huge bonuses top out rather than overflow
+        let mut matcher = FuzzyMatcher::default().with_bonuses(Bonuses {
+            boundary: u32::MAX,
+            camel: u32::MAX,
+            leaf: u32::MAX,
+        });
+        assert_eq!(matcher.match_names("sh010", paths)[0].1, u32::MAX);
+    }
+}
@@ -0,0 +1,47 @@
+use std::{env, path::Path, process::Command};
+
+/// The editor the user asked for in `$VISUAL` or `$EDITOR`, if any
+pub fn editor() -> Option<String> {
+    ["VISUAL", "EDITOR"]
+        .into_iter()
+        .filter_map(|var| env::var(var).ok())
+        .find(|editor| !editor.trim().is_empty())
+}
+
+/// The command opening `location` in `editor`, or with the desktop's
+/// default application when there's no editor.
+/// The editor may come with arguments, like `code --wait`.
+pub fn open_command(location: &Path, editor: Option<&str>) -> Command {
+    let mut command = match editor {
+        Some(editor) => {
+            let mut command = Command::new("sh");
+            command.arg("-c").arg(format!("{editor} \"$1\"")).arg("sh");
+            command
+        }
+        None if cfg!(target_os = "macos") => Command::new("open"),
+        None => Command::new("xdg-open"),
+    };
+    command.arg(location);
+    command
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_open_command() {
+        let location = Path::new("/shows/man vs bee/sh010");
+        let command = open_command(location, Some("code --wait"));
+        assert_eq!(command.get_program(), "sh");
+        let args: Vec<_> = command.get_args().collect();
+        assert_eq!(
+            args,
+            ["-c", "code --wait \"$1\"", "sh", "/shows/man vs bee/sh010"]
+        );
+
+        let command = open_command(location, None);
+        assert_eq!(command.get_args().collect::<Vec<_>>(), [location]);
+    }
+}
@@ -0,0 +1,216 @@
+use ratatui::{
+    buffer::Buffer,
+    layout::{Alignment, Rect},
+    style::Style,
+    symbols::border,
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, List, ListItem, ListState, StatefulWidget, Widget},
+};
+
+use crate::{action::Action, dialog::centered_rect, matcher::FuzzyMatcher, text};
+
+/// The commands offered by the palette, with the action each one runs
+pub const COMMANDS: [(&str, Action); 25] = [
+    ("Toggle preview", Action::TogglePreview),
+    ("Open in editor", Action::OpenExternally),
+    ("Item actions", Action::OpenItemMenu),
+    ("Show item details", Action::ShowDetails),
+    ("Reload items", Action::Reload),
+    ("Export results to CSV", Action::ExportCsv),
+    ("Select text with the mouse", Action::SelectText),
+    ("Reload config", Action::ReloadConfig),
+    ("Change sort", Action::CycleSort),
+    ("Filter by status", Action::CycleStatusFilter),
+    ("Show/hide archived items", Action::ToggleArchived),
+    ("Toggle regex matching", Action::ToggleMatchMode),
+    ("Pin/unpin highlighted item", Action::TogglePin),
+    ("Mark all matches", Action::MarkAll),
+    ("Unmark everything", Action::UnmarkAll),
+    ("Invert marks", Action::InvertMarks),
+    ("Show/hide marked items", Action::ToggleMarksPanel),
+    ("Go to top level", Action::GoToTop),
+    ("Browse selection history", Action::ShowHistory),
+    ("Jump to a visible item", Action::Jump),
+    ("Next tab", Action::NextTab),
+    ("Clear query", Action::ClearQuery),
+    ("Undo query edit", Action::Undo),
+    ("Show/hide debug overlay", Action::ToggleDebug),
+    ("Quit", Action::Quit),
+];
+
+/// What the user did with the palette, see [`CommandPalette::handle_action`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PaletteOutcome {
+    /// The palette stays open
+    Pending,
+    Cancelled,
+    /// Close the palette and run this action
+    Run(Action),
+}
+
+/// A modal popup fuzzy-searching through [`COMMANDS`].
+/// While it's open, it should receive every action.
+#[derive(Debug, Clone)]
+pub struct CommandPalette {
+    query: String,
+    /// Indices into [`COMMANDS`] of the ones matching the query, best first
+    matches: Vec<usize>,
+    list_state: ListState,
+    /// Style of the highlighted command
+    highlight: Style,
+    /// What the border is drawn with
+    borders: border::Set,
+}
+
+impl CommandPalette {
+    pub fn new(matcher: &mut FuzzyMatcher, highlight: Style) -> Self {
+        let mut palette = Self {
+            query: String::new(),
+            matches: Vec::new(),
+            list_state: ListState::default(),
+            highlight,
+            borders: border::PLAIN,
+        };
+        palette.update_matches(matcher);
+        palette
+    }
+
+    pub fn with_borders(mut self, borders: border::Set) -> Self {
+        self.borders = borders;
+        self
+    }
+
+    /// The action of the highlighted command, if any matches
+    pub fn highlighted(&self) -> Option<Action> {
+        let index = self.list_state.selected()?;
+        self.matches.get(index).map(|i| COMMANDS[*i].1)
+    }
+
+    pub fn handle_action(&mut self, action: Action, matcher: &mut FuzzyMatcher) -> PaletteOutcome {
+        match action {
+            Action::Back | Action::OpenPalette => return PaletteOutcome::Cancelled,
+            Action::Accept => {
+                return match self.highlighted() {
+                    Some(action) => PaletteOutcome::Run(action),
+                    None => PaletteOutcome::Pending,
+                }
+            }
+            Action::MoveUp => self.move_highlight(-1),
+            Action::MoveDown => self.move_highlight(1),
+            Action::Insert(c) => {
+                self.query.push(c);
+                self.update_matches(matcher);
+            }
+            Action::DeleteChar => {
+                text::pop_grapheme(&mut self.query);
+                self.update_matches(matcher);
+            }
+            Action::ClearQuery => {
+                self.query.clear();
+                self.update_matches(matcher);
+            }
+            _ => {}
+        }
+
+        PaletteOutcome::Pending
+    }
+
+    fn update_matches(&mut self, matcher: &mut FuzzyMatcher) {
+        let labels = COMMANDS.iter().map(|(label, _)| *label);
+        self.matches = matcher
+            .match_names(&self.query, labels)
+            .into_iter()
+            .map(|(index, _score)| index)
+            .collect();
+
+        let selected = if self.matches.is_empty() {
+            None
+        } else {
+            Some(0)
+        };
+        self.list_state = ListState::default().with_selected(selected);
+    }
+
+    fn move_highlight(&mut self, delta: isize) {
+        let Some(selected) = self.list_state.selected() else {
+            return;
+        };
+        let last = self.matches.len().saturating_sub(1);
+        self.list_state
+            .select(Some(selected.saturating_add_signed(delta).min(last)));
+    }
+}
+
+impl Widget for &CommandPalette {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let height = COMMANDS.len() as u16 + 4;
+        let area = centered_rect(40, height, area);
+
+        let block = Block::default()
+            .title(" Commands ")
+            .title_alignment(Alignment::Center)
+            .borders(Borders::ALL)
+            .border_set(self.borders);
+        let inner = block.inner(area);
+
+        Clear.render(area, buf);
+        block.render(area, buf);
+
+        if inner.height == 0 {
+            return;
+        }
+        let query_area = Rect { height: 1, ..inner };
+        let list_area = Rect {
+            y: inner.y + 1,
+            height: inner.height - 1,
+            ..inner
+        };
+
+        Line::from(vec!["> ".into(), Span::raw(self.query.as_str())]).render(query_area, buf);
+
+        let items: Vec<ListItem> = self
+            .matches
+            .iter()
+            .map(|i| ListItem::new(COMMANDS[*i].0))
+            .collect();
+        let mut list_state = self.list_state.clone();
+        StatefulWidget::render(
+            List::new(items).highlight_style(self.highlight),
+            list_area,
+            buf,
+            &mut list_state,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_palette_fuzzy_finds_commands() {
+        let mut matcher = FuzzyMatcher::default();
+        let mut palette = CommandPalette::new(&mut matcher, Style::new());
+        assert_eq!(palette.highlighted(), Some(Action::TogglePreview));
+
+        for c in "sort".chars() {
+            palette.handle_action(Action::Insert(c), &mut matcher);
+        }
+        assert_eq!(
+            palette.handle_action(Action::Accept, &mut matcher),
+            PaletteOutcome::Run(Action::CycleSort)
+        );
+
+        palette.handle_action(Action::Insert('z'), &mut matcher);
+        assert_eq!(palette.highlighted(), None);
+        assert_eq!(
+            palette.handle_action(Action::Accept, &mut matcher),
+            PaletteOutcome::Pending
+        );
+        assert_eq!(
+            palette.handle_action(Action::Back, &mut matcher),
+            PaletteOutcome::Cancelled
+        );
+    }
+}
@@ -0,0 +1,96 @@
+use std::{collections::BTreeSet, fs, io, path::PathBuf};
+
+use color_eyre::eyre::WrapErr;
+
+/// The set of items the user pinned, identified by their full hierarchy path
+/// (e.g. `man_vs_bee/sequence_001`). Stored on disk as one path per line.
+#[derive(Debug, Default)]
+pub struct Pins {
+    /// Where to persist the pins. When `None`, they only live in memory.
+    file: Option<PathBuf>,
+    paths: BTreeSet<String>,
+}
+
+impl Pins {
+    /// Reads the pins from the given file. A missing file just means no pins yet.
+    pub fn load(file: Option<PathBuf>) -> color_eyre::Result<Self> {
+        let mut paths = BTreeSet::new();
+
+        if let Some(file) = &file {
+            match fs::read_to_string(file) {
+                Ok(contents) => {
+                    paths = contents
+                        .lines()
+                        .map(str::trim)
+                        .filter(|line| !line.is_empty())
+                        .map(String::from)
+                        .collect();
+                }
+                Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+                Err(e) => {
+                    return Err(e).wrap_err_with(|| format!("failed to read pins from {file:?}"))
+                }
+            }
+        }
+
+        Ok(Self { file, paths })
+    }
+
+    pub fn contains(&self, path: &str) -> bool {
+        self.paths.contains(path)
+    }
+
+    /// Pins the path if it wasn't, unpins it otherwise, and saves the result.
+    /// Returns whether the path is now pinned.
+    pub fn toggle(&mut self, path: String) -> color_eyre::Result<bool> {
+        let pinned = if self.paths.remove(&path) {
+            false
+        } else {
+            self.paths.insert(path);
+            true
+        };
+        self.save()?;
+
+        Ok(pinned)
+    }
+
+    fn save(&self) -> color_eyre::Result<()> {
+        let Some(file) = &self.file else {
+            return Ok(());
+        };
+
+        if let Some(parent) = file.parent() {
+            fs::create_dir_all(parent)
+                .wrap_err_with(|| format!("failed to create directory {parent:?}"))?;
+        }
+
+        let mut contents: String = self.paths.iter().map(|p| format!("{p}\n")).collect();
+        if contents.is_empty() {
+            contents.push('\n');
+        }
+        fs::write(file, contents).wrap_err_with(|| format!("failed to write pins to {file:?}"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_pins_roundtrip() -> color_eyre::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let file = dir.path().join("nested").join("pins.txt");
+
+        let mut pins = Pins::load(Some(file.clone()))?;
+        assert!(pins.toggle("man_vs_bee".into())?);
+        assert!(pins.toggle("project_001/sequence_002".into())?);
+        assert!(!pins.toggle("man_vs_bee".into())?);
+
+        let pins = Pins::load(Some(file))?;
+        assert!(pins.contains("project_001/sequence_002"));
+        assert!(!pins.contains("man_vs_bee"));
+
+        Ok(())
+    }
+}
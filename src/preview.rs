@@ -0,0 +1,232 @@
+use std::{
+    collections::HashMap,
+    io::{BufRead, BufReader},
+    process::{Command, Stdio},
+    sync::mpsc::{self, Receiver, Sender},
+    thread,
+};
+
+use crate::ansi::{self, AnsiText};
+
+/// Lines kept per preview. The command is stopped once it printed this many.
+pub const MAX_PREVIEW_LINES: usize = 1000;
+
+/// The output of the preview command for one item, as far as it got
+#[derive(Debug, Default)]
+pub struct Preview {
+    pub lines: Vec<AnsiText>,
+    /// Whether the command is done, either because it exited or was cut short
+    pub done: bool,
+    /// Whether the command printed more than [`MAX_PREVIEW_LINES`]
+    pub truncated: bool,
+    /// Why the command couldn't be run, or how it failed
+    pub error: Option<String>,
+}
+
+/// What a background preview thread reports back
+#[derive(Debug)]
+enum Update {
+    Line(String),
+    Truncated,
+    Failed(String),
+    Done,
+}
+
+/// Runs a shell command like `cat {path}/README` for items, like `fzf --preview`.
+/// Commands run in background threads and stream their output back, which
+/// is collected by [`Previewer::poll`]. Outputs are cached per item.
+#[derive(Debug)]
+pub struct Previewer {
+    command: String,
+    cache: HashMap<String, Preview>,
+    /// Bumped when the cache is cleared, to ignore what older commands still send
+    generation: u64,
+    sender: Sender<Message>,
+    receiver: Receiver<Message>,
+}
+
+/// An update, with the generation and cache key of the command it's from
+type Message = (u64, String, Update);
+
+impl Previewer {
+    pub fn new(command: impl Into<String>) -> Self {
+        let (sender, receiver) = mpsc::channel();
+        Self {
+            command: command.into(),
+            cache: HashMap::new(),
+            generation: 0,
+            sender,
+            receiver,
+        }
+    }
+
+    /// Starts previewing the item at `path` (its hierarchy path, used as the cache key),
+    /// unless it's already cached. `location` replaces `{path}` in the command.
+    pub fn request(&mut self, path: &str, name: &str, location: &str) {
+        if self.cache.contains_key(path) {
+            return;
+        }
+        self.cache.insert(path.to_string(), Preview::default());
+
+        let command = expand_command(&self.command, name, location);
+        let key = path.to_string();
+        let generation = self.generation;
+        let sender = self.sender.clone();
+        thread::spawn(move || run_command(&command, generation, &key, &sender));
+    }
+
+    /// Forgets every preview, so they are run again when next requested
+    pub fn clear(&mut self) {
+        self.cache.clear();
+        self.generation += 1;
+    }
+
+    /// Collects the output streamed so far. Returns whether there was any.
+    pub fn poll(&mut self) -> bool {
+        let mut updated = false;
+        while let Ok((generation, key, update)) = self.receiver.try_recv() {
+            if generation != self.generation {
+                continue;
+            }
+            let Some(preview) = self.cache.get_mut(&key) else {
+                continue;
+            };
+            match update {
+                Update::Line(line) => preview.lines.push(ansi::parse(&line)),
+                Update::Truncated => preview.truncated = true,
+                Update::Failed(error) => preview.error = Some(error),
+                Update::Done => preview.done = true,
+            }
+            updated = true;
+        }
+        updated
+    }
+
+    pub fn get(&self, path: &str) -> Option<&Preview> {
+        self.cache.get(path)
+    }
+
+    /// Whether some command is still running
+    pub fn is_busy(&self) -> bool {
+        self.cache.values().any(|preview| !preview.done)
+    }
+}
+
+/// Replaces `{path}` and `{name}` in the command with the shell-quoted values
+pub fn expand_command(command: &str, name: &str, location: &str) -> String {
+    command
+        .replace("{path}", &shell_quote(location))
+        .replace("{name}", &shell_quote(name))
+}
+
+/// Quotes `value` so `sh` reads it as a single word
+fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', r"'\''"))
+}
+
+/// Runs the command and sends its output line by line, until it exits
+/// or printed [`MAX_PREVIEW_LINES`]. The receiver going away is not an error.
+fn run_command(command: &str, generation: u64, key: &str, sender: &Sender<Message>) {
+    let send = |update| sender.send((generation, key.to_string(), update)).is_ok();
+
+    let child = Command::new("sh")
+        .arg("-c")
+        .arg(command)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn();
+    let mut child = match child {
+        Ok(child) => child,
+        Err(e) => {
+            send(Update::Failed(format!(
+                "failed to run the preview command: {e}"
+            )));
+            send(Update::Done);
+            return;
+        }
+    };
+
+    if let Some(stdout) = child.stdout.take() {
+        for (count, line) in BufReader::new(stdout).lines().enumerate() {
+            let Ok(line) = line else {
+                break;
+            };
+            if count == MAX_PREVIEW_LINES {
+                send(Update::Truncated);
+                let _ = child.kill();
+                break;
+            }
+            if !send(Update::Line(line)) {
+                let _ = child.kill();
+                break;
+            }
+        }
+    }
+
+    match child.wait() {
+        Ok(status) if !status.success() && status.code().is_some() => {
+            send(Update::Failed(format!(
+                "preview command exited with {status}"
+            )));
+        }
+        Err(e) => {
+            send(Update::Failed(format!(
+                "failed to wait for the preview command: {e}"
+            )));
+        }
+        _ => {}
+    }
+    send(Update::Done);
+}
+
+#[cfg(test)]
+mod tests {
+
+    use std::time::{Duration, Instant};
+
+    use super::*;
+
+    fn wait_for(previewer: &mut Previewer) {
+        let start = Instant::now();
+        while previewer.is_busy() && start.elapsed() < Duration::from_secs(5) {
+            previewer.poll();
+            thread::sleep(Duration::from_millis(10));
+        }
+    }
+
+    #[test]
+    fn test_expand_command_quotes_values() {
+        assert_eq!(
+            expand_command("cat {path}/README", "sh010", "/shows/it's/sh010"),
+            r"cat '/shows/it'\''s/sh010'/README"
+        );
+        assert_eq!(expand_command("echo {name}", "a b", ""), "echo 'a b'");
+    }
+
+    #[test]
+    fn test_preview_streams_and_truncates() {
+        let mut previewer = Previewer::new("printf 'one\\ntwo\\n'; echo {name}");
+        previewer.request("man_vs_bee", "man_vs_bee", "man_vs_bee");
+        wait_for(&mut previewer);
+        // Everything was collected already
+        assert!(!previewer.poll());
+
+        let preview = previewer.get("man_vs_bee").unwrap();
+        let lines: Vec<&str> = preview.lines.iter().map(|l| l.text.as_str()).collect();
+        assert_eq!(lines, vec!["one", "two", "man_vs_bee"]);
+        assert!(!preview.truncated);
+        assert_eq!(preview.error, None);
+
+        let mut previewer = Previewer::new("yes");
+        previewer.request("endless", "endless", "endless");
+        wait_for(&mut previewer);
+
+        let preview = previewer.get("endless").unwrap();
+        assert_eq!(preview.lines.len(), MAX_PREVIEW_LINES);
+        assert!(preview.truncated);
+
+        previewer.clear();
+        assert!(previewer.get("endless").is_none());
+    }
+}
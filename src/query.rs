@@ -0,0 +1,179 @@
+use crate::backend::Item;
+
+/// A `key:value` term narrowing results down by metadata
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Facet {
+    pub key: String,
+    pub value: String,
+}
+
+impl Facet {
+    /// Whether the item has a field called `key` whose value starts with
+    /// `value`, ignoring case. `status` refers to the item status.
+    pub fn matches(&self, item: &Item) -> bool {
+        let field = match self.key.as_str() {
+            "status" => item.status.map(|s| s.label()),
+            key => item.metadata.get(key).map(String::as_str),
+        };
+
+        field.is_some_and(|field| field.to_lowercase().starts_with(&self.value.to_lowercase()))
+    }
+}
+
+/// A `project:bee`, `seq:001` or `shot:sh01` term, narrowing results down to
+/// those under a project, sequence or shot whose name contains the value
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Scope {
+    /// How deep in the hierarchy: 0 for projects, 1 for sequences, 2 for shots
+    pub level: usize,
+    pub value: String,
+}
+
+impl Scope {
+    /// Whether the name at the scope's level of `path` contains the value, ignoring case
+    pub fn matches(&self, path: &[&str]) -> bool {
+        path.get(self.level)
+            .is_some_and(|name| name.to_lowercase().contains(&self.value.to_lowercase()))
+    }
+}
+
+/// A search query, split into metadata facets, hierarchy scopes and the text
+/// left for fuzzy matching
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Query {
+    pub facets: Vec<Facet>,
+    pub scopes: Vec<Scope>,
+    pub text: String,
+}
+
+impl Query {
+    /// Splits `status:approved artist:anna sh01` into the facets and `sh01`, and
+    /// `project:bee seq:001 sh01` into the scopes and `sh01`.
+    /// A dangling `key:` is ignored, since it's usually still being typed.
+    pub fn parse(input: &str) -> Self {
+        let mut query = Query::default();
+        let mut words = Vec::new();
+
+        for word in input.split_whitespace() {
+            match word.split_once(':') {
+                Some((key, "")) if is_facet_key(key) => {}
+                Some((key, value)) if is_facet_key(key) => match scope_level(key) {
+                    Some(level) => query.scopes.push(Scope {
+                        level,
+                        value: value.to_string(),
+                    }),
+                    None => query.facets.push(Facet {
+                        key: key.to_lowercase(),
+                        value: value.to_string(),
+                    }),
+                },
+                _ => words.push(word),
+            }
+        }
+
+        query.text = words.join(" ");
+        query
+    }
+
+    /// Whether the item satisfies every facet of the query
+    pub fn matches_facets(&self, item: &Item) -> bool {
+        self.facets.iter().all(|facet| facet.matches(item))
+    }
+
+    /// Whether the item, listed under `path`, is within every scope of the query.
+    /// Names like `project/sequence/shot` (e.g. piped in) count as that many levels.
+    pub fn matches_scopes(&self, path: &[String], item: &Item) -> bool {
+        if self.scopes.is_empty() {
+            return true;
+        }
+        let full_path: Vec<&str> = path
+            .iter()
+            .map(String::as_str)
+            .chain(item.name.split('/'))
+            .collect();
+        self.scopes.iter().all(|scope| scope.matches(&full_path))
+    }
+}
+
+/// The level of the hierarchy a scope key refers to
+fn scope_level(key: &str) -> Option<usize> {
+    match key.to_lowercase().as_str() {
+        "project" | "proj" => Some(0),
+        "sequence" | "seq" => Some(1),
+        "shot" => Some(2),
+        _ => None,
+    }
+}
+
+fn is_facet_key(key: &str) -> bool {
+    !key.is_empty() && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::backend::{ItemKind, Status};
+
+    #[test]
+    fn test_parse_facets_and_text() {
+        let query = Query::parse("status:approved sh01 artist:anna");
+        assert_eq!(query.text, "sh01");
+        assert_eq!(
+            query.facets,
+            vec![
+                Facet {
+                    key: "status".into(),
+                    value: "approved".into()
+                },
+                Facet {
+                    key: "artist".into(),
+                    value: "anna".into()
+                },
+            ]
+        );
+
+        // Still typing the value, and things that aren't keys
+        let query = Query::parse("artist: a:b:c ::");
+        assert!(query.facets.len() == 1 && query.facets[0].value == "b:c");
+        assert_eq!(query.text, "::");
+    }
+
+    #[test]
+    fn test_scopes() {
+        let query = Query::parse("project:bee SEQ:001 sh01 shot:");
+        assert_eq!(query.text, "sh01");
+        assert_eq!(
+            query.scopes,
+            vec![
+                Scope {
+                    level: 0,
+                    value: "bee".into()
+                },
+                Scope {
+                    level: 1,
+                    value: "001".into()
+                },
+            ]
+        );
+
+        let path = vec!["man_vs_bee".to_string()];
+        assert!(query.matches_scopes(&path, &Item::new("sequence_001", ItemKind::Sequence)));
+        assert!(!query.matches_scopes(&path, &Item::new("sequence_002", ItemKind::Sequence)));
+        assert!(!query.matches_scopes(&[], &Item::new("man_vs_bee", ItemKind::Project)));
+        // Piped paths
+        let line = Item::new("Man_vs_Bee/sequence_001/sh010", ItemKind::Shot);
+        assert!(query.matches_scopes(&[], &line));
+    }
+
+    #[test]
+    fn test_facet_matching() {
+        let item = Item::new("sh010", ItemKind::Shot)
+            .with_status(Status::Approved)
+            .with_metadata("artist", "Anna");
+
+        assert!(Query::parse("status:appr artist:anna").matches_facets(&item));
+        assert!(!Query::parse("status:wip").matches_facets(&item));
+        assert!(!Query::parse("camera:a").matches_facets(&item));
+    }
+}
@@ -0,0 +1,69 @@
+use std::time::{Duration, Instant};
+
+/// A token bucket: lets `rate` requests through per second on average,
+/// with bursts of up to `rate` requests at once
+#[derive(Debug, Clone)]
+pub struct TokenBucket {
+    rate: f64,
+    tokens: f64,
+    refilled_at: Instant,
+}
+
+impl TokenBucket {
+    /// Starts full, at `rate` requests per second
+    pub fn new(rate: u32, now: Instant) -> Self {
+        let rate = f64::from(rate.max(1));
+        Self {
+            rate,
+            tokens: rate,
+            refilled_at: now,
+        }
+    }
+
+    fn refill(&mut self, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.refilled_at);
+        self.tokens = (self.tokens + elapsed.as_secs_f64() * self.rate).min(self.rate);
+        self.refilled_at = now;
+    }
+
+    /// Takes a token if there's one, meaning a request can be made right now
+    pub fn try_take(&mut self, now: Instant) -> bool {
+        self.refill(now);
+        if self.tokens < 1.0 {
+            return false;
+        }
+        self.tokens -= 1.0;
+        true
+    }
+
+    /// How long until a token is available
+    pub fn wait(&mut self, now: Instant) -> Duration {
+        self.refill(now);
+        Duration::from_secs_f64((1.0 - self.tokens).max(0.0) / self.rate)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_token_bucket() {
+        let start = Instant::now();
+        let mut bucket = TokenBucket::new(4, start);
+        let taken = (0..10).filter(|_| bucket.try_take(start)).count();
+        assert_eq!(taken, 4);
+        assert_eq!(bucket.wait(start), Duration::from_millis(250));
+
+        let later = start + Duration::from_millis(600);
+        assert!(bucket.try_take(later));
+        assert!(bucket.try_take(later));
+        assert!(!bucket.try_take(later));
+
+        // Waiting long doesn't allow bigger bursts
+        let much_later = later + Duration::from_secs(60);
+        let taken = (0..10).filter(|_| bucket.try_take(much_later)).count();
+        assert_eq!(taken, 4);
+    }
+}
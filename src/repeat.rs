@@ -0,0 +1,69 @@
+use std::time::{Duration, Instant};
+
+/// How many moves in a row go one row at a time, when not configured
+pub const DEFAULT_AFTER: u32 = 8;
+/// How many rows each move goes after that, when not configured
+pub const DEFAULT_STEP: usize = 5;
+
+/// Moves closer together than this are taken as the key being held down.
+/// Terminals repeat keys every 30 to 50ms, after a longer first delay.
+const REPEAT_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Speeds up moving through the results while Up or Down is held, so thousands
+/// of them can be gone through by key repeat
+#[derive(Debug, Clone)]
+pub struct Acceleration {
+    after: u32,
+    step: usize,
+    /// The direction and time of the last move
+    last: Option<(isize, Instant)>,
+    repeats: u32,
+}
+
+impl Acceleration {
+    /// Moves one row at a time for the first `after` repeats, then `step` rows
+    pub fn new(after: u32, step: usize) -> Self {
+        Self {
+            after,
+            step: step.max(1),
+            last: None,
+            repeats: 0,
+        }
+    }
+
+    /// How many rows a move by `delta` at `now` goes
+    pub fn rows(&mut self, delta: isize, now: Instant) -> isize {
+        let repeated = self.last.is_some_and(|(last_delta, at)| {
+            last_delta == delta && now.saturating_duration_since(at) < REPEAT_INTERVAL
+        });
+        self.repeats = if repeated { self.repeats + 1 } else { 0 };
+        self.last = Some((delta, now));
+
+        if self.repeats >= self.after {
+            delta * self.step as isize
+        } else {
+            delta
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_acceleration() {
+        let mut acceleration = Acceleration::new(2, 5);
+        let start = Instant::now();
+        let at = |ms| start + Duration::from_millis(ms);
+
+        assert_eq!(acceleration.rows(1, at(0)), 1);
+        assert_eq!(acceleration.rows(1, at(30)), 1);
+        assert_eq!(acceleration.rows(1, at(60)), 5);
+        assert_eq!(acceleration.rows(1, at(90)), 5);
+        // Letting go, or turning around, starts over
+        assert_eq!(acceleration.rows(1, at(500)), 1);
+        assert_eq!(acceleration.rows(-1, at(530)), -1);
+    }
+}
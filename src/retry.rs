@@ -0,0 +1,74 @@
+use std::{
+    collections::hash_map::RandomState,
+    hash::{BuildHasher, Hasher},
+    time::Duration,
+};
+
+/// How long to wait between attempts at something failing for transient
+/// reasons (e.g. a network share timing out), and when to give up
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Backoff {
+    /// The wait before the first retry, doubled for each of the next ones
+    pub base: Duration,
+    /// The longest wait between two attempts
+    pub max: Duration,
+    /// How many attempts there are in total, the first one included
+    pub attempts: u32,
+}
+
+impl Default for Backoff {
+    fn default() -> Self {
+        Self {
+            base: Duration::from_millis(500),
+            max: Duration::from_secs(30),
+            attempts: 5,
+        }
+    }
+}
+
+impl Backoff {
+    /// The wait before making the given attempt (the second one being the first retry)
+    pub fn delay(&self, attempt: u32) -> Duration {
+        let doublings = attempt.saturating_sub(2).min(31);
+        self.base.saturating_mul(1 << doublings).min(self.max)
+    }
+
+    /// [`Backoff::delay`] give or take 25%, so clients failing together
+    /// don't all retry together
+    pub fn jittered_delay(&self, attempt: u32) -> Duration {
+        let random = RandomState::new().build_hasher().finish();
+        let factor = 0.75 + 0.5 * (random as f64 / u64::MAX as f64);
+        self.delay(attempt).mul_f64(factor)
+    }
+
+    /// Whether there are attempts left after the given one
+    pub fn can_retry(&self, attempt: u32) -> bool {
+        attempt < self.attempts
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_backoff_doubles_up_to_the_max() {
+        let backoff = Backoff {
+            base: Duration::from_secs(1),
+            max: Duration::from_secs(5),
+            attempts: 4,
+        };
+        let delays: Vec<u64> = (2..=6).map(|a| backoff.delay(a).as_secs()).collect();
+        assert_eq!(delays, [1, 2, 4, 5, 5]);
+        assert_eq!(backoff.delay(u32::MAX), Duration::from_secs(5));
+
+        for _ in 0..100 {
+            let delay = backoff.jittered_delay(3);
+            assert!(delay >= Duration::from_millis(1500) && delay <= Duration::from_millis(2500));
+        }
+
+        assert!(backoff.can_retry(3));
+        assert!(!backoff.can_retry(4));
+    }
+}
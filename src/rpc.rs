@@ -0,0 +1,276 @@
+use std::io::{BufRead, Write};
+
+use color_eyre::eyre::{eyre, WrapErr};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{
+    backend::Backend,
+    finder::{Finder, ResultEntry},
+    matcher::FuzzyMatcher,
+};
+
+/// Standard JSON-RPC 2.0 error codes
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+/// The backend failed, e.g. the path given doesn't exist
+const BACKEND_ERROR: i64 = -32000;
+
+#[derive(Debug, Deserialize)]
+struct Request {
+    jsonrpc: String,
+    /// Requests without an id are notifications, which get no response
+    #[serde(default)]
+    id: Option<Value>,
+    method: String,
+    #[serde(default)]
+    params: Value,
+}
+
+#[derive(Debug, Serialize)]
+struct Response {
+    jsonrpc: &'static str,
+    id: Value,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    result: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<Error>,
+}
+
+#[derive(Debug, Serialize)]
+struct Error {
+    code: i64,
+    message: String,
+}
+
+impl Error {
+    fn new(code: i64, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct ListChildrenParams {
+    #[serde(default)]
+    path: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct MatchParams {
+    #[serde(default)]
+    path: Vec<String>,
+    query: String,
+    #[serde(default)]
+    limit: Option<usize>,
+}
+
+#[derive(Debug, Deserialize)]
+struct SelectParams {
+    /// The full path of the item, e.g. `["man_vs_bee", "sequence_001", "sh010"]`
+    path: Vec<String>,
+}
+
+/// Answers newline-delimited JSON-RPC 2.0 requests read from `reader` until it runs out,
+/// writing each response on its own line. Methods:
+/// - `listChildren {path}`: the items under `path`, the top level by default
+/// - `match {path, query, limit}`: the items under `path` matching `query`, best first
+/// - `select {path}`: checks that `path` is a leaf item and returns it as `a/b/c`
+pub fn run(
+    backend: &dyn Backend,
+    normalize: bool,
+    reader: impl BufRead,
+    mut writer: impl Write,
+) -> color_eyre::Result<()> {
+    let mut finder = Finder::new(backend, FuzzyMatcher::new(normalize))?;
+
+    for line in reader.lines() {
+        let line = line.wrap_err("failed to read a request")?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        if let Some(response) = handle_line(&mut finder, backend, &line) {
+            serde_json::to_writer(&mut writer, &response)?;
+            writeln!(writer)?;
+            writer.flush()?;
+        }
+    }
+
+    Ok(())
+}
+
+fn handle_line(finder: &mut Finder, backend: &dyn Backend, line: &str) -> Option<Response> {
+    let value: Value = match serde_json::from_str(line) {
+        Ok(value) => value,
+        Err(e) => {
+            return Some(error_response(
+                Value::Null,
+                Error::new(PARSE_ERROR, e.to_string()),
+            ))
+        }
+    };
+    let request: Request = match serde_json::from_value(value) {
+        Ok(request) => request,
+        Err(e) => {
+            return Some(error_response(
+                Value::Null,
+                Error::new(INVALID_REQUEST, e.to_string()),
+            ))
+        }
+    };
+    if request.jsonrpc != "2.0" {
+        let id = request.id.unwrap_or(Value::Null);
+        return Some(error_response(
+            id,
+            Error::new(INVALID_REQUEST, "only JSON-RPC 2.0 is supported"),
+        ));
+    }
+
+    let result = call(finder, backend, &request.method, request.params);
+    let id = request.id?;
+    Some(match result {
+        Ok(result) => Response {
+            jsonrpc: "2.0",
+            id,
+            result: Some(result),
+            error: None,
+        },
+        Err(error) => error_response(id, error),
+    })
+}
+
+fn error_response(id: Value, error: Error) -> Response {
+    Response {
+        jsonrpc: "2.0",
+        id,
+        result: None,
+        error: Some(error),
+    }
+}
+
+fn call(
+    finder: &mut Finder,
+    backend: &dyn Backend,
+    method: &str,
+    params: Value,
+) -> Result<Value, Error> {
+    let backend_error = |e: color_eyre::Report| Error::new(BACKEND_ERROR, format!("{e:#}"));
+
+    let result = match method {
+        "listChildren" => {
+            let params: ListChildrenParams = parse_params(params)?;
+            let items = backend.children(&params.path).map_err(backend_error)?;
+            to_value(items.iter().map(ResultEntry::from).collect::<Vec<_>>())
+        }
+        "match" => {
+            let params: MatchParams = parse_params(params)?;
+            finder.set_path(params.path).map_err(backend_error)?;
+            finder.set_query(params.query);
+            let results: Vec<ResultEntry> = finder
+                .results()
+                .take(params.limit.unwrap_or(usize::MAX))
+                .map(ResultEntry::from)
+                .collect();
+            to_value(results)
+        }
+        "select" => {
+            let params: SelectParams = parse_params(params)?;
+            let selection = select(backend, &params.path).map_err(backend_error)?;
+            to_value(serde_json::json!({ "selection": selection }))
+        }
+        _ => {
+            return Err(Error::new(
+                METHOD_NOT_FOUND,
+                format!("unknown method {method:?}"),
+            ))
+        }
+    };
+
+    Ok(result)
+}
+
+fn parse_params<T: DeserializeOwned>(params: Value) -> Result<T, Error> {
+    // Methods whose params are all optional can be called without any
+    let params = if params.is_null() {
+        Value::Object(Default::default())
+    } else {
+        params
+    };
+    serde_json::from_value(params).map_err(|e| Error::new(INVALID_PARAMS, e.to_string()))
+}
+
+fn to_value(value: impl Serialize) -> Value {
+    serde_json::to_value(value).unwrap_or(Value::Null)
+}
+
+/// Checks that `path` leads to a leaf item, which is returned as `a/b/c`
+fn select(backend: &dyn Backend, path: &[String]) -> color_eyre::Result<String> {
+    let (name, parent) = path
+        .split_last()
+        .ok_or_else(|| eyre!("the path to select is empty"))?;
+    let item = backend
+        .children(parent)?
+        .into_iter()
+        .find(|item| &item.name == name)
+        .ok_or_else(|| eyre!("there is no {name:?} under {parent:?}"))?;
+    if !item.kind.is_leaf() {
+        return Err(eyre!("{name:?} is a {}, not a leaf", item.kind.label()));
+    }
+
+    Ok(path.join("/"))
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::backend::MockBackend;
+
+    fn session(requests: &[&str]) -> color_eyre::Result<Vec<Value>> {
+        let mut output = Vec::new();
+        run(
+            &MockBackend,
+            false,
+            requests.join("\n").as_bytes(),
+            &mut output,
+        )?;
+        Ok(String::from_utf8(output)?
+            .lines()
+            .map(serde_json::from_str)
+            .collect::<Result<_, _>>()?)
+    }
+
+    #[test]
+    fn test_rpc_methods() -> color_eyre::Result<()> {
+        let responses = session(&[
+            r#"{"jsonrpc": "2.0", "id": 1, "method": "listChildren"}"#,
+            r#"{"jsonrpc": "2.0", "id": 2, "method": "match", "params": {"query": "man_vs", "limit": 1}}"#,
+            r#"{"jsonrpc": "2.0", "id": 3, "method": "select", "params": {"path": ["man_vs_bee", "sequence_001", "sh010"]}}"#,
+            r#"{"jsonrpc": "2.0", "id": 4, "method": "select", "params": {"path": ["man_vs_bee"]}}"#,
+            r#"{"jsonrpc": "2.0", "method": "match", "params": {"query": "bee"}}"#,
+            r#"{"jsonrpc": "2.0", "id": 5, "method": "explode"}"#,
+            "{",
+        ])?;
+
+        assert_eq!(responses.len(), 6);
+        assert_eq!(responses[0]["id"], 1);
+        assert_eq!(responses[0]["result"][0]["kind"], "project");
+        assert_eq!(
+            responses[1]["result"],
+            serde_json::json!([{"name": "man_vs_bee", "kind": "project", "leaf": false}])
+        );
+        assert_eq!(
+            responses[2]["result"]["selection"],
+            "man_vs_bee/sequence_001/sh010"
+        );
+        assert_eq!(responses[3]["error"]["code"], BACKEND_ERROR);
+        assert_eq!(responses[4]["error"]["code"], METHOD_NOT_FOUND);
+        assert_eq!(responses[5]["error"]["code"], PARSE_ERROR);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,239 @@
+use std::{
+    fs,
+    io::{self, BufRead, BufReader, Write},
+    os::unix::{
+        fs::FileTypeExt,
+        net::{UnixListener, UnixStream},
+    },
+    path::Path,
+};
+
+use color_eyre::eyre::{bail, WrapErr};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    backend::Backend,
+    finder::{Accepted, Finder, ResultEntry},
+    matcher::FuzzyMatcher,
+};
+
+/// A command sent by a client, as one JSON object per line,
+/// e.g. `{"command": "set-query", "query": "sh01"}`
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(tag = "command", rename_all = "kebab-case")]
+pub enum Request {
+    SetQuery {
+        query: String,
+    },
+    /// Lists the best matches, all of them unless `limit` is given
+    GetResults {
+        #[serde(default)]
+        limit: Option<usize>,
+    },
+    /// Descends into the result at `index`, or selects it if it's a leaf
+    Accept {
+        #[serde(default)]
+        index: usize,
+    },
+}
+
+/// The answer to a [`Request`], sent back as one JSON object per line
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "status", rename_all = "kebab-case")]
+pub enum Response {
+    /// The query was set, and this many items match it
+    Ok {
+        matches: usize,
+    },
+    Results {
+        path: Vec<String>,
+        /// How many items match in total, whatever the limit
+        total: usize,
+        results: Vec<ResultEntry>,
+    },
+    Entered {
+        path: Vec<String>,
+    },
+    Selected {
+        selection: String,
+    },
+    Error {
+        message: String,
+    },
+}
+
+/// Answers the requests of clients connecting to a Unix socket at `socket_path`,
+/// one client at a time, until the process is killed. Every client starts
+/// browsing from the top level.
+pub fn serve(socket_path: &Path, backend: &dyn Backend, normalize: bool) -> color_eyre::Result<()> {
+    remove_stale_socket(socket_path)?;
+    let listener = UnixListener::bind(socket_path)
+        .wrap_err_with(|| format!("failed to listen on {socket_path:?}"))?;
+    tracing::info!("Listening on {socket_path:?}");
+
+    for stream in listener.incoming() {
+        let stream = match stream {
+            Ok(stream) => stream,
+            Err(e) => {
+                tracing::warn!("Could not accept a client: {e}");
+                continue;
+            }
+        };
+
+        // Neither of these is the client's fault, the next one may fare better
+        let mut finder = match Finder::new(backend, FuzzyMatcher::new(normalize)) {
+            Ok(finder) => finder,
+            Err(e) => {
+                tracing::warn!("Could not list the top level for a client: {e:#}");
+                continue;
+            }
+        };
+        let reader = match stream.try_clone() {
+            Ok(stream) => BufReader::new(stream),
+            Err(e) => {
+                tracing::warn!("Could not read from a client: {e}");
+                continue;
+            }
+        };
+        if let Err(e) = handle_client(&mut finder, reader, stream) {
+            tracing::warn!("Client went away: {e:#}");
+        }
+    }
+
+    Ok(())
+}
+
+/// Removes a socket left behind by a previous run, which would make binding
+/// fail. Anything else at `socket_path`, or a socket another server still
+/// listens on, is left alone and refuses to start.
+fn remove_stale_socket(socket_path: &Path) -> color_eyre::Result<()> {
+    let metadata = match fs::symlink_metadata(socket_path) {
+        Ok(metadata) => metadata,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(()),
+        Err(e) => return Err(e).wrap_err_with(|| format!("failed to read {socket_path:?}")),
+    };
+    if !metadata.file_type().is_socket() {
+        bail!("{socket_path:?} already exists and isn't a socket");
+    }
+    if UnixStream::connect(socket_path).is_ok() {
+        bail!("another server is already listening on {socket_path:?}");
+    }
+
+    fs::remove_file(socket_path)
+        .wrap_err_with(|| format!("failed to remove the old socket {socket_path:?}"))
+}
+
+/// Answers each line read from `reader` with a line written to `writer`,
+/// until `reader` runs out
+pub fn handle_client(
+    finder: &mut Finder,
+    reader: impl BufRead,
+    mut writer: impl Write,
+) -> color_eyre::Result<()> {
+    for line in reader.lines() {
+        let line = line.wrap_err("failed to read a request")?;
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let response = match serde_json::from_str(&line) {
+            Ok(request) => handle_request(finder, request),
+            Err(e) => Response::Error {
+                message: format!("invalid request: {e}"),
+            },
+        };
+        serde_json::to_writer(&mut writer, &response)?;
+        writeln!(writer)?;
+        writer.flush()?;
+    }
+
+    Ok(())
+}
+
+pub fn handle_request(finder: &mut Finder, request: Request) -> Response {
+    match request {
+        Request::SetQuery { query } => {
+            finder.set_query(query);
+            Response::Ok {
+                matches: finder.match_count(),
+            }
+        }
+        Request::GetResults { limit } => Response::Results {
+            path: finder.path().to_vec(),
+            total: finder.match_count(),
+            results: finder
+                .results()
+                .take(limit.unwrap_or(usize::MAX))
+                .map(ResultEntry::from)
+                .collect(),
+        },
+        Request::Accept { index } => match finder.accept(index) {
+            Ok(Accepted::Entered(path)) => Response::Entered { path },
+            Ok(Accepted::Selected(selection)) => Response::Selected { selection },
+            Err(e) => Response::Error {
+                message: format!("{e:#}"),
+            },
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::backend::MockBackend;
+
+    #[test]
+    fn test_client_session() -> color_eyre::Result<()> {
+        let mut finder = Finder::new(&MockBackend, FuzzyMatcher::default())?;
+        let requests = [
+            r#"{"command": "set-query", "query": "man_vs"}"#,
+            r#"{"command": "get-results", "limit": 1}"#,
+            r#"{"command": "accept"}"#,
+            r#"{"command": "accept", "index": 1000}"#,
+            "not json",
+        ]
+        .join("\n");
+
+        let mut output = Vec::new();
+        handle_client(&mut finder, requests.as_bytes(), &mut output)?;
+        let lines: Vec<serde_json::Value> = String::from_utf8(output)?
+            .lines()
+            .map(serde_json::from_str)
+            .collect::<Result<_, _>>()?;
+
+        assert_eq!(lines.len(), 5);
+        assert_eq!(lines[0]["status"], "ok");
+        assert_eq!(lines[1]["status"], "results");
+        assert_eq!(lines[1]["results"][0]["name"], "man_vs_bee");
+        assert_eq!(lines[1]["results"][0]["kind"], "project");
+        assert_eq!(lines[1]["results"].as_array().unwrap().len(), 1);
+        assert_eq!(lines[2]["status"], "entered");
+        assert_eq!(lines[2]["path"][0], "man_vs_bee");
+        assert_eq!(lines[3]["status"], "error");
+        assert_eq!(lines[4]["status"], "error");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_remove_stale_socket() -> color_eyre::Result<()> {
+        let dir = tempfile::tempdir()?;
+
+        let file = dir.path().join("notes.txt");
+        fs::write(&file, "keep me")?;
+        assert!(remove_stale_socket(&file).is_err());
+        assert!(file.exists());
+
+        let socket = dir.path().join("cgs.sock");
+        let listener = UnixListener::bind(&socket)?;
+        assert!(remove_stale_socket(&socket).is_err());
+        assert!(socket.exists());
+
+        drop(listener);
+        remove_stale_socket(&socket)?;
+        assert!(!socket.exists());
+
+        Ok(())
+    }
+}
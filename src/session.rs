@@ -0,0 +1,50 @@
+use std::{fs, io, path::Path};
+
+use color_eyre::eyre::WrapErr;
+
+/// Reads the context (e.g. `["man_vs_bee", "sequence_001"]`) the user last
+/// picked something from. A missing file means there's nothing to resume.
+pub fn load_last_context(file: &Path) -> color_eyre::Result<Vec<String>> {
+    match fs::read_to_string(file) {
+        Ok(contents) => Ok(contents
+            .trim()
+            .split('/')
+            .filter(|name| !name.is_empty())
+            .map(String::from)
+            .collect()),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(e) => Err(e).wrap_err_with(|| format!("failed to read last context from {file:?}")),
+    }
+}
+
+/// Remembers the context of the selection, so that `--resume` can start there
+pub fn save_last_context(file: &Path, context: &[&str]) -> color_eyre::Result<()> {
+    if let Some(parent) = file.parent() {
+        fs::create_dir_all(parent)
+            .wrap_err_with(|| format!("failed to create directory {parent:?}"))?;
+    }
+
+    fs::write(file, format!("{}\n", context.join("/")))
+        .wrap_err_with(|| format!("failed to write last context to {file:?}"))
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_last_context_roundtrip() -> color_eyre::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let file = dir.path().join("last_context.txt");
+        assert!(load_last_context(&file)?.is_empty());
+
+        save_last_context(&file, &["man_vs_bee", "sequence_001"])?;
+        assert_eq!(
+            load_last_context(&file)?,
+            vec!["man_vs_bee", "sequence_001"]
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,35 @@
+/// Shells that `cgs-go init` has key bindings for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum Shell {
+    Bash,
+    Zsh,
+    Fish,
+}
+
+impl Shell {
+    /// Functions binding the picker to Alt+G and adding a `cgs-cd` command,
+    /// to be evaluated by the shell's startup file
+    pub fn init_script(&self) -> &'static str {
+        match self {
+            Shell::Bash => include_str!("shell/init.bash"),
+            Shell::Zsh => include_str!("shell/init.zsh"),
+            Shell::Fish => include_str!("shell/init.fish"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_init_scripts() {
+        for shell in [Shell::Bash, Shell::Zsh, Shell::Fish] {
+            let script = shell.init_script();
+            assert!(script.contains("cgs-go --output-file"), "{shell:?}");
+            assert!(script.contains("--print-location"), "{shell:?}");
+            assert!(script.contains("cgs-cd"), "{shell:?}");
+        }
+    }
+}
@@ -0,0 +1,26 @@
+# cgs-go key bindings for bash, set up with: eval "$(cgs-go init bash)"
+
+# Runs the picker on the terminal, printing what was selected
+__cgs_go_pick() {
+    local output
+    output=$(mktemp) || return
+    cgs-go --output-file "$output" "$@" </dev/tty >/dev/tty
+    cat "$output"
+    rm -f "$output"
+}
+
+# Alt+G: insert the selection at the cursor
+__cgs_go_widget() {
+    local selection
+    selection=$(__cgs_go_pick) || return
+    READLINE_LINE="${READLINE_LINE:0:READLINE_POINT}${selection}${READLINE_LINE:READLINE_POINT}"
+    READLINE_POINT=$((READLINE_POINT + ${#selection}))
+}
+bind -m emacs-standard -x '"\eg": __cgs_go_widget'
+bind -m vi-insert -x '"\eg": __cgs_go_widget'
+
+# cd into the selected shot, for --root (or `root` in the config)
+cgs-cd() {
+    local dir
+    dir=$(__cgs_go_pick --print-location "$@") && [ -n "$dir" ] && cd -- "$dir"
+}
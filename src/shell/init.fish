@@ -0,0 +1,24 @@
+# cgs-go key bindings for fish, set up with: cgs-go init fish | source
+
+# Runs the picker on the terminal, printing what was selected
+function __cgs_go_pick
+    set -l output (mktemp); or return
+    cgs-go --output-file $output $argv </dev/tty >/dev/tty
+    cat $output
+    rm -f $output
+end
+
+# Alt+G: insert the selection at the cursor
+function __cgs_go_widget
+    set -l selection (__cgs_go_pick)
+    and commandline --insert -- $selection
+    commandline --function repaint
+end
+bind \eg __cgs_go_widget
+
+# cd into the selected shot, for --root (or `root` in the config)
+function cgs-cd
+    set -l dir (__cgs_go_pick --print-location $argv)
+    and test -n "$dir"
+    and cd -- $dir
+end
@@ -0,0 +1,116 @@
+use std::{cmp::Reverse, collections::HashMap};
+
+use crate::backend::Item;
+
+/// How the matching items are ordered in the results list
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SortMode {
+    /// Best fuzzy match first
+    #[default]
+    Score,
+    /// By name, ignoring case
+    Alphabetical,
+    /// Most recently modified first, for backends that know about it
+    Recency,
+}
+
+impl SortMode {
+    pub fn label(&self) -> &'static str {
+        match self {
+            SortMode::Score => "score",
+            SortMode::Alphabetical => "alphabetical",
+            SortMode::Recency => "recency",
+        }
+    }
+
+    pub fn next(&self) -> Self {
+        match self {
+            SortMode::Score => SortMode::Alphabetical,
+            SortMode::Alphabetical => SortMode::Recency,
+            SortMode::Recency => SortMode::Score,
+        }
+    }
+
+    /// Reorders `matches` (indices into `items`, already sorted by score).
+    /// Sorting is stable, so equal items keep their score order.
+    pub fn sort(&self, matches: &mut [usize], items: &[Item]) {
+        match self {
+            SortMode::Score => {}
+            SortMode::Alphabetical => matches.sort_by_cached_key(|i| items[*i].name.to_lowercase()),
+            // Items without a modification time go last
+            SortMode::Recency => matches.sort_by_key(|i| {
+                let modified = items[*i].modified;
+                (modified.is_none(), Reverse(modified))
+            }),
+        }
+    }
+}
+
+/// Where the highlight goes when the results change
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum StartAt {
+    /// The top row
+    #[default]
+    First,
+    /// The bottom row
+    Last,
+    /// The best fuzzy match, wherever the sort mode and pins put it
+    QueryBest,
+}
+
+impl StartAt {
+    /// The row to highlight among `matches`, given the score of each item
+    pub fn index(&self, matches: &[usize], scores: &HashMap<usize, u32>) -> Option<usize> {
+        match self {
+            StartAt::First => (!matches.is_empty()).then_some(0),
+            StartAt::Last => matches.len().checked_sub(1),
+            // The first of the best, so ties go to the top
+            StartAt::QueryBest => matches
+                .iter()
+                .enumerate()
+                .max_by_key(|(row, i)| (scores.get(i).copied().unwrap_or(0), Reverse(*row)))
+                .map(|(row, _)| row),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use std::time::{Duration, SystemTime};
+
+    use super::*;
+    use crate::backend::ItemKind;
+
+    #[test]
+    fn test_sort_modes() {
+        let day = Duration::from_secs(60 * 60 * 24);
+        let items = vec![
+            Item::new("b", ItemKind::Shot).with_modified(SystemTime::UNIX_EPOCH + day),
+            Item::new("C", ItemKind::Shot),
+            Item::new("a", ItemKind::Shot).with_modified(SystemTime::UNIX_EPOCH + day * 2),
+        ];
+
+        let mut matches = vec![1, 0, 2];
+        SortMode::Score.sort(&mut matches, &items);
+        assert_eq!(matches, vec![1, 0, 2]);
+
+        SortMode::Alphabetical.sort(&mut matches, &items);
+        assert_eq!(matches, vec![2, 0, 1]);
+
+        SortMode::Recency.sort(&mut matches, &items);
+        assert_eq!(matches, vec![2, 0, 1]);
+    }
+
+    #[test]
+    fn test_start_at() {
+        let scores = HashMap::from([(4, 10), (7, 30), (9, 30)]);
+        let matches = [4, 9, 7];
+        assert_eq!(StartAt::First.index(&matches, &scores), Some(0));
+        assert_eq!(StartAt::Last.index(&matches, &scores), Some(2));
+        assert_eq!(StartAt::QueryBest.index(&matches, &scores), Some(1));
+        assert_eq!(StartAt::Last.index(&[], &scores), None);
+    }
+}
@@ -0,0 +1,38 @@
+use std::path::{Path, PathBuf};
+
+/// Where we keep files that should survive between runs (pins, last session...)
+pub fn state_dir() -> Option<PathBuf> {
+    dirs::state_dir()
+        .or_else(dirs::data_local_dir)
+        .map(|dir| dir.join("cgs-go"))
+}
+
+/// Where we keep files that can be thrown away anytime, only making things faster
+/// or available offline
+pub fn cache_dir() -> Option<PathBuf> {
+    dirs::cache_dir().map(|dir| dir.join("cgs-go"))
+}
+
+/// Where the items listed under `root` are cached, for `--offline-cache`
+pub fn listings_cache_dir(root: &Path) -> Option<PathBuf> {
+    let root = root.canonicalize().unwrap_or_else(|_| root.to_path_buf());
+    let name = root.to_string_lossy().replace(['/', '\\', ':'], "%");
+    cache_dir().map(|dir| dir.join("listings").join(name))
+}
+
+/// The file listing the pinned items
+pub fn pins_file() -> Option<PathBuf> {
+    state_dir().map(|dir| dir.join("pins.txt"))
+}
+
+/// The file listing every selection accepted from `source`, for the recent ones.
+/// Each root, command, etc. has its own, since their paths mean nothing to the others.
+pub fn history_file(source: &str) -> Option<PathBuf> {
+    let name = source.replace(['/', '\\', ':'], "%");
+    state_dir().map(|dir| dir.join("history").join(format!("{name}.jsonl")))
+}
+
+/// The file remembering the context of the last selection, for `--resume`
+pub fn last_context_file() -> Option<PathBuf> {
+    state_dir().map(|dir| dir.join("last_context.txt"))
+}
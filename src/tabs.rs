@@ -0,0 +1,60 @@
+use crate::{backend::ItemKind, levels::LevelState, undo::UndoStack};
+
+/// The pages the results can be split into, one per type of entity
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Tab {
+    #[default]
+    Shots,
+    Assets,
+    Renders,
+}
+
+impl Tab {
+    pub const ALL: [Tab; 3] = [Tab::Shots, Tab::Assets, Tab::Renders];
+
+    pub fn title(&self) -> &'static str {
+        match self {
+            Tab::Shots => "Shots",
+            Tab::Assets => "Assets",
+            Tab::Renders => "Renders",
+        }
+    }
+
+    /// Whether items of this kind are listed in this tab.
+    /// Projects lead to every kind of entity, so they're in all of them.
+    pub fn accepts(&self, kind: ItemKind) -> bool {
+        match self {
+            Tab::Shots => !matches!(kind, ItemKind::Asset | ItemKind::Render),
+            Tab::Assets => matches!(kind, ItemKind::Project | ItemKind::Asset),
+            Tab::Renders => matches!(kind, ItemKind::Project | ItemKind::Render),
+        }
+    }
+}
+
+/// What a tab remembers while another one is shown
+#[derive(Debug, Default)]
+pub struct TabState {
+    pub path: Vec<String>,
+    /// What the levels above the current one were showing, when known
+    pub levels: Vec<Option<LevelState>>,
+    pub query: String,
+    pub query_undo: UndoStack,
+    /// Index of the highlighted result
+    pub selected: Option<usize>,
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_tabs_split_entity_kinds() {
+        assert!(Tab::ALL.iter().all(|tab| tab.accepts(ItemKind::Project)));
+        assert!(Tab::Shots.accepts(ItemKind::Sequence));
+        assert!(!Tab::Shots.accepts(ItemKind::Asset));
+        assert!(Tab::Assets.accepts(ItemKind::Asset));
+        assert!(!Tab::Assets.accepts(ItemKind::Shot));
+        assert!(Tab::Renders.accepts(ItemKind::Render));
+    }
+}
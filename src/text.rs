@@ -0,0 +1,158 @@
+use ratatui::symbols::border;
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+/// Removes the last user-perceived character from `text`: a whole emoji
+/// sequence or a letter with its combining accents, not just a code point.
+pub fn pop_grapheme(text: &mut String) -> Option<String> {
+    let (start, grapheme) = text.grapheme_indices(true).next_back()?;
+    let grapheme = grapheme.to_string();
+    text.truncate(start);
+    Some(grapheme)
+}
+
+/// How many terminal columns `text` takes (CJK and most emojis take two)
+pub fn width(text: &str) -> usize {
+    UnicodeWidthStr::width(text)
+}
+
+/// Returns the longest prefix of `text` that fits in `max_width` columns,
+/// without ever splitting a grapheme
+pub fn truncate_to_width(text: &str, max_width: usize) -> &str {
+    let mut used = 0;
+    for (start, grapheme) in text.grapheme_indices(true) {
+        used += width(grapheme);
+        if used > max_width {
+            return &text[..start];
+        }
+    }
+
+    text
+}
+
+/// Returns what's left of `text` once its first `columns` columns are skipped
+pub fn skip_width(text: &str, columns: usize) -> &str {
+    let mut skipped = 0;
+    for (start, grapheme) in text.grapheme_indices(true) {
+        if skipped >= columns {
+            return &text[start..];
+        }
+        skipped += width(grapheme);
+    }
+
+    ""
+}
+
+/// Which part of a text is visible once clipped, see [`clip_range`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Clipped {
+    /// Whether an ellipsis goes before the visible part
+    pub leading: bool,
+    /// Byte range of the visible part
+    pub visible: std::ops::Range<usize>,
+    /// Whether an ellipsis goes after the visible part
+    pub trailing: bool,
+}
+
+/// Works out what part of `text` fits in `max_width` columns, after scrolling
+/// `offset` columns into it, leaving room for an `ellipsis` on the hidden sides.
+pub fn clip_range(text: &str, offset: usize, max_width: usize, ellipsis: &str) -> Clipped {
+    let visible = skip_width(text, offset);
+    let start = text.len() - visible.len();
+    let leading = offset > 0 && !visible.is_empty();
+    let leading_width = if leading { width(ellipsis) } else { 0 };
+
+    if leading_width + width(visible) <= max_width {
+        return Clipped {
+            leading,
+            visible: start..text.len(),
+            trailing: false,
+        };
+    }
+
+    // Not even room for the ellipses, so just cut
+    let room = max_width.saturating_sub(leading_width + width(ellipsis));
+    if room == 0 {
+        let visible = truncate_to_width(visible, max_width);
+        return Clipped {
+            leading: false,
+            visible: start..start + visible.len(),
+            trailing: false,
+        };
+    }
+
+    let visible = truncate_to_width(visible, room);
+    Clipped {
+        leading,
+        visible: start..start + visible.len(),
+        trailing: true,
+    }
+}
+
+/// Block borders drawn with `+`, `-` and `|`, for terminals that can't draw
+/// box-drawing symbols
+pub const ASCII_BORDERS: border::Set = border::Set {
+    top_left: "+",
+    top_right: "+",
+    bottom_left: "+",
+    bottom_right: "+",
+    vertical_left: "|",
+    vertical_right: "|",
+    horizontal_top: "-",
+    horizontal_bottom: "-",
+};
+
+/// The borders of the app's blocks: `lines`, or [`ASCII_BORDERS`] without unicode
+pub fn borders(unicode: bool, lines: border::Set) -> border::Set {
+    if unicode {
+        lines
+    } else {
+        ASCII_BORDERS
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    /// Clips `text` the way the list draws it, as a plain string
+    fn clip(text: &str, offset: usize, max_width: usize, ellipsis: &str) -> String {
+        let clipped = clip_range(text, offset, max_width, ellipsis);
+        let leading = if clipped.leading { ellipsis } else { "" };
+        let trailing = if clipped.trailing { ellipsis } else { "" };
+
+        format!("{leading}{}{trailing}", &text[clipped.visible])
+    }
+
+    #[test]
+    fn test_pop_grapheme() {
+        // An 'e' followed by a combining acute accent, then a family emoji
+        let mut text = "cafe\u{301} 👨‍👩‍👧".to_string();
+        assert_eq!(pop_grapheme(&mut text).as_deref(), Some("👨‍👩‍👧"));
+        assert_eq!(pop_grapheme(&mut text).as_deref(), Some(" "));
+        assert_eq!(pop_grapheme(&mut text).as_deref(), Some("e\u{301}"));
+        assert_eq!(text, "caf");
+
+        let mut empty = String::new();
+        assert_eq!(pop_grapheme(&mut empty), None);
+    }
+
+    #[test]
+    fn test_truncate_to_width() {
+        assert_eq!(width("項目_001"), 8);
+        assert_eq!(truncate_to_width("項目_001", 3), "項");
+        assert_eq!(truncate_to_width("項目_001", 4), "項目");
+        assert_eq!(truncate_to_width("sh010", 10), "sh010");
+    }
+
+    #[test]
+    fn test_clip() {
+        assert_eq!(clip("sequence_001", 0, 20, "…"), "sequence_001");
+        assert_eq!(clip("sequence_001", 0, 8, "…"), "sequenc…");
+        assert_eq!(clip("sequence_001", 4, 8, "…"), "…ence_0…");
+        assert_eq!(clip("sequence_001", 9, 8, "…"), "…001");
+        assert_eq!(clip("sequence_001", 0, 2, "..."), "se");
+        assert_eq!(skip_width("項目_001", 2), "目_001");
+    }
+}
@@ -0,0 +1,487 @@
+use std::str::FromStr;
+
+use ratatui::style::{Color, Modifier, Style};
+
+use crate::backend::{ItemKind, Status};
+
+/// All the styles used to draw the UI, in one place
+#[derive(Debug, Clone)]
+pub struct Theme {
+    pub title: Style,
+    pub query: Style,
+    /// The pointer in the gutter of the highlighted row
+    pub pointer: Style,
+    pub highlight: Style,
+    pub pin: Style,
+    pub mark: Style,
+    pub archived: Style,
+    pub create: Style,
+    pub error: Style,
+    pub project: Style,
+    pub sequence: Style,
+    pub shot: Style,
+    pub asset: Style,
+    pub render: Style,
+    pub wip: Style,
+    pub review: Style,
+    pub approved: Style,
+    pub omitted: Style,
+    /// The keys in the hints at the bottom of the list and the panels
+    pub key: Style,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self {
+            title: Style::new().add_modifier(Modifier::BOLD),
+            query: Style::new().fg(Color::Yellow),
+            pointer: Style::new().fg(Color::Magenta).add_modifier(Modifier::BOLD),
+            highlight: Style::new().fg(Color::Magenta).add_modifier(Modifier::BOLD),
+            pin: Style::new().fg(Color::Red),
+            mark: Style::new().fg(Color::Magenta).add_modifier(Modifier::BOLD),
+            archived: Style::new().add_modifier(Modifier::DIM),
+            create: Style::new().fg(Color::Green).add_modifier(Modifier::ITALIC),
+            error: Style::new().fg(Color::Red),
+            project: Style::new().fg(Color::Blue),
+            sequence: Style::new().fg(Color::Cyan),
+            shot: Style::new().fg(Color::Green),
+            asset: Style::new().fg(Color::Yellow),
+            render: Style::new().fg(Color::LightMagenta),
+            wip: Style::new().fg(Color::LightBlue),
+            review: Style::new().fg(Color::LightYellow),
+            approved: Style::new().fg(Color::LightGreen),
+            omitted: Style::new()
+                .fg(Color::DarkGray)
+                .add_modifier(Modifier::CROSSED_OUT),
+            key: Style::new().fg(Color::Blue).add_modifier(Modifier::BOLD),
+        }
+    }
+}
+
+/// The themes that come built in, see `--theme`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ThemeName {
+    /// For dark backgrounds
+    #[default]
+    Default,
+    /// For light backgrounds
+    Light,
+    /// Bright colors, and what's highlighted or marked stands out without them too
+    HighContrast,
+}
+
+impl From<ThemeName> for Theme {
+    fn from(name: ThemeName) -> Self {
+        match name {
+            ThemeName::Default => Theme::default(),
+            ThemeName::Light => Theme::light(),
+            ThemeName::HighContrast => Theme::high_contrast(),
+        }
+    }
+}
+
+/// The names of the styles of a [`Theme`], as written in the config
+const STYLE_NAMES: [&str; 19] = [
+    "title",
+    "query",
+    "pointer",
+    "highlight",
+    "pin",
+    "mark",
+    "archived",
+    "create",
+    "error",
+    "project",
+    "sequence",
+    "shot",
+    "asset",
+    "render",
+    "wip",
+    "review",
+    "approved",
+    "omitted",
+    "key",
+];
+
+impl Theme {
+    /// Darker colors, readable on a light background
+    pub fn light() -> Self {
+        Self {
+            query: Style::new().fg(Color::Blue),
+            highlight: Style::new().fg(Color::Magenta).add_modifier(Modifier::BOLD),
+            asset: Style::new().fg(Color::Rgb(175, 95, 0)),
+            render: Style::new().fg(Color::Magenta),
+            wip: Style::new().fg(Color::Blue),
+            review: Style::new().fg(Color::Rgb(175, 95, 0)),
+            approved: Style::new().fg(Color::Green),
+            omitted: Style::new()
+                .fg(Color::Gray)
+                .add_modifier(Modifier::CROSSED_OUT),
+            ..Self::default()
+        }
+    }
+
+    /// Bright colors on top of bold, underline and reverse video, so nothing
+    /// is told apart by its color only
+    pub fn high_contrast() -> Self {
+        let bold = Style::new().add_modifier(Modifier::BOLD);
+        Self {
+            title: bold.add_modifier(Modifier::UNDERLINED),
+            query: bold.fg(Color::White),
+            pointer: bold.fg(Color::White),
+            highlight: bold.add_modifier(Modifier::REVERSED | Modifier::UNDERLINED),
+            pin: bold.fg(Color::LightRed),
+            mark: bold
+                .fg(Color::LightMagenta)
+                .add_modifier(Modifier::UNDERLINED),
+            archived: Style::new().fg(Color::Gray).add_modifier(Modifier::ITALIC),
+            create: bold
+                .fg(Color::LightGreen)
+                .add_modifier(Modifier::UNDERLINED),
+            error: bold.fg(Color::LightRed).add_modifier(Modifier::UNDERLINED),
+            project: bold.fg(Color::LightBlue),
+            sequence: bold.fg(Color::LightCyan),
+            shot: bold.fg(Color::LightGreen),
+            asset: bold.fg(Color::LightYellow),
+            render: bold.fg(Color::LightMagenta),
+            wip: Style::new().fg(Color::LightCyan),
+            review: bold.fg(Color::LightYellow),
+            approved: bold
+                .fg(Color::LightGreen)
+                .add_modifier(Modifier::UNDERLINED),
+            omitted: Style::new()
+                .fg(Color::White)
+                .add_modifier(Modifier::CROSSED_OUT),
+            key: bold.fg(Color::LightBlue),
+        }
+    }
+
+    /// Replaces the style called `name` (like a field of [`Theme`])
+    /// with one written like `"bold yellow on blue"`, see [`parse_style`]
+    pub fn set(&mut self, name: &str, style: &str) -> Result<(), String> {
+        let field = self
+            .style_mut(name)
+            .ok_or_else(|| format!("unknown theme style {name:?}"))?;
+        *field = parse_style(style)?;
+        Ok(())
+    }
+
+    /// Brings the colors down to what the terminal can show. Without any,
+    /// the highlighted row is drawn in reverse video instead.
+    pub fn with_depth(mut self, depth: ColorDepth) -> Self {
+        for name in STYLE_NAMES {
+            let style = self.style_mut(name).expect("every style has a name");
+            *style = Style {
+                fg: style.fg.and_then(|color| depth.convert(color)),
+                bg: style.bg.and_then(|color| depth.convert(color)),
+                ..*style
+            };
+        }
+        if depth == ColorDepth::None {
+            self.highlight = self.highlight.add_modifier(Modifier::REVERSED);
+        }
+        self
+    }
+
+    fn style_mut(&mut self, name: &str) -> Option<&mut Style> {
+        let field = match name {
+            "title" => &mut self.title,
+            "query" => &mut self.query,
+            // What the gutter used to show, before the pointer
+            "pointer" | "index" => &mut self.pointer,
+            "highlight" => &mut self.highlight,
+            "pin" => &mut self.pin,
+            "mark" => &mut self.mark,
+            "archived" => &mut self.archived,
+            "create" => &mut self.create,
+            "error" => &mut self.error,
+            "project" => &mut self.project,
+            "sequence" => &mut self.sequence,
+            "shot" => &mut self.shot,
+            "asset" => &mut self.asset,
+            "render" => &mut self.render,
+            "wip" => &mut self.wip,
+            "review" => &mut self.review,
+            "approved" => &mut self.approved,
+            "omitted" => &mut self.omitted,
+            "key" => &mut self.key,
+            _ => return None,
+        };
+        Some(field)
+    }
+
+    /// The style of the badge/icon drawn next to an item of the given kind
+    pub fn kind_style(&self, kind: ItemKind) -> Style {
+        match kind {
+            ItemKind::Project => self.project,
+            ItemKind::Sequence => self.sequence,
+            ItemKind::Shot => self.shot,
+            ItemKind::Asset => self.asset,
+            ItemKind::Render => self.render,
+            ItemKind::Line => Style::new(),
+        }
+    }
+
+    /// The style of the status badge drawn after an item's name
+    pub fn status_style(&self, status: Status) -> Style {
+        match status {
+            Status::Wip => self.wip,
+            Status::Review => self.review,
+            Status::Approved => self.approved,
+            Status::Omitted => self.omitted,
+        }
+    }
+}
+
+/// Whether to draw colors, see `--color`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ColorChoice {
+    /// When the terminal can and `NO_COLOR` isn't set
+    #[default]
+    Auto,
+    /// Always, as configured
+    Always,
+    /// Never: only bold, reversed, etc.
+    Never,
+}
+
+impl ColorChoice {
+    /// How many colors to draw with, looking at the terminal through `var` when
+    /// it's up to it. `var` gets environment variables, like [`std::env::var`].
+    pub fn depth(&self, var: impl Fn(&str) -> Option<String>) -> ColorDepth {
+        match self {
+            ColorChoice::Auto => ColorDepth::detect(var),
+            ColorChoice::Always => ColorDepth::TrueColor,
+            ColorChoice::Never => ColorDepth::None,
+        }
+    }
+}
+
+/// How many colors the terminal can show
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorDepth {
+    None,
+    Ansi16,
+    Ansi256,
+    TrueColor,
+}
+
+impl ColorDepth {
+    /// Guesses from `NO_COLOR` (see <https://no-color.org>), `COLORTERM` and `TERM`
+    pub fn detect(var: impl Fn(&str) -> Option<String>) -> Self {
+        if var("NO_COLOR").is_some_and(|value| !value.is_empty()) {
+            return ColorDepth::None;
+        }
+        if var("COLORTERM").is_some_and(|value| value == "truecolor" || value == "24bit") {
+            return ColorDepth::TrueColor;
+        }
+        match var("TERM").as_deref() {
+            Some("dumb") => ColorDepth::None,
+            Some(term) if term.contains("256color") => ColorDepth::Ansi256,
+            _ => ColorDepth::Ansi16,
+        }
+    }
+
+    /// The closest color to `color` that can be shown, if any
+    fn convert(&self, color: Color) -> Option<Color> {
+        match (self, color) {
+            (ColorDepth::None, _) => None,
+            (ColorDepth::TrueColor, _) | (_, Color::Reset) => Some(color),
+            (ColorDepth::Ansi256, Color::Rgb(r, g, b)) => Some(Color::Indexed(to_cube(r, g, b))),
+            (ColorDepth::Ansi256, _) => Some(color),
+            (ColorDepth::Ansi16, Color::Rgb(r, g, b)) => Some(nearest_ansi16((r, g, b))),
+            (ColorDepth::Ansi16, Color::Indexed(index)) if index >= 16 => {
+                Some(nearest_ansi16(indexed_to_rgb(index)))
+            }
+            (ColorDepth::Ansi16, _) => Some(color),
+        }
+    }
+}
+
+/// The 16 colors every terminal has, with roughly how xterm draws them
+const ANSI16: [(Color, (u8, u8, u8)); 16] = [
+    (Color::Black, (0, 0, 0)),
+    (Color::Red, (205, 0, 0)),
+    (Color::Green, (0, 205, 0)),
+    (Color::Yellow, (205, 205, 0)),
+    (Color::Blue, (0, 0, 238)),
+    (Color::Magenta, (205, 0, 205)),
+    (Color::Cyan, (0, 205, 205)),
+    (Color::Gray, (229, 229, 229)),
+    (Color::DarkGray, (127, 127, 127)),
+    (Color::LightRed, (255, 0, 0)),
+    (Color::LightGreen, (0, 255, 0)),
+    (Color::LightYellow, (255, 255, 0)),
+    (Color::LightBlue, (92, 92, 255)),
+    (Color::LightMagenta, (255, 0, 255)),
+    (Color::LightCyan, (0, 255, 255)),
+    (Color::White, (255, 255, 255)),
+];
+
+fn nearest_ansi16((r, g, b): (u8, u8, u8)) -> Color {
+    let distance = |(cr, cg, cb): (u8, u8, u8)| {
+        let d = |a: u8, b: u8| (i32::from(a) - i32::from(b)).pow(2);
+        d(r, cr) + d(g, cg) + d(b, cb)
+    };
+    ANSI16
+        .iter()
+        .min_by_key(|(_, rgb)| distance(*rgb))
+        .map(|(color, _)| *color)
+        .expect("there are 16 colors")
+}
+
+/// The index of the closest color in the 6x6x6 cube of the 256 color palette
+fn to_cube(r: u8, g: u8, b: u8) -> u8 {
+    let level = |c: u8| {
+        (0..6)
+            .min_by_key(|l| (i32::from(cube_level(*l)) - i32::from(c)).abs())
+            .expect("there are 6 levels")
+    };
+    16 + 36 * level(r) + 6 * level(g) + level(b)
+}
+
+/// The intensity of a level of the color cube
+fn cube_level(level: u8) -> u8 {
+    if level == 0 {
+        0
+    } else {
+        55 + 40 * level
+    }
+}
+
+/// Roughly how a color of the 256 color palette is drawn
+fn indexed_to_rgb(index: u8) -> (u8, u8, u8) {
+    match index {
+        0..16 => ANSI16[usize::from(index)].1,
+        16..232 => {
+            let cube = index - 16;
+            (
+                cube_level(cube / 36),
+                cube_level(cube / 6 % 6),
+                cube_level(cube % 6),
+            )
+        }
+        _ => {
+            let gray = 8 + 10 * (index - 232);
+            (gray, gray, gray)
+        }
+    }
+}
+
+/// Parses styles written as words, like `"bold yellow"`, `"#ff8800 on black"`
+/// or `"dim italic"`. Colors are the ones ratatui knows by name, hex or index.
+pub fn parse_style(text: &str) -> Result<Style, String> {
+    let mut style = Style::new();
+    let mut words = text.split_whitespace();
+    while let Some(word) = words.next() {
+        let modifier = match word.to_ascii_lowercase().as_str() {
+            "bold" => Some(Modifier::BOLD),
+            "dim" => Some(Modifier::DIM),
+            "italic" => Some(Modifier::ITALIC),
+            "underlined" => Some(Modifier::UNDERLINED),
+            "reversed" => Some(Modifier::REVERSED),
+            "crossed_out" => Some(Modifier::CROSSED_OUT),
+            _ => None,
+        };
+        if let Some(modifier) = modifier {
+            style = style.add_modifier(modifier);
+        } else if word == "on" {
+            let color = words
+                .next()
+                .ok_or_else(|| format!("missing background color after \"on\" in {text:?}"))?;
+            style = style.bg(parse_color(color)?);
+        } else {
+            style = style.fg(parse_color(word)?);
+        }
+    }
+
+    Ok(style)
+}
+
+fn parse_color(word: &str) -> Result<Color, String> {
+    Color::from_str(word).map_err(|_| format!("unknown color or modifier {word:?}"))
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_parse_style() {
+        assert_eq!(
+            parse_style("bold yellow on #000080"),
+            Ok(Style::new()
+                .fg(Color::Yellow)
+                .bg(Color::Rgb(0, 0, 128))
+                .add_modifier(Modifier::BOLD))
+        );
+        assert_eq!(parse_style(""), Ok(Style::new()));
+        assert!(parse_style("sparkly").is_err());
+        assert!(parse_style("red on").is_err());
+
+        let mut theme = Theme::default();
+        assert!(theme.set("highlight", "reversed").is_ok());
+        assert_eq!(
+            theme.highlight,
+            Style::new().add_modifier(Modifier::REVERSED)
+        );
+        assert!(theme.set("background", "red").is_err());
+    }
+
+    #[test]
+    fn test_high_contrast_does_not_rely_on_colors() {
+        let theme = Theme::from(ThemeName::HighContrast).with_depth(ColorDepth::None);
+        assert!(theme.highlight.add_modifier.contains(Modifier::REVERSED));
+        for style in [theme.highlight, theme.mark, theme.create, theme.error] {
+            assert!(style.add_modifier.contains(Modifier::UNDERLINED));
+        }
+    }
+
+    #[test]
+    fn test_color_depth() {
+        let env = |vars: &'static [(&str, &str)]| {
+            move |name: &str| {
+                vars.iter()
+                    .find(|(var, _)| *var == name)
+                    .map(|(_, value)| value.to_string())
+            }
+        };
+        let detect = |vars| ColorDepth::detect(env(vars));
+        assert_eq!(detect(&[("TERM", "xterm-256color")]), ColorDepth::Ansi256);
+        assert_eq!(
+            detect(&[("TERM", "xterm"), ("COLORTERM", "truecolor")]),
+            ColorDepth::TrueColor
+        );
+        assert_eq!(detect(&[("TERM", "xterm")]), ColorDepth::Ansi16);
+        assert_eq!(detect(&[("TERM", "dumb")]), ColorDepth::None);
+        assert_eq!(
+            detect(&[("TERM", "xterm-256color"), ("NO_COLOR", "1")]),
+            ColorDepth::None
+        );
+        assert_eq!(
+            ColorChoice::Always.depth(env(&[("NO_COLOR", "1")])),
+            ColorDepth::TrueColor
+        );
+
+        let orange = Color::Rgb(255, 135, 0);
+        assert_eq!(
+            ColorDepth::Ansi256.convert(orange),
+            Some(Color::Indexed(208))
+        );
+        assert_eq!(ColorDepth::Ansi16.convert(orange), Some(Color::Yellow));
+        assert_eq!(
+            ColorDepth::Ansi16.convert(Color::Indexed(208)),
+            Some(Color::Yellow)
+        );
+
+        let theme = Theme::default().with_depth(ColorDepth::None);
+        assert_eq!(
+            theme.highlight,
+            Style::new().add_modifier(Modifier::BOLD | Modifier::REVERSED)
+        );
+        assert_eq!(theme.omitted.fg, None);
+    }
+}
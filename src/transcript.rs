@@ -0,0 +1,175 @@
+use std::{
+    collections::VecDeque,
+    fs::File,
+    io::{BufRead, BufReader, Write},
+    path::Path,
+    time::{Duration, Instant},
+};
+
+use color_eyre::eyre::WrapErr;
+use serde::{Deserialize, Serialize};
+
+use crate::action::InputEvent;
+
+/// What the app looked like after an input, to tell when a replay goes differently
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub path: Vec<String>,
+    pub query: String,
+    pub matches: usize,
+    /// The name of the highlighted item
+    pub highlighted: Option<String>,
+    pub message: Option<String>,
+}
+
+/// One line of a transcript: an input, when it came and what it led to
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Entry {
+    /// Milliseconds since the app started
+    pub at_ms: u64,
+    pub input: InputEvent,
+    pub state: Snapshot,
+}
+
+/// Writes every input of a session to a file as JSON lines, see `--record`
+#[derive(Debug)]
+pub struct Recorder {
+    file: File,
+    start: Instant,
+}
+
+impl Recorder {
+    pub fn create(file: &Path) -> color_eyre::Result<Self> {
+        Ok(Self {
+            file: File::create(file)
+                .wrap_err_with(|| format!("failed to create the transcript {file:?}"))?,
+            start: Instant::now(),
+        })
+    }
+
+    /// Appends a line right away, so the transcript is there even if the app crashes
+    pub fn record(&mut self, input: &InputEvent, state: Snapshot) -> color_eyre::Result<()> {
+        let entry = Entry {
+            at_ms: self.start.elapsed().as_millis() as u64,
+            input: input.clone(),
+            state,
+        };
+        writeln!(self.file, "{}", serde_json::to_string(&entry)?)
+            .wrap_err("failed to write to the transcript")
+    }
+}
+
+/// What to do next while replaying, see [`Replay::next`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ReplayStep {
+    /// Handle this input now
+    Input(Entry),
+    /// The next input isn't due before this long
+    Wait(Duration),
+    Done,
+}
+
+/// Plays a transcript back, with the same pauses between inputs, see `--replay`
+#[derive(Debug)]
+pub struct Replay {
+    entries: VecDeque<Entry>,
+    start: Instant,
+    /// How many times faster than recorded. Not positive means without pauses.
+    speed: f64,
+}
+
+impl Replay {
+    pub fn load(file: &Path, speed: f64) -> color_eyre::Result<Self> {
+        let reader = BufReader::new(
+            File::open(file).wrap_err_with(|| format!("failed to open the transcript {file:?}"))?,
+        );
+        let mut entries = VecDeque::new();
+        for (number, line) in reader.lines().enumerate() {
+            let line = line.wrap_err_with(|| format!("failed to read the transcript {file:?}"))?;
+            let entry = serde_json::from_str(&line)
+                .wrap_err_with(|| format!("bad entry on line {} of {file:?}", number + 1))?;
+            entries.push_back(entry);
+        }
+        Ok(Self::new(entries, speed))
+    }
+
+    pub fn new(entries: impl IntoIterator<Item = Entry>, speed: f64) -> Self {
+        Self {
+            entries: entries.into_iter().collect(),
+            start: Instant::now(),
+            speed,
+        }
+    }
+
+    pub fn next(&mut self, now: Instant) -> ReplayStep {
+        let Some(entry) = self.entries.front() else {
+            return ReplayStep::Done;
+        };
+        // No pauses at 0, nor for speeds that make no sense, like negative ones
+        let delay = Duration::from_millis(entry.at_ms).as_secs_f64() / self.speed;
+        let due = Duration::try_from_secs_f64(delay)
+            .ok()
+            .and_then(|delay| self.start.checked_add(delay))
+            .unwrap_or(self.start);
+        match due.checked_duration_since(now) {
+            Some(wait) if !wait.is_zero() => ReplayStep::Wait(wait),
+            _ => ReplayStep::Input(self.entries.pop_front().unwrap()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::action::Action;
+
+    #[test]
+    fn test_record_and_replay() -> color_eyre::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let file = dir.path().join("transcript.jsonl");
+        let mut recorder = Recorder::create(&file)?;
+        let state = Snapshot {
+            query: "b".to_string(),
+            ..Snapshot::default()
+        };
+        recorder.record(&InputEvent::Action(Action::Insert('b')), state.clone())?;
+        recorder.record(&InputEvent::Paste("ee".into()), Snapshot::default())?;
+
+        let mut replay = Replay::load(&file, 0.0)?;
+        let ReplayStep::Input(entry) = replay.next(Instant::now()) else {
+            panic!("the first input should be due");
+        };
+        assert_eq!(entry.input, InputEvent::Action(Action::Insert('b')));
+        assert_eq!(entry.state, state);
+        assert!(matches!(replay.next(Instant::now()), ReplayStep::Input(_)));
+        assert_eq!(replay.next(Instant::now()), ReplayStep::Done);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_replay_speed() {
+        let entry = Entry {
+            at_ms: 1000,
+            input: InputEvent::Action(Action::MoveDown),
+            state: Snapshot::default(),
+        };
+        let mut replay = Replay::new([entry.clone()], 4.0);
+        let start = replay.start;
+        assert_eq!(
+            replay.next(start + Duration::from_millis(100)),
+            ReplayStep::Wait(Duration::from_millis(150))
+        );
+        assert!(matches!(
+            replay.next(start + Duration::from_millis(250)),
+            ReplayStep::Input(_)
+        ));
+
+        for speed in [0.0, -1.0, f64::NAN] {
+            let mut replay = Replay::new([entry.clone()], speed);
+            let start = replay.start;
+            assert!(matches!(replay.next(start), ReplayStep::Input(_)));
+        }
+    }
+}
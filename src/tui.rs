@@ -1,19 +1,184 @@
-use std::io::{self, stdout, Stdout};
+use std::{
+    io::{self, stdout, Stdout, Write},
+    sync::atomic::{AtomicBool, Ordering},
+};
 
-use crossterm::{execute, terminal::*};
+use crossterm::{
+    event::{
+        DisableBracketedPaste, DisableFocusChange, DisableMouseCapture, EnableBracketedPaste,
+        EnableFocusChange, EnableMouseCapture, KeyboardEnhancementFlags,
+        PopKeyboardEnhancementFlags, PushKeyboardEnhancementFlags,
+    },
+    execute,
+    terminal::*,
+};
 use ratatui::prelude::*;
 
+use crate::graphics::base64;
+
+#[cfg(all(feature = "termion", feature = "termwiz"))]
+compile_error!("the `termion` and `termwiz` features can't be enabled together");
+
+/// The library drawing to the terminal, picked with cargo features (crossterm by default).
+/// Input is read with crossterm whatever the backend.
+#[cfg(not(any(feature = "termion", feature = "termwiz")))]
+pub type TerminalBackend = CrosstermBackend<Stdout>;
+#[cfg(feature = "termion")]
+pub type TerminalBackend = ratatui::backend::TermionBackend<Stdout>;
+#[cfg(feature = "termwiz")]
+pub type TerminalBackend = ratatui::backend::TermwizBackend;
+
 /// A type alias for the terminal type used by the app
-pub type Tui = Terminal<CrosstermBackend<Stdout>>;
+pub type Tui = Terminal<TerminalBackend>;
 
-pub fn init() -> io::Result<Tui> {
-    execute!(stdout(), EnterAlternateScreen)?;
-    enable_raw_mode()?;
-    Terminal::new(CrosstermBackend::new(stdout()))
+/// How many rows the app takes below the prompt when not using the alternate screen
+const INLINE_HEIGHT: u16 = 20;
+
+/// Whether the keyboard enhancement flags were pushed, to pop them when restoring
+static KEYBOARD_ENHANCED: AtomicBool = AtomicBool::new(false);
+
+/// How the terminal is set up for the app, and torn down afterwards
+#[derive(Debug, Clone, Copy)]
+pub struct Options {
+    /// Take over the whole screen and give it back untouched on exit.
+    /// Otherwise the app is drawn inline, below the current prompt.
+    pub alternate_screen: bool,
+    /// Receive mouse events
+    pub mouse: bool,
+    /// Receive pasted text as a single event, see `App::handle_paste`
+    pub paste: bool,
+    /// Be told when the terminal gains or loses focus, to pause in the background
+    pub focus: bool,
+    /// Use the kitty keyboard protocol when the terminal supports it, so chords like
+    /// Ctrl+Enter, Shift+Enter or Ctrl+I (told apart from Tab) can be bound
+    pub keyboard_enhancement: bool,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            alternate_screen: true,
+            mouse: false,
+            paste: true,
+            focus: true,
+            keyboard_enhancement: true,
+        }
+    }
+}
+
+pub fn init(options: Options) -> io::Result<Tui> {
+    resume(options)?;
+
+    let backend = new_backend()?;
+    if options.alternate_screen {
+        Terminal::new(backend)
+    } else {
+        Terminal::with_options(
+            backend,
+            TerminalOptions {
+                viewport: Viewport::Inline(INLINE_HEIGHT),
+            },
+        )
+    }
+}
+
+#[cfg(not(any(feature = "termion", feature = "termwiz")))]
+fn new_backend() -> io::Result<TerminalBackend> {
+    Ok(CrosstermBackend::new(stdout()))
+}
+
+#[cfg(feature = "termion")]
+fn new_backend() -> io::Result<TerminalBackend> {
+    Ok(ratatui::backend::TermionBackend::new(stdout()))
+}
+
+/// Termwiz sets up its own terminal, always on the alternate screen
+#[cfg(feature = "termwiz")]
+fn new_backend() -> io::Result<TerminalBackend> {
+    ratatui::backend::TermwizBackend::new().map_err(|e| io::Error::other(e.to_string()))
 }
 
-pub fn restore() -> io::Result<()> {
-    execute!(stdout(), LeaveAlternateScreen)?;
-    disable_raw_mode()?;
+/// Sets the terminal up for the app again, after [`suspend`]
+pub fn resume(options: Options) -> io::Result<()> {
+    #[cfg(windows)]
+    enable_virtual_terminal();
+    enable_raw_mode()?;
+    if options.alternate_screen {
+        execute!(stdout(), EnterAlternateScreen)?;
+    }
+    if options.paste {
+        execute!(stdout(), EnableBracketedPaste)?;
+    }
+    if options.mouse {
+        execute!(stdout(), EnableMouseCapture)?;
+    }
+    if options.focus {
+        execute!(stdout(), EnableFocusChange)?;
+    }
+    // Terminals without support don't answer the query, or don't know it at all
+    if options.keyboard_enhancement && supports_keyboard_enhancement().unwrap_or(false) {
+        execute!(
+            stdout(),
+            PushKeyboardEnhancementFlags(KeyboardEnhancementFlags::DISAMBIGUATE_ESCAPE_CODES)
+        )?;
+        KEYBOARD_ENHANCED.store(true, Ordering::Relaxed);
+    }
     Ok(())
 }
+
+/// Puts `text` in the system clipboard with the OSC 52 escape sequence,
+/// which works over SSH too. Terminals not supporting it ignore it.
+pub fn copy_to_clipboard(out: &mut impl Write, text: &str) -> io::Result<()> {
+    write!(out, "\x1b]52;c;{}\x07", base64(text.as_bytes()))?;
+    out.flush()
+}
+
+/// Starts or stops receiving mouse events, when the app receives them at all.
+/// While stopped, the terminal selects text on click and drag as usual.
+pub fn set_mouse_capture(options: Options, enabled: bool) -> io::Result<()> {
+    match (options.mouse, enabled) {
+        (false, _) => Ok(()),
+        (true, true) => execute!(stdout(), EnableMouseCapture),
+        (true, false) => execute!(stdout(), DisableMouseCapture),
+    }
+}
+
+/// Hands the terminal over to another program, until [`resume`]
+pub fn suspend(options: Options) -> io::Result<()> {
+    restore(options)
+}
+
+/// Gives the terminal back the way it was. Every step is attempted even when
+/// one fails (e.g. on consoles not knowing about focus changes), so the shell
+/// isn't left in raw mode or on the alternate screen. The first error is returned.
+pub fn restore(options: Options) -> io::Result<()> {
+    let steps = [
+        KEYBOARD_ENHANCED
+            .swap(false, Ordering::Relaxed)
+            .then(|| execute!(stdout(), PopKeyboardEnhancementFlags)),
+        options
+            .focus
+            .then(|| execute!(stdout(), DisableFocusChange)),
+        options
+            .mouse
+            .then(|| execute!(stdout(), DisableMouseCapture)),
+        options
+            .paste
+            .then(|| execute!(stdout(), DisableBracketedPaste)),
+        options
+            .alternate_screen
+            .then(|| execute!(stdout(), LeaveAlternateScreen)),
+        Some(disable_raw_mode()),
+    ];
+    steps.into_iter().flatten().collect()
+}
+
+/// Turns on escape sequences in the Windows console (conhost), where they're
+/// off unless asked for. Windows Terminal has them on already. Without them,
+/// crossterm falls back to the console API, which can't draw everything.
+#[cfg(windows)]
+fn enable_virtual_terminal() {
+    if !crossterm::ansi_support::supports_ansi() {
+        tracing::warn!("This console doesn't support escape sequences, colors may be off");
+    }
+}
@@ -0,0 +1,62 @@
+/// The most edits we remember, so a long session doesn't grow without bounds
+const MAX_UNDO: usize = 200;
+
+/// Keeps the previous versions of a text, so edits can be undone and redone
+#[derive(Debug, Default)]
+pub struct UndoStack {
+    undo: Vec<String>,
+    redo: Vec<String>,
+}
+
+impl UndoStack {
+    /// Records the text as it was before an edit. Any redo history is lost.
+    pub fn record(&mut self, previous: &str) {
+        if self.undo.len() == MAX_UNDO {
+            self.undo.remove(0);
+        }
+        self.undo.push(previous.to_string());
+        self.redo.clear();
+    }
+
+    /// Returns the text as it was before the last edit, if any
+    pub fn undo(&mut self, current: &str) -> Option<String> {
+        let previous = self.undo.pop()?;
+        self.redo.push(current.to_string());
+        Some(previous)
+    }
+
+    /// Returns the text as it was before the last undo, if any
+    pub fn redo(&mut self, current: &str) -> Option<String> {
+        let next = self.redo.pop()?;
+        self.undo.push(current.to_string());
+        Some(next)
+    }
+
+    pub fn clear(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_undo_redo() {
+        let mut stack = UndoStack::default();
+        stack.record("");
+        stack.record("a");
+
+        assert_eq!(stack.undo("ab").as_deref(), Some("a"));
+        assert_eq!(stack.undo("a").as_deref(), Some(""));
+        assert_eq!(stack.undo(""), None);
+        assert_eq!(stack.redo("").as_deref(), Some("a"));
+
+        // A new edit forgets what could be redone
+        stack.record("a");
+        assert_eq!(stack.redo("ac"), None);
+        assert_eq!(stack.undo("ac").as_deref(), Some("a"));
+    }
+}
@@ -0,0 +1,175 @@
+use std::{
+    path::{Path, PathBuf},
+    sync::mpsc::{self, Receiver},
+};
+
+use color_eyre::eyre::{eyre, WrapErr};
+use notify::{event::ModifyKind, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+
+/// Watches the directory shown by the app, to notice entries being added or removed.
+/// Only one directory is watched at a time, not recursively.
+pub struct DirWatcher {
+    watcher: RecommendedWatcher,
+    events: Events,
+    dir: Option<PathBuf>,
+}
+
+type Events = Receiver<notify::Result<notify::Event>>;
+
+/// A watcher sending what it sees to the returned receiver
+fn channel_watcher() -> color_eyre::Result<(RecommendedWatcher, Events)> {
+    let (sender, events) = mpsc::channel();
+    let watcher = notify::recommended_watcher(move |event| {
+        // The receiver going away just means the app is exiting
+        let _ = sender.send(event);
+    })
+    .wrap_err("failed to start watching for changes")?;
+    Ok((watcher, events))
+}
+
+/// Takes every event received so far, keeping the ones `is_change` is true for.
+/// Returns whether there was any.
+fn drain(events: &Events, is_change: impl Fn(&notify::Event) -> bool) -> bool {
+    let mut changed = false;
+    while let Ok(event) = events.try_recv() {
+        match event {
+            Ok(event) => changed |= is_change(&event),
+            Err(e) => tracing::warn!("Error while watching for changes: {e}"),
+        }
+    }
+    changed
+}
+
+impl DirWatcher {
+    pub fn new() -> color_eyre::Result<Self> {
+        let (watcher, events) = channel_watcher()?;
+        Ok(Self {
+            watcher,
+            events,
+            dir: None,
+        })
+    }
+
+    /// Watches `dir` instead of the previous directory, if it's a different one
+    pub fn watch(&mut self, dir: Option<&Path>) -> color_eyre::Result<()> {
+        if self.dir.as_deref() == dir {
+            return Ok(());
+        }
+
+        if let Some(previous) = self.dir.take() {
+            // The directory may be gone already
+            let _ = self.watcher.unwatch(&previous);
+        }
+        if let Some(dir) = dir {
+            self.watcher
+                .watch(dir, RecursiveMode::NonRecursive)
+                .wrap_err_with(|| format!("failed to watch {dir:?}"))?;
+            self.dir = Some(dir.to_path_buf());
+        }
+        // Whatever happened before belongs to the previous directory
+        while self.events.try_recv().is_ok() {}
+
+        Ok(())
+    }
+
+    /// Whether entries were created, removed or renamed since the last call
+    pub fn changed(&self) -> bool {
+        drain(&self.events, |event| {
+            matches!(
+                event.kind,
+                EventKind::Create(_)
+                    | EventKind::Remove(_)
+                    | EventKind::Modify(ModifyKind::Name(_))
+            )
+        })
+    }
+}
+
+/// Watches a single file, like the config file. Its directory is watched instead
+/// of the file itself, since editors often save by replacing the file.
+pub struct FileWatcher {
+    _watcher: RecommendedWatcher,
+    events: Events,
+    file: PathBuf,
+}
+
+impl FileWatcher {
+    pub fn new(file: &Path) -> color_eyre::Result<Self> {
+        let dir = file
+            .parent()
+            .ok_or_else(|| eyre!("{file:?} is not in a directory"))?;
+        let (mut watcher, events) = channel_watcher()?;
+        watcher
+            .watch(dir, RecursiveMode::NonRecursive)
+            .wrap_err_with(|| format!("failed to watch {dir:?}"))?;
+
+        Ok(Self {
+            _watcher: watcher,
+            events,
+            file: file.to_path_buf(),
+        })
+    }
+
+    /// Whether the file was written, created or removed since the last call
+    pub fn changed(&self) -> bool {
+        drain(&self.events, |event| {
+            !event.kind.is_access()
+                && event
+                    .paths
+                    .iter()
+                    .any(|path| path.file_name() == self.file.file_name())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use std::{
+        fs, thread,
+        time::{Duration, Instant},
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_notices_new_directories() -> color_eyre::Result<()> {
+        let root = tempfile::tempdir()?;
+        let mut watcher = DirWatcher::new()?;
+        watcher.watch(Some(root.path()))?;
+        assert!(!watcher.changed());
+
+        fs::create_dir(root.path().join("sh040"))?;
+        let start = Instant::now();
+        let mut changed = false;
+        while !changed && start.elapsed() < Duration::from_secs(5) {
+            thread::sleep(Duration::from_millis(10));
+            changed = watcher.changed();
+        }
+        assert!(changed);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_notices_file_writes() -> color_eyre::Result<()> {
+        let root = tempfile::tempdir()?;
+        let file = root.path().join("config.toml");
+        let watcher = FileWatcher::new(&file)?;
+
+        fs::write(root.path().join("other.toml"), "")?;
+        thread::sleep(Duration::from_millis(100));
+        assert!(!watcher.changed());
+
+        fs::write(&file, "[layout]\n")?;
+        let start = Instant::now();
+        let mut changed = false;
+        while !changed && start.elapsed() < Duration::from_secs(5) {
+            thread::sleep(Duration::from_millis(10));
+            changed = watcher.changed();
+        }
+        assert!(changed);
+
+        Ok(())
+    }
+}